@@ -1,8 +1,24 @@
 //! brw - Smart browser routing CLI
 
+mod output;
+
 use clap::{Parser, Subcommand};
 
-use browserware_detect::{Browser, BrowserFamily, detect_browsers, detect_default_browser};
+use browserware_detect::{
+    Browser, BrowserFamily, Packaging, detect_browsers, detect_default_browser,
+};
+use browserware_launch::{DEFAULT_CRASH_WINDOW, LaunchOptions, LaunchPlan, SpawnOptions};
+use browserware_profiles::ProfileExt;
+use browserware_rules::{ProfileHistory, SuggestOptions};
+use browserware_types::{
+    BrowserTarget, Error, LaunchMode, Profile, ProfileSelector, Result, Url, WindowGeometry,
+};
+
+use output::{
+    BrowsersOutput, DetailsOutput, ErrorDetails, ErrorOutput, ExtensionsOutput, ProfileChange,
+    ProfileChangeOutput, ProfileExtensions, ProfilesOutput, SCHEMA_VERSION, SchemaName,
+    SuggestionsOutput,
+};
 
 #[derive(Parser)]
 #[command(name = "brw")]
@@ -36,10 +52,21 @@ enum Commands {
         #[arg(short = 'F', long)]
         family: Option<String>,
     },
-    /// List profiles for a browser
+    /// List or manage profiles for a browser
+    #[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
     Profiles {
-        /// Browser ID or name
-        browser: String,
+        /// Browser ID or name, optionally with packaging and a profile to
+        /// look up (e.g. `chrome@flatpak`, `chrome:Work`)
+        #[arg(required = true)]
+        browser: Option<String>,
+        /// List the extensions installed in each profile
+        #[arg(long)]
+        extensions: bool,
+        /// Report each profile's disk usage, lock state and file health
+        #[arg(long, conflicts_with = "extensions")]
+        details: bool,
+        #[command(subcommand)]
+        action: Option<ProfilesAction>,
     },
     /// Open URL(s) with routing rules
     Open {
@@ -48,21 +75,109 @@ enum Commands {
         /// Override browser selection
         #[arg(short, long)]
         browser: Option<String>,
-        /// Override profile selection
+        /// Override profile selection (name, ID, email, or `@default`,
+        /// `@last-used`, `@last-active`)
         #[arg(short, long)]
         profile: Option<String>,
+        /// Launch mode: new-tab, new-window, private, guest, app or kiosk
+        /// (repeatable)
+        #[arg(short, long = "mode", value_name = "MODE")]
+        modes: Vec<String>,
+        /// Window size and position, as `[WIDTHxHEIGHT][+X+Y]`
+        #[arg(long, value_name = "GEOMETRY")]
+        window: Option<String>,
+        /// Append the browser's output to a file instead of discarding it
+        #[arg(long, value_name = "FILE")]
+        log: Option<std::path::PathBuf>,
+        /// Print the browser command line without starting the browser
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Work with routing rules
+    Rules {
+        #[command(subcommand)]
+        action: RulesAction,
     },
     /// Manage configuration
     Config {
         #[command(subcommand)]
         action: ConfigAction,
     },
+    /// Print the JSON Schema of a configuration or output type
+    Schema {
+        /// Type to describe
+        name: SchemaName,
+    },
     /// Register as default browser
     Register,
     /// Unregister as default browser
     Unregister,
 }
 
+#[derive(Subcommand)]
+enum ProfilesAction {
+    /// Create a profile (the browser must not be running)
+    Create {
+        /// Browser ID or name, optionally with packaging (e.g. `firefox@flatpak`)
+        browser: String,
+        /// Display name of the new profile
+        name: String,
+    },
+    /// Rename a profile (the profile must not be in use)
+    Rename {
+        /// Browser and profile, e.g. `chrome:Work`
+        target: String,
+        /// New display name
+        new_name: String,
+    },
+    /// Delete a profile and its data (the profile must not be in use)
+    Delete {
+        /// Browser and profile, e.g. `chrome:Work`
+        target: String,
+        /// Remove the profile from the browser's list but keep its directory
+        #[arg(long)]
+        keep_files: bool,
+    },
+    /// Back up a profile to a compressed archive (caches are left out)
+    Export {
+        /// Browser and profile, e.g. `chrome:Work`
+        target: String,
+        /// Archive to create, e.g. `work.tar.gz`
+        archive: std::path::PathBuf,
+    },
+    /// Restore a backup as a new profile (the browser must not be running)
+    Restore {
+        /// Browser ID or name, optionally with packaging (e.g. `chrome@flatpak`)
+        browser: String,
+        /// Archive made by `brw profiles export`
+        archive: std::path::PathBuf,
+        /// Display name of the restored profile (the backed up name if omitted)
+        #[arg(long)]
+        name: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum RulesAction {
+    /// Suggest rules from the browsing history of each profile
+    ///
+    /// History databases are copied and read locally; nothing is sent
+    /// anywhere.
+    Suggest {
+        /// Browsers to analyze (all detected browsers if omitted)
+        browsers: Vec<String>,
+        /// Minimum visits to a domain across all profiles
+        #[arg(long, default_value_t = SuggestOptions::default().min_visits)]
+        min_visits: u64,
+        /// Minimum share of a domain's visits made in one profile (0 to 1)
+        #[arg(long, default_value_t = SuggestOptions::default().min_confidence)]
+        min_confidence: f64,
+        /// Write the TOML snippet to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
+    },
+}
+
 #[derive(Subcommand)]
 enum ConfigAction {
     /// Show current configuration
@@ -87,32 +202,79 @@ fn main() {
         )
         .init();
 
+    let format = cli.format;
+    if let Err(err) = run(cli) {
+        report_error(&err, format);
+        std::process::exit(err.exit_code());
+    }
+}
+
+/// Execute the selected command
+fn run(cli: Cli) -> Result<()> {
     match cli.command {
         Commands::Browsers { family } => {
-            cmd_browsers(cli.format, family.as_deref());
-        }
-        Commands::Profiles { browser } => {
-            println!("Profile listing for '{browser}' not yet implemented (Milestone 2)");
+            cmd_browsers(cli.format, family.as_deref())?;
         }
+        Commands::Profiles {
+            browser,
+            extensions,
+            details,
+            action,
+        } => match action {
+            Some(action) => cmd_profiles_change(cli.format, action)?,
+            None => cmd_profiles(
+                cli.format,
+                browser.as_deref().unwrap_or_default(),
+                extensions,
+                details,
+            )?,
+        },
         Commands::Open {
             urls,
             browser,
             profile,
+            modes,
+            window,
+            log,
+            dry_run,
         } => {
-            println!("Opening URLs: {urls:?}");
-            if let Some(b) = browser {
-                println!("  Browser: {b}");
-            }
-            if let Some(p) = profile {
-                println!("  Profile: {p}");
-            }
-            println!("Full routing not yet implemented (Milestone 4)");
+            let options = launch_options(browser.as_deref(), &modes, window.as_deref())?;
+            let spawn = (!dry_run).then(|| {
+                let spawn = SpawnOptions::new().with_crash_window(DEFAULT_CRASH_WINDOW);
+                match log {
+                    Some(log) => spawn.with_log(log),
+                    None => spawn,
+                }
+            });
+            cmd_open(
+                &urls,
+                browser.as_deref(),
+                profile.as_deref(),
+                options,
+                spawn.as_ref(),
+            )?;
         }
+        Commands::Rules { action } => match action {
+            RulesAction::Suggest {
+                browsers,
+                min_visits,
+                min_confidence,
+                output,
+            } => {
+                let options = SuggestOptions::default()
+                    .with_min_visits(min_visits)
+                    .with_min_confidence(min_confidence);
+                cmd_rules_suggest(cli.format, &browsers, &options, output.as_deref())?;
+            }
+        },
         Commands::Config { action } => match action {
             ConfigAction::Show => println!("Config show not yet implemented"),
             ConfigAction::Edit => println!("Config edit not yet implemented"),
             ConfigAction::Check => println!("Config check not yet implemented"),
         },
+        Commands::Schema { name } => {
+            println!("{}", serde_json::to_string_pretty(&name.schema())?);
+        }
         Commands::Register => {
             println!("Register not yet implemented (Milestone 5)");
         }
@@ -120,18 +282,136 @@ fn main() {
             println!("Unregister not yet implemented (Milestone 5)");
         }
     }
+    Ok(())
+}
+
+/// Print an error and its causes to stderr
+fn report_error(err: &Error, format: OutputFormat) {
+    if matches!(format, OutputFormat::Json) {
+        let output = ErrorOutput {
+            schema_version: SCHEMA_VERSION,
+            error: ErrorDetails {
+                code: err.code(),
+                message: err.to_string(),
+                causes: causes(err).collect(),
+                exit_code: err.exit_code(),
+            },
+        };
+        if let Ok(json) = serde_json::to_string_pretty(&output) {
+            eprintln!("{json}");
+            return;
+        }
+    }
+
+    eprintln!("error[{}]: {err}", err.code());
+    for cause in causes(err) {
+        eprintln!("  caused by: {cause}");
+    }
+}
+
+/// Iterate over the messages of an error's source chain
+fn causes(err: &Error) -> impl Iterator<Item = String> + '_ {
+    std::iter::successors(std::error::Error::source(err), |e| e.source()).map(ToString::to_string)
+}
+
+/// Combine the target's mode with `--mode` and `--window`.
+fn launch_options(
+    browser_arg: Option<&str>,
+    modes: &[String],
+    window: Option<&str>,
+) -> Result<LaunchOptions> {
+    let target_mode = browser_arg
+        .map(str::parse::<BrowserTarget>)
+        .transpose()?
+        .and_then(|t| t.mode);
+    let mut options = LaunchOptions::new();
+    if let Some(mode) = target_mode {
+        options = options.with_mode(mode);
+    }
+    for mode in modes {
+        options = options.with_mode(mode.parse::<LaunchMode>()?);
+    }
+    if let Some(window) = window {
+        options = options.with_window(window.parse::<WindowGeometry>()?);
+    }
+    Ok(options)
+}
+
+/// Execute the open command (`spawn` is `None` for a dry run)
+fn cmd_open(
+    urls: &[String],
+    browser_arg: Option<&str>,
+    profile_arg: Option<&str>,
+    options: LaunchOptions,
+    spawn: Option<&SpawnOptions>,
+) -> Result<()> {
+    if let Some(p) = profile_arg {
+        ProfileSelector::from_query(p)?;
+    }
+    let target = browser_arg.map(str::parse::<BrowserTarget>).transpose()?;
+    println!("Opening URLs: {urls:?}");
+
+    let profile = profile_arg.or_else(|| target.as_ref().and_then(|t| t.profile.as_deref()));
+    let Some(b) = browser_arg else {
+        if let Some(p) = profile {
+            println!("  Profile: {p}");
+        }
+        println!("Full routing not yet implemented (Milestone 4)");
+        return Ok(());
+    };
+    println!("  Browser: {b}");
+
+    let browser = resolve_browser(b)?;
+    let mut plan = LaunchPlan::builder(&browser).with_options(options);
+    let mut open = urls.to_vec();
+    if let Some(p) = profile {
+        let resolved = browserware_profiles::find_profile(&browser, p)?;
+        println!("  Profile: {} ({})", resolved.name, resolved.id);
+        if let Some(container) = target.as_ref().and_then(|t| t.container.as_deref()) {
+            println!("  Container: {container}");
+            open = urls
+                .iter()
+                .map(|url| browserware_profiles::container_url(&resolved, container, url))
+                .collect::<std::result::Result<_, _>>()?;
+            for url in &open {
+                println!("  Open: {url}");
+            }
+        }
+        plan = plan.with_profile(&resolved);
+    }
+    let open = open
+        .iter()
+        .map(|url| Url::parse(url))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    let plan = plan.with_urls(open).build()?;
+    println!("  Command: {}", command_line(&plan));
+    if let Some(spawn) = spawn {
+        let handle = browserware_launch::spawn(&plan, spawn)?;
+        println!("  Started {} (pid {})", browser.name, handle.pid());
+    }
+    Ok(())
+}
+
+/// Format a launch plan as a shell command line.
+fn command_line(plan: &LaunchPlan) -> String {
+    std::iter::once(plan.program.to_string_lossy().into_owned())
+        .chain(plan.args.iter().cloned())
+        .map(|arg| {
+            let is_safe = |c: char| c.is_ascii_alphanumeric() || "-_./:=,+@%".contains(c);
+            if !arg.is_empty() && arg.chars().all(is_safe) {
+                arg
+            } else {
+                format!("'{}'", arg.replace('\'', r"'\''"))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
 }
 
 /// Execute the browsers command
-fn cmd_browsers(format: OutputFormat, family_filter: Option<&str>) {
+fn cmd_browsers(format: OutputFormat, family_filter: Option<&str>) -> Result<()> {
     // Parse family filter if provided
-    let family = family_filter.map(|filter| {
-        let Some(f) = parse_browser_family(filter) else {
-            // Error message already printed by parse_browser_family
-            std::process::exit(1);
-        };
-        f
-    });
+    let family = family_filter.map(parse_browser_family).transpose()?;
 
     // Get the default browser for marking
     let default_browser = detect_default_browser();
@@ -145,25 +425,442 @@ fn cmd_browsers(format: OutputFormat, family_filter: Option<&str>) {
     // Output based on format
     match format {
         OutputFormat::Table => print_browsers_table(&browsers, default_id),
-        OutputFormat::Json => print_browsers_json(&browsers, default_id),
+        OutputFormat::Json => print_browsers_json(&browsers, default_id)?,
         OutputFormat::Plain => print_browsers_plain(&browsers, default_id),
     }
+    Ok(())
+}
+
+/// Execute the profiles command
+fn cmd_profiles(
+    format: OutputFormat,
+    browser_arg: &str,
+    extensions: bool,
+    details: bool,
+) -> Result<()> {
+    let browser = resolve_browser(browser_arg)?;
+    let target: BrowserTarget = browser_arg.parse()?;
+    let profiles = match target.profile {
+        Some(query) => vec![browserware_profiles::find_profile(&browser, &query)?],
+        None => browserware_profiles::list_profiles(&browser)?,
+    };
+    if extensions {
+        return print_extensions(format, &browser, &profiles);
+    }
+    if details {
+        return print_details(format, &browser, &profiles);
+    }
+
+    match format {
+        OutputFormat::Table => print_profiles_table(&browser, &profiles),
+        OutputFormat::Json => {
+            let output = ProfilesOutput {
+                schema_version: SCHEMA_VERSION,
+                browser: &browser.id.0,
+                profiles: &profiles,
+                count: profiles.len(),
+            };
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        }
+        OutputFormat::Plain => {
+            for profile in &profiles {
+                println!("{}", profile.id);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Print the extensions of each profile
+fn print_extensions(format: OutputFormat, browser: &Browser, profiles: &[Profile]) -> Result<()> {
+    let entries = profiles
+        .iter()
+        .map(|profile| {
+            Ok(ProfileExtensions {
+                profile: &profile.id.0,
+                name: &profile.name,
+                extensions: profile.extensions()?,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    match format {
+        OutputFormat::Table => {
+            for (index, entry) in entries.iter().enumerate() {
+                if index > 0 {
+                    println!();
+                }
+                println!("{} ({})", entry.name, entry.profile);
+                if entry.extensions.is_empty() {
+                    println!("  No extensions.");
+                }
+                for extension in &entry.extensions {
+                    println!(
+                        "  {} {} {} [{}{}] {}",
+                        if extension.enabled { "*" } else { "-" },
+                        extension.name,
+                        extension.version.as_deref().unwrap_or("-"),
+                        extension.source,
+                        if extension.enabled { "" } else { ", disabled" },
+                        extension.id,
+                    );
+                }
+            }
+        }
+        OutputFormat::Json => {
+            let output = ExtensionsOutput {
+                schema_version: SCHEMA_VERSION,
+                browser: &browser.id.0,
+                count: entries.len(),
+                profiles: entries,
+            };
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        }
+        OutputFormat::Plain => {
+            for entry in &entries {
+                for extension in &entry.extensions {
+                    println!("{}\t{}", entry.profile, extension.id);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Print the disk usage and health of each profile
+fn print_details(format: OutputFormat, browser: &Browser, profiles: &[Profile]) -> Result<()> {
+    let reports: Vec<_> = profiles.iter().map(ProfileExt::inspect).collect();
+
+    match format {
+        OutputFormat::Table => {
+            for (index, report) in reports.iter().enumerate() {
+                if index > 0 {
+                    println!();
+                }
+                let size = report.size;
+                println!(
+                    "{} ({})  [{}]",
+                    report.profile.name, report.profile.id, report.verdict
+                );
+                println!(
+                    "  Size:      {} (cache {}, data {})",
+                    format_size(size.total),
+                    format_size(size.cache),
+                    format_size(size.data)
+                );
+                println!(
+                    "  Last used: {}",
+                    report.last_used.map_or_else(|| "-".to_string(), format_age)
+                );
+                println!("  Lock:      {}", report.lock);
+                let checks: Vec<String> = report
+                    .checks
+                    .iter()
+                    .map(|check| format!("{} {}", check.file, check.status))
+                    .collect();
+                println!("  Files:     {}", checks.join(", "));
+                for problem in &report.problems {
+                    println!("  ! {problem}");
+                }
+            }
+        }
+        OutputFormat::Json => {
+            let output = DetailsOutput {
+                schema_version: SCHEMA_VERSION,
+                browser: &browser.id.0,
+                profiles: &reports,
+                count: reports.len(),
+            };
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        }
+        OutputFormat::Plain => {
+            for report in &reports {
+                println!(
+                    "{}\t{}\t{}",
+                    report.profile.id, report.verdict, report.size.total
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Format a byte count with binary units (e.g. `1.5 GiB`)
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    #[allow(clippy::cast_precision_loss)]
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+/// Format a Unix timestamp as the time elapsed since (e.g. `3 days ago`)
+fn format_age(timestamp: u64) -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    let elapsed = now.saturating_sub(timestamp);
+    let (count, unit) = match elapsed {
+        0..60 => return "just now".to_string(),
+        60..3600 => (elapsed / 60, "minute"),
+        3600..86_400 => (elapsed / 3600, "hour"),
+        _ => (elapsed / 86_400, "day"),
+    };
+    format!("{count} {unit}{} ago", if count == 1 { "" } else { "s" })
+}
+
+/// Execute a profiles subcommand that changes a browser's profiles
+fn cmd_profiles_change(format: OutputFormat, action: ProfilesAction) -> Result<()> {
+    let (browser, change, profile) = match action {
+        ProfilesAction::Create { browser, name } => {
+            let browser = resolve_browser(&browser)?;
+            let profile = browserware_profiles::create_profile(&browser, &name)?;
+            (browser, ProfileChange::Created, profile)
+        }
+        ProfilesAction::Rename { target, new_name } => {
+            let (browser, query) = resolve_profile_target(&target)?;
+            let profile = browserware_profiles::rename_profile(&browser, &query, &new_name)?;
+            (browser, ProfileChange::Renamed, profile)
+        }
+        ProfilesAction::Delete { target, keep_files } => {
+            let (browser, query) = resolve_profile_target(&target)?;
+            let profile = browserware_profiles::delete_profile(&browser, &query, keep_files)?;
+            (browser, ProfileChange::Deleted, profile)
+        }
+        ProfilesAction::Export { target, archive } => {
+            let (browser, query) = resolve_profile_target(&target)?;
+            let manifest = browserware_profiles::export_profile(&browser, &query, &archive)?;
+            (browser, ProfileChange::Exported, manifest.profile)
+        }
+        ProfilesAction::Restore {
+            browser,
+            archive,
+            name,
+        } => {
+            let browser = resolve_browser(&browser)?;
+            let profile =
+                browserware_profiles::restore_profile(&browser, &archive, name.as_deref())?;
+            (browser, ProfileChange::Restored, profile)
+        }
+    };
+
+    match format {
+        OutputFormat::Table => println!(
+            "{} profile '{}' ({}) for {}",
+            change.label(),
+            profile.name,
+            profile.id,
+            browser.name
+        ),
+        OutputFormat::Json => {
+            let output = ProfileChangeOutput {
+                schema_version: SCHEMA_VERSION,
+                browser: &browser.id.0,
+                change,
+                profile: &profile,
+            };
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        }
+        OutputFormat::Plain => println!("{}", profile.id),
+    }
+    Ok(())
+}
+
+/// Execute the rules suggest command
+fn cmd_rules_suggest(
+    format: OutputFormat,
+    browser_args: &[String],
+    options: &SuggestOptions,
+    output: Option<&std::path::Path>,
+) -> Result<()> {
+    if !(0.0..=1.0).contains(&options.min_confidence) {
+        return Err(Error::InvalidArgument {
+            argument: "min-confidence".to_string(),
+            message: format!("{} is not between 0 and 1", options.min_confidence),
+        });
+    }
+    if matches!(format, OutputFormat::Json) && output.is_some() {
+        return Err(Error::InvalidArgument {
+            argument: "output".to_string(),
+            message: "--output writes TOML and cannot be combined with --format json".to_string(),
+        });
+    }
+    let browsers = if browser_args.is_empty() {
+        detect_browsers()
+    } else {
+        browser_args
+            .iter()
+            .map(|arg| resolve_browser(arg))
+            .collect::<Result<_>>()?
+    };
+
+    let mut histories = Vec::new();
+    for browser in &browsers {
+        let profiles = match browserware_profiles::list_profiles(browser) {
+            Ok(profiles) => profiles,
+            Err(err) => {
+                tracing::warn!(browser = %browser.id, "Skipping browser: {err}");
+                continue;
+            }
+        };
+        for profile in profiles {
+            match profile.host_visits() {
+                Ok(visits) => histories.push(ProfileHistory::new(
+                    BrowserTarget::for_profile(browser, &profile),
+                    visits.into_iter().map(|v| (v.host, v.visits)),
+                )),
+                Err(err) => {
+                    tracing::warn!(browser = %browser.id, profile = %profile.id, "Skipping profile: {err}");
+                }
+            }
+        }
+    }
+    let suggestions = browserware_rules::suggest_rules(&histories, options);
+
+    if matches!(format, OutputFormat::Json) {
+        let output = SuggestionsOutput {
+            schema_version: SCHEMA_VERSION,
+            profiles: histories.len(),
+            count: suggestions.len(),
+            suggestions: &suggestions,
+        };
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    let toml = browserware_rules::suggestions_toml(&suggestions);
+    match output {
+        Some(path) => {
+            std::fs::write(path, toml).map_err(|err| Error::io_at(path, err))?;
+            println!(
+                "Wrote {} suggestion(s) from {} profile(s) to {}",
+                suggestions.len(),
+                histories.len(),
+                path.display()
+            );
+        }
+        None => print!("{toml}"),
+    }
+    Ok(())
+}
+
+/// Split a `browser:profile` target into the browser and the profile query
+fn resolve_profile_target(arg: &str) -> Result<(Browser, String)> {
+    let target: BrowserTarget = arg.parse()?;
+    let query = target.profile.ok_or_else(|| Error::InvalidTarget {
+        input: arg.to_string(),
+        reason: "expected browser:profile (e.g. chrome:Work)".to_string(),
+    })?;
+    Ok((resolve_browser(arg)?, query))
+}
+
+/// Find a browser by ID or name, optionally qualified with `@packaging`
+///
+/// Detected installs are preferred. Browsers known to the registry are
+/// accepted even when detection does not report them, so their data can
+/// still be inspected.
+fn resolve_browser(arg: &str) -> Result<Browser> {
+    let target: BrowserTarget = arg.parse()?;
+    let wanted = &target.browser.0;
+    let matches = |b: &Browser| {
+        (b.id.0 == *wanted || b.name.eq_ignore_ascii_case(wanted))
+            && target.packaging.is_none_or(|p| b.packaging == p)
+    };
+    if let Some(browser) = detect_browsers().into_iter().find(matches) {
+        return Ok(browser);
+    }
+
+    let meta =
+        browserware_detect::registry::find_by_id(wanted).ok_or_else(|| Error::BrowserNotFound {
+            browser: arg.to_string(),
+        })?;
+    tracing::debug!(
+        browser = meta.id,
+        "Browser not detected, using registry entry"
+    );
+    let packaging = target.packaging.unwrap_or_default();
+    // Sandboxed installs are run through their exported launcher
+    let executable = match packaging {
+        Packaging::Flatpak => meta
+            .flatpak_id
+            .map(browserware_detect::flatpak_exported_binary),
+        Packaging::Snap => meta
+            .snap_name
+            .map(|name| std::path::PathBuf::from("/snap/bin").join(name)),
+        Packaging::Native => None,
+    }
+    .unwrap_or_else(|| {
+        meta.linux_executables
+            .first()
+            .copied()
+            .unwrap_or(meta.id)
+            .into()
+    });
+    Ok(Browser::new(meta.id, meta.name, executable)
+        .with_variant(meta.variant)
+        .with_packaging(packaging))
+}
+
+/// Print profiles in table format
+fn print_profiles_table(browser: &Browser, profiles: &[Profile]) {
+    if profiles.is_empty() {
+        println!("No profiles found for {}.", browser.name);
+        return;
+    }
+
+    // Add 2 to ID for "* " prefix on default
+    let id_width = profiles
+        .iter()
+        .map(|p| p.id.0.len())
+        .max()
+        .unwrap_or(2)
+        .max(2)
+        + 2;
+    let name_width = profiles
+        .iter()
+        .map(|p| p.name.len())
+        .max()
+        .unwrap_or(4)
+        .max(4);
+
+    println!("{:id_width$}  {:name_width$}  EMAIL", "ID", "NAME");
+    println!("{:-<id_width$}  {:-<name_width$}  {:-<5}", "", "", "");
+    for profile in profiles {
+        let marker = if profile.is_default { "* " } else { "  " };
+        println!(
+            "{:id_width$}  {:name_width$}  {}",
+            format!("{marker}{}", profile.id),
+            profile.name,
+            profile.email.as_deref().unwrap_or("-"),
+        );
+    }
+
+    println!();
+    println!("{} profile(s) for {}", profiles.len(), browser.name);
 }
 
 /// Parse a browser family from string
-fn parse_browser_family(s: &str) -> Option<BrowserFamily> {
+fn parse_browser_family(s: &str) -> Result<BrowserFamily> {
     let family = match s.to_lowercase().as_str() {
         "chromium" | "chrome" => BrowserFamily::Chromium,
         "firefox" | "gecko" => BrowserFamily::Firefox,
         "webkit" | "safari" => BrowserFamily::WebKit,
         "other" => BrowserFamily::Other,
         _ => {
-            eprintln!("Unknown browser family: {s}");
-            eprintln!("Valid families: chromium, firefox, webkit, other");
-            return None;
+            return Err(Error::InvalidArgument {
+                argument: "browser family".to_string(),
+                message: format!("unknown family '{s}' (valid: chromium, firefox, webkit, other)"),
+            });
         }
     };
-    Some(family)
+    Ok(family)
 }
 
 /// Print browsers in table format
@@ -248,24 +945,16 @@ fn print_browsers_table(browsers: &[Browser], default_id: Option<&str>) {
 }
 
 /// Print browsers in JSON format
-fn print_browsers_json(browsers: &[Browser], default_id: Option<&str>) {
-    #[derive(serde::Serialize)]
-    struct BrowserOutput<'a> {
-        browsers: &'a [Browser],
-        default: Option<&'a str>,
-        count: usize,
-    }
-
-    let output = BrowserOutput {
+fn print_browsers_json(browsers: &[Browser], default_id: Option<&str>) -> Result<()> {
+    let output = BrowsersOutput {
+        schema_version: SCHEMA_VERSION,
         browsers,
         default: default_id,
         count: browsers.len(),
     };
 
-    match serde_json::to_string_pretty(&output) {
-        Ok(json) => println!("{json}"),
-        Err(e) => eprintln!("Error serializing to JSON: {e}"),
-    }
+    println!("{}", serde_json::to_string_pretty(&output)?);
+    Ok(())
 }
 
 /// Print browsers in plain format (one per line)
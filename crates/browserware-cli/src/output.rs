@@ -0,0 +1,234 @@
+//! JSON output envelopes and their schemas.
+//!
+//! Every JSON document `brw` prints carries a `schema_version`. It is bumped
+//! whenever a field is removed, renamed or changes meaning; adding fields is
+//! not a breaking change.
+
+// Allow pub items: this is a binary crate, they are public to `main` only.
+#![allow(unreachable_pub)]
+
+use browserware_detect::Browser;
+use browserware_profiles::{Extension, ProfileReport};
+use browserware_rules::Suggestion;
+use browserware_types::{ErrorCode, Profile};
+use schemars::{JsonSchema, Schema, generate::SchemaSettings};
+use serde::Serialize;
+
+/// Version of the JSON output format.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Output of `brw browsers --format json`.
+#[derive(Serialize, JsonSchema)]
+pub struct BrowsersOutput<'a> {
+    /// Version of the output format
+    pub schema_version: u32,
+    /// Detected browsers
+    pub browsers: &'a [Browser],
+    /// ID of the system default browser (if known)
+    pub default: Option<&'a str>,
+    /// Number of detected browsers
+    pub count: usize,
+}
+
+/// Output of `brw profiles <browser> --format json`.
+#[derive(Serialize, JsonSchema)]
+pub struct ProfilesOutput<'a> {
+    /// Version of the output format
+    pub schema_version: u32,
+    /// ID of the browser the profiles belong to
+    pub browser: &'a str,
+    /// Profiles, in the browser's display order
+    pub profiles: &'a [Profile],
+    /// Number of profiles
+    pub count: usize,
+}
+
+/// Output of `brw profiles <browser> --extensions --format json`.
+#[derive(Serialize, JsonSchema)]
+pub struct ExtensionsOutput<'a> {
+    /// Version of the output format
+    pub schema_version: u32,
+    /// ID of the browser the profiles belong to
+    pub browser: &'a str,
+    /// Profiles and their extensions, in the browser's display order
+    pub profiles: Vec<ProfileExtensions<'a>>,
+    /// Number of profiles
+    pub count: usize,
+}
+
+/// The extensions of one profile.
+#[derive(Serialize, JsonSchema)]
+pub struct ProfileExtensions<'a> {
+    /// Profile ID
+    pub profile: &'a str,
+    /// Profile display name
+    pub name: &'a str,
+    /// Installed extensions, ordered by ID
+    pub extensions: Vec<Extension>,
+}
+
+/// Output of `brw profiles <browser> --details --format json`.
+#[derive(Serialize, JsonSchema)]
+pub struct DetailsOutput<'a> {
+    /// Version of the output format
+    pub schema_version: u32,
+    /// ID of the browser the profiles belong to
+    pub browser: &'a str,
+    /// Disk usage and health of each profile, in the browser's display order
+    pub profiles: &'a [ProfileReport],
+    /// Number of profiles
+    pub count: usize,
+}
+
+/// Output of `brw profiles create|rename|delete|export|restore --format json`.
+#[derive(Serialize, JsonSchema)]
+pub struct ProfileChangeOutput<'a> {
+    /// Version of the output format
+    pub schema_version: u32,
+    /// ID of the browser the profile belongs to
+    pub browser: &'a str,
+    /// What happened to the profile
+    pub change: ProfileChange,
+    /// The profile after the change (before it, for deletions)
+    pub profile: &'a Profile,
+}
+
+/// A change made to a profile.
+#[derive(Clone, Copy, Serialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProfileChange {
+    /// The profile was created
+    Created,
+    /// The profile was renamed
+    Renamed,
+    /// The profile was deleted
+    Deleted,
+    /// The profile was backed up
+    Exported,
+    /// The profile was restored from a backup
+    Restored,
+}
+
+impl ProfileChange {
+    /// Past-tense verb for messages.
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Created => "Created",
+            Self::Renamed => "Renamed",
+            Self::Deleted => "Deleted",
+            Self::Exported => "Exported",
+            Self::Restored => "Restored",
+        }
+    }
+}
+
+/// Output of `brw rules suggest --format json`.
+#[derive(Serialize, JsonSchema)]
+pub struct SuggestionsOutput<'a> {
+    /// Version of the output format
+    pub schema_version: u32,
+    /// Suggested domains, grouped by profile and ordered by visits
+    pub suggestions: &'a [Suggestion],
+    /// Number of profiles whose history was analyzed
+    pub profiles: usize,
+    /// Number of suggestions
+    pub count: usize,
+}
+
+/// Error report printed to stderr with `--format json`.
+#[derive(Serialize, JsonSchema)]
+pub struct ErrorOutput {
+    /// Version of the output format
+    pub schema_version: u32,
+    /// The error
+    pub error: ErrorDetails,
+}
+
+/// Details of a failed command.
+#[derive(Serialize, JsonSchema)]
+pub struct ErrorDetails {
+    /// Stable error code
+    pub code: ErrorCode,
+    /// Human-readable message
+    pub message: String,
+    /// Messages of the underlying causes, outermost first
+    pub causes: Vec<String>,
+    /// Process exit code
+    pub exit_code: i32,
+}
+
+/// Types `brw schema` can print.
+#[derive(Clone, Copy, clap::ValueEnum)]
+pub enum SchemaName {
+    /// An installed browser
+    Browser,
+    /// A browser engine family and release channel
+    BrowserVariant,
+    /// A browser profile
+    Profile,
+    /// A `browser[@packaging][:profile[/container]][#mode]` target string
+    BrowserTarget,
+    /// The routing rule configuration file
+    Config,
+    /// Output of `brw browsers --format json`
+    BrowsersOutput,
+    /// Output of `brw profiles <browser> --format json`
+    ProfilesOutput,
+    /// Output of `brw profiles <browser> --extensions --format json`
+    ExtensionsOutput,
+    /// Output of `brw profiles <browser> --details --format json`
+    DetailsOutput,
+    /// Output of `brw profiles create|rename|delete|export|restore --format json`
+    ProfileChangeOutput,
+    /// Output of `brw rules suggest --format json`
+    SuggestionsOutput,
+    /// Error report printed with `--format json`
+    ErrorOutput,
+}
+
+impl SchemaName {
+    /// Generate the JSON Schema for this type.
+    pub fn schema(self) -> Schema {
+        // Output types are described as they are written, configuration as it
+        // is read (defaults make fields optional).
+        let output = SchemaSettings::draft2020_12().for_serialize();
+        let input = SchemaSettings::draft2020_12().for_deserialize();
+
+        match self {
+            Self::Browser => output.into_generator().into_root_schema_for::<Browser>(),
+            Self::BrowserVariant => output
+                .into_generator()
+                .into_root_schema_for::<browserware_types::BrowserVariant>(),
+            Self::Profile => output
+                .into_generator()
+                .into_root_schema_for::<browserware_types::Profile>(),
+            Self::BrowserTarget => input
+                .into_generator()
+                .into_root_schema_for::<browserware_types::BrowserTarget>(),
+            Self::Config => input
+                .into_generator()
+                .into_root_schema_for::<browserware_rules::RulesConfig>(),
+            Self::BrowsersOutput => output
+                .into_generator()
+                .into_root_schema_for::<BrowsersOutput<'_>>(),
+            Self::ProfilesOutput => output
+                .into_generator()
+                .into_root_schema_for::<ProfilesOutput<'_>>(),
+            Self::ExtensionsOutput => output
+                .into_generator()
+                .into_root_schema_for::<ExtensionsOutput<'_>>(),
+            Self::DetailsOutput => output
+                .into_generator()
+                .into_root_schema_for::<DetailsOutput<'_>>(),
+            Self::ProfileChangeOutput => output
+                .into_generator()
+                .into_root_schema_for::<ProfileChangeOutput<'_>>(),
+            Self::SuggestionsOutput => output
+                .into_generator()
+                .into_root_schema_for::<SuggestionsOutput<'_>>(),
+            Self::ErrorOutput => output
+                .into_generator()
+                .into_root_schema_for::<ErrorOutput>(),
+        }
+    }
+}
@@ -38,3 +38,542 @@ fn open_subcommand_exists() {
         .assert()
         .success();
 }
+
+#[test]
+fn open_plans_launch_modes() {
+    let home = tempfile::tempdir().unwrap();
+    let open = |args: &[&str]| {
+        brw()
+            .args(["open", "--dry-run"])
+            .args(args)
+            .arg("https://example.com/")
+            .env("HOME", home.path())
+            .assert()
+    };
+
+    open(&["-b", "chrome", "-m", "private", "--window", "800x600+0+0"])
+        .success()
+        .stdout(predicate::str::contains(
+            "--incognito --window-size=800,600 --window-position=0,0 -- https://example.com/",
+        ));
+    open(&["-b", "firefox#new-window"])
+        .success()
+        .stdout(predicate::str::contains("-new-window https://example.com/"));
+    open(&["-b", "firefox", "-m", "app"])
+        .code(64)
+        .stderr(predicate::str::contains(
+            "firefox does not support app mode",
+        ));
+    open(&["-b", "chrome", "-m", "private", "-m", "guest"])
+        .code(64)
+        .stderr(predicate::str::contains("private and guest modes"));
+    open(&["-b", "chrome", "--window", "wide"]).code(64);
+}
+
+#[test]
+fn open_runs_sandboxed_browsers_through_their_wrapper() {
+    let home = tempfile::tempdir().unwrap();
+    brw()
+        .args(["open", "--dry-run", "-b", "firefox@flatpak"])
+        .arg("file:///nonexistent/page.html")
+        .env("HOME", home.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "flatpak run org.mozilla.firefox file:///nonexistent/page.html",
+        ))
+        .stderr(predicate::str::contains(
+            "is not reachable inside the Flatpak sandbox of org.mozilla.firefox",
+        ));
+}
+
+#[test]
+#[cfg(unix)]
+fn open_reports_browser_start_failures() {
+    let dir = tempfile::tempdir().unwrap();
+    let log = dir.path().join("browser.log");
+    // A Chromium-family browser from the registry that is not installed here
+    brw()
+        .args(["open", "--browser", "vivaldi", "--log"])
+        .arg(&log)
+        .arg("https://example.com/")
+        .env("HOME", dir.path())
+        .env("PATH", dir.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("failed to launch vivaldi"));
+}
+
+#[test]
+fn unknown_family_is_a_usage_error() {
+    brw()
+        .args(["browsers", "--family", "netscape"])
+        .assert()
+        .code(64)
+        .stderr(predicate::str::contains("error[invalid_argument]"));
+}
+
+#[test]
+fn errors_are_json_with_json_format() {
+    brw()
+        .args(["--format", "json", "browsers", "--family", "netscape"])
+        .assert()
+        .code(64)
+        .stderr(predicate::str::contains(r#""code": "invalid_argument""#));
+}
+
+#[test]
+fn browsers_json_has_schema_version() {
+    brw()
+        .args(["--format", "json", "browsers"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""schema_version": 1"#));
+}
+
+#[test]
+fn schema_prints_json_schemas() {
+    for name in [
+        "browser",
+        "browser-variant",
+        "profile",
+        "browser-target",
+        "config",
+        "browsers-output",
+        "profiles-output",
+        "details-output",
+        "suggestions-output",
+        "error-output",
+    ] {
+        let output = brw().args(["schema", name]).output().unwrap();
+        assert!(output.status.success(), "schema {name} failed");
+        let schema: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert!(
+            schema.get("$schema").is_some(),
+            "schema {name} has no $schema"
+        );
+    }
+}
+
+#[test]
+fn config_schema_describes_rules() {
+    brw()
+        .args(["schema", "config"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""rules""#))
+        .stdout(predicate::str::contains(r#""BrowserTarget""#));
+}
+
+#[test]
+fn profiles_of_unknown_browser_is_not_found() {
+    brw()
+        .args(["profiles", "netscape-navigator"])
+        .assert()
+        .code(69)
+        .stderr(predicate::str::contains("error[browser_not_found]"));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn profiles_lists_chromium_local_state() {
+    let home = tempfile::tempdir().unwrap();
+    let chrome = home.path().join(".config/google-chrome");
+    std::fs::create_dir_all(&chrome).unwrap();
+    std::fs::write(
+        chrome.join("Local State"),
+        r#"{"profile": {"info_cache": {
+            "Default": {"name": "Personal"},
+            "Profile 2": {"name": "Work", "user_name": "me@corp.example.com"}
+        }, "last_used": "Profile 2"}}"#,
+    )
+    .unwrap();
+
+    let output = brw()
+        .args(["--format", "json", "profiles", "chrome"])
+        .env("HOME", home.path())
+        .env_remove("XDG_CONFIG_HOME")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["schema_version"], 1);
+    assert_eq!(json["browser"], "chrome");
+    assert_eq!(json["count"], 2);
+    assert_eq!(json["profiles"][1]["email"], "me@corp.example.com");
+    assert_eq!(json["profiles"][1]["is_default"], true);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn profiles_lists_firefox_profiles_ini() {
+    let home = tempfile::tempdir().unwrap();
+    let firefox = home.path().join(".mozilla/firefox");
+    std::fs::create_dir_all(&firefox).unwrap();
+    std::fs::write(
+        firefox.join("profiles.ini"),
+        "[Profile0]\nName=default-release\nIsRelative=1\nPath=abc.default-release\n\n\
+         [Profile1]\nName=dev\nIsRelative=1\nPath=def.dev\nDefault=1\n",
+    )
+    .unwrap();
+
+    brw()
+        .args(["--format", "plain", "profiles", "firefox"])
+        .env("HOME", home.path())
+        .assert()
+        .success()
+        .stdout("abc.default-release\ndef.dev\n");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn profiles_lists_gnome_web_apps() {
+    let home = tempfile::tempdir().unwrap();
+    let data = home.path().join(".local/share");
+    std::fs::create_dir_all(data.join("epiphany")).unwrap();
+    let app = data.join("org.gnome.Epiphany.WebApp_f00d");
+    std::fs::create_dir_all(&app).unwrap();
+    std::fs::write(
+        app.join("org.gnome.Epiphany.WebApp_f00d.desktop"),
+        "[Desktop Entry]\nName=Mail\nExec=epiphany --application-mode https://mail.example.com/\n",
+    )
+    .unwrap();
+
+    let output = brw()
+        .args(["--format", "json", "profiles", "gnome-web"])
+        .env("HOME", home.path())
+        .env_remove("XDG_DATA_HOME")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["count"], 2);
+    assert_eq!(json["profiles"][0]["id"], "default");
+    assert_eq!(json["profiles"][1]["name"], "Mail");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn profiles_finds_profile_by_name_or_prefix() {
+    let home = tempfile::tempdir().unwrap();
+    let firefox = home.path().join(".mozilla/firefox");
+    std::fs::create_dir_all(&firefox).unwrap();
+    std::fs::write(
+        firefox.join("profiles.ini"),
+        "[Profile0]\nName=Work\nIsRelative=1\nPath=abc.work\n\n\
+         [Profile1]\nName=Workshop\nIsRelative=1\nPath=def.workshop\n",
+    )
+    .unwrap();
+
+    brw()
+        .args(["--format", "plain", "profiles", "firefox:work"])
+        .env("HOME", home.path())
+        .assert()
+        .success()
+        .stdout("abc.work\n");
+    brw()
+        .args(["profiles", "firefox:wor"])
+        .env("HOME", home.path())
+        .assert()
+        .code(64)
+        .stderr(predicate::str::contains("abc.work, def.workshop"));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn profile_selectors_resolve_and_validate() {
+    let home = tempfile::tempdir().unwrap();
+    let firefox = home.path().join(".mozilla/firefox");
+    std::fs::create_dir_all(&firefox).unwrap();
+    std::fs::write(
+        firefox.join("profiles.ini"),
+        "[Profile0]\nName=Work\nIsRelative=1\nPath=abc.work\n\n\
+         [Profile1]\nName=Home\nIsRelative=1\nPath=def.home\nDefault=1\n",
+    )
+    .unwrap();
+
+    brw()
+        .args(["--format", "plain", "profiles", "firefox:@default"])
+        .env("HOME", home.path())
+        .assert()
+        .success()
+        .stdout("def.home\n");
+    brw()
+        .args(["open", "--profile", "@newest", "https://example.com"])
+        .assert()
+        .code(64)
+        .stderr(predicate::str::contains("unknown profile selector"));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn profiles_create_rename_and_delete() {
+    let home = tempfile::tempdir().unwrap();
+    let run = |args: &[&str]| {
+        brw()
+            .args(["--format", "plain", "profiles"])
+            .args(args)
+            .env("HOME", home.path())
+            .assert()
+    };
+
+    let output = run(&["create", "firefox", "Work"]).success();
+    let id = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+    let id = id.trim();
+    assert!(id.ends_with(".Work"), "{id}");
+    assert!(home.path().join(".mozilla/firefox").join(id).is_dir());
+
+    run(&["create", "firefox", "work"]).code(64);
+    run(&["rename", "firefox:Work", "Office"]).success();
+    run(&["firefox:office"]).success().stdout(format!("{id}\n"));
+    run(&["delete", "firefox:Office"]).success();
+    run(&["firefox"]).success().stdout("");
+    assert!(!home.path().join(".mozilla/firefox").join(id).exists());
+
+    run(&["rename", "firefox", "Office"])
+        .code(64)
+        .stderr(predicate::str::contains("expected browser:profile"));
+}
+
+#[test]
+fn profiles_details_report_health() {
+    let home = tempfile::tempdir().unwrap();
+    let firefox = home.path().join(".mozilla/firefox");
+    for dir in ["abc.ok", "def.broken"] {
+        std::fs::create_dir_all(firefox.join(dir)).unwrap();
+    }
+    std::fs::write(
+        firefox.join("profiles.ini"),
+        "[Profile0]\nName=ok\nIsRelative=1\nPath=abc.ok\n\n\
+         [Profile1]\nName=broken\nIsRelative=1\nPath=def.broken\n",
+    )
+    .unwrap();
+    std::fs::write(firefox.join("abc.ok/prefs.js"), "user_pref(\"a\", 1);\n").unwrap();
+    std::fs::write(firefox.join("def.broken/prefs.js"), "user_pref(\"a\",").unwrap();
+
+    brw()
+        .args(["--format", "plain", "profiles", "firefox", "--details"])
+        .env("HOME", home.path())
+        .assert()
+        .success()
+        .stdout("abc.ok\thealthy\t19\ndef.broken\tcorrupt\t14\n");
+
+    let output = brw()
+        .args([
+            "--format",
+            "json",
+            "profiles",
+            "firefox:broken",
+            "--details",
+        ])
+        .env("HOME", home.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let report = &json["profiles"][0];
+    assert_eq!(report["verdict"], "corrupt");
+    assert_eq!(report["lock"]["state"], "free");
+    assert_eq!(report["checks"][0]["status"], "invalid");
+    assert_eq!(report["size"]["data"], 14);
+
+    brw()
+        .args(["profiles", "firefox", "--details", "--extensions"])
+        .assert()
+        .code(2);
+}
+
+#[test]
+fn profiles_export_and_restore() {
+    let home = tempfile::tempdir().unwrap();
+    let run = |args: &[&str]| {
+        brw()
+            .args(["--format", "plain", "profiles"])
+            .args(args)
+            .env("HOME", home.path())
+            .assert()
+    };
+    let archive = home.path().join("work.tar.gz");
+    let archive = archive.to_str().unwrap();
+
+    let output = run(&["create", "firefox", "Work"]).success();
+    let id = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+    let profile = home.path().join(".mozilla/firefox").join(id.trim());
+    std::fs::write(profile.join("prefs.js"), "user_pref(\"a\", 1);\n").unwrap();
+    std::fs::create_dir_all(profile.join("cache2")).unwrap();
+
+    run(&["export", "firefox:Work", archive]).success();
+    run(&["restore", "chrome", archive])
+        .code(64)
+        .stderr(predicate::str::contains(
+            "firefox profile cannot be restored",
+        ));
+    run(&["restore", "firefox", archive]).code(64);
+    let output = run(&["restore", "firefox", archive, "--name", "Work copy"]).success();
+    let id = String::from_utf8(output.get_output().stdout.clone()).unwrap();
+    let restored = home.path().join(".mozilla/firefox").join(id.trim());
+    assert!(restored.join("prefs.js").is_file());
+    assert!(!restored.join("cache2").exists());
+    run(&["firefox:Work copy"]).success().stdout(id);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn open_in_firefox_container() {
+    let home = tempfile::tempdir().unwrap();
+    let firefox = home.path().join(".mozilla/firefox");
+    let profile = firefox.join("abc.default");
+    std::fs::create_dir_all(&profile).unwrap();
+    std::fs::write(
+        firefox.join("profiles.ini"),
+        "[Profile0]\nName=default\nIsRelative=1\nPath=abc.default\nDefault=1\n",
+    )
+    .unwrap();
+    std::fs::write(
+        profile.join("containers.json"),
+        r#"{"version": 5, "identities": [{"userContextId": 3, "public": true,
+            "l10nID": "userContextBanking.label", "icon": "dollar", "color": "green"}]}"#,
+    )
+    .unwrap();
+    let open = |target: &str| {
+        brw()
+            .args(["open", "--dry-run", "--browser", target])
+            .arg("https://bank.example/")
+            .env("HOME", home.path())
+            .assert()
+    };
+
+    open("firefox:@default/banking")
+        .code(69)
+        .stderr(predicate::str::contains("is not installed"));
+
+    std::fs::write(
+        profile.join("extensions.json"),
+        r#"{"addons": [{"id": "{f069aec0-43c5-4bbf-b6b4-df95c4326b98}", "active": true}]}"#,
+    )
+    .unwrap();
+    open("firefox:@default/banking")
+        .success()
+        .stdout(predicate::str::contains(
+            "ext+container:name=Banking&url=https%3A%2F%2Fbank.example%2F",
+        ));
+    open("firefox:default/Travel")
+        .code(69)
+        .stderr(predicate::str::contains("container not found: Travel"));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn profiles_lists_extensions() {
+    let home = tempfile::tempdir().unwrap();
+    let firefox = home.path().join(".mozilla/firefox");
+    let profile = firefox.join("abc.default");
+    std::fs::create_dir_all(&profile).unwrap();
+    std::fs::write(
+        firefox.join("profiles.ini"),
+        "[Profile0]\nName=default\nIsRelative=1\nPath=abc.default\n",
+    )
+    .unwrap();
+    std::fs::write(
+        profile.join("extensions.json"),
+        r#"{"addons": [{"id": "uBlock0@raymondhill.net", "version": "1.62.0",
+            "type": "extension", "active": true, "location": "app-profile",
+            "defaultLocale": {"name": "uBlock Origin"},
+            "installTelemetryInfo": {"source": "amo"}}]}"#,
+    )
+    .unwrap();
+
+    let output = brw()
+        .args(["--format", "json", "profiles", "firefox", "--extensions"])
+        .env("HOME", home.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["schema_version"], 1);
+    let extension = &json["profiles"][0]["extensions"][0];
+    assert_eq!(extension["name"], "uBlock Origin");
+    assert_eq!(extension["version"], "1.62.0");
+    assert_eq!(extension["enabled"], true);
+    assert_eq!(extension["source"], "store");
+}
+
+#[test]
+fn rules_suggest_from_history() {
+    let home = tempfile::tempdir().unwrap();
+    let firefox = home.path().join(".mozilla/firefox");
+    std::fs::create_dir_all(&firefox).unwrap();
+    std::fs::write(
+        firefox.join("profiles.ini"),
+        "[Profile0]\nName=work\nIsRelative=1\nPath=abc.work\n\n\
+         [Profile1]\nName=home\nIsRelative=1\nPath=def.home\n",
+    )
+    .unwrap();
+    for (dir, urls) in [
+        (
+            "abc.work",
+            [("https://github.com/org", 30), ("https://news.example", 1)],
+        ),
+        (
+            "def.home",
+            [("https://github.com/me", 1), ("https://news.example", 20)],
+        ),
+    ] {
+        std::fs::create_dir_all(firefox.join(dir)).unwrap();
+        let places = rusqlite::Connection::open(firefox.join(dir).join("places.sqlite")).unwrap();
+        places
+            .execute_batch("CREATE TABLE moz_places (url TEXT, visit_count INTEGER)")
+            .unwrap();
+        for (url, count) in urls {
+            places
+                .execute(
+                    "INSERT INTO moz_places VALUES (?1, ?2)",
+                    rusqlite::params![url, count],
+                )
+                .unwrap();
+        }
+    }
+
+    brw()
+        .args(["rules", "suggest", "firefox"])
+        .env("HOME", home.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "target = \"firefox:abc.work\"\ndomains = [\n    # github.com: 97% of 31 visits",
+        ))
+        .stdout(predicate::str::contains(
+            "\"news.example\", \"*.news.example\"",
+        ));
+
+    let output = brw()
+        .args([
+            "--format",
+            "json",
+            "rules",
+            "suggest",
+            "firefox",
+            "--min-confidence",
+            "0.99",
+        ])
+        .env("HOME", home.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["profiles"], 2);
+    assert_eq!(json["count"], 0);
+
+    let snippet = home.path().join("rules.toml");
+    brw()
+        .args([
+            "--format", "json", "rules", "suggest", "firefox", "--output",
+        ])
+        .arg(&snippet)
+        .env("HOME", home.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--format json"));
+    assert!(!snippet.exists());
+}
@@ -0,0 +1,324 @@
+//! User data directory resolution.
+//!
+//! Browsers keep their profiles below a per-browser user data directory whose
+//! location depends on the platform, the release channel, and how the browser
+//! is packaged. The [registry](crate::registry) stores the locations as
+//! templates; this module resolves them to concrete paths.
+//!
+//! Sandboxed Linux packages relocate the user's directories:
+//!
+//! | Packaging | Home | `$XDG_CONFIG_HOME` | `$XDG_DATA_HOME` |
+//! |-----------|------|--------------------|------------------|
+//! | Native    | `~`  | `~/.config` | `~/.local/share` |
+//! | Flatpak   | `~/.var/app/<id>` | `~/.var/app/<id>/config` | `~/.var/app/<id>/data` |
+//! | Snap      | `~/snap/<name>/current` | `~/snap/<name>/current/.config` | `~/snap/<name>/current/.local/share` |
+
+use std::path::{Path, PathBuf};
+
+use browserware_types::{Browser, Packaging};
+
+use crate::registry::{self, BrowserMeta, DataRoot, UserDataDir};
+
+/// Base directories that user data directory templates are resolved against.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BaseDirs {
+    /// The user's home directory.
+    pub home: PathBuf,
+    /// `$XDG_CONFIG_HOME` (default `~/.config`).
+    pub xdg_config: PathBuf,
+    /// `$XDG_DATA_HOME` (default `~/.local/share`).
+    pub xdg_data: PathBuf,
+    /// `%LOCALAPPDATA%` (default `~\AppData\Local`).
+    pub local_app_data: PathBuf,
+    /// `%APPDATA%` (default `~\AppData\Roaming`).
+    pub roaming_app_data: PathBuf,
+}
+
+impl BaseDirs {
+    /// Create base directories using the conventional defaults for `home`.
+    ///
+    /// Environment overrides such as `$XDG_CONFIG_HOME` are ignored.
+    pub fn with_home(home: impl Into<PathBuf>) -> Self {
+        let home = home.into();
+        Self {
+            xdg_config: home.join(".config"),
+            xdg_data: home.join(".local").join("share"),
+            local_app_data: home.join("AppData").join("Local"),
+            roaming_app_data: home.join("AppData").join("Roaming"),
+            home,
+        }
+    }
+
+    /// Create base directories for the current user.
+    ///
+    /// Honors `$XDG_CONFIG_HOME`, `$XDG_DATA_HOME`, `%LOCALAPPDATA%` and
+    /// `%APPDATA%` when set. Returns `None` if the home directory is unknown.
+    #[must_use]
+    pub fn from_env() -> Option<Self> {
+        let home = home::home_dir()?;
+        Some(Self::from_vars(home, |key| std::env::var_os(key)))
+    }
+
+    fn from_vars(home: PathBuf, var: impl Fn(&str) -> Option<std::ffi::OsString>) -> Self {
+        // The XDG spec requires absolute paths; relative values are ignored.
+        let absolute = |key: &str| var(key).map(PathBuf::from).filter(|p| p.is_absolute());
+
+        let mut dirs = Self::with_home(home);
+        if let Some(path) = absolute("XDG_CONFIG_HOME") {
+            dirs.xdg_config = path;
+        }
+        if let Some(path) = absolute("XDG_DATA_HOME") {
+            dirs.xdg_data = path;
+        }
+        if let Some(path) = absolute("LOCALAPPDATA") {
+            dirs.local_app_data = path;
+        }
+        if let Some(path) = absolute("APPDATA") {
+            dirs.roaming_app_data = path;
+        }
+        dirs
+    }
+
+    /// Resolve a user data directory template.
+    ///
+    /// Returns `None` for [`DataRoot::SnapCommon`], which only has a meaning
+    /// for a specific Snap package.
+    #[must_use]
+    pub fn resolve(&self, dir: UserDataDir) -> Option<PathBuf> {
+        let base = match dir.root {
+            DataRoot::Home => self.home.clone(),
+            DataRoot::XdgConfig => self.xdg_config.clone(),
+            DataRoot::XdgData => self.xdg_data.clone(),
+            DataRoot::Library => self.home.join("Library"),
+            DataRoot::ApplicationSupport => self.home.join("Library").join("Application Support"),
+            DataRoot::LocalAppData => self.local_app_data.clone(),
+            DataRoot::RoamingAppData => self.roaming_app_data.clone(),
+            DataRoot::SnapCommon => return None,
+        };
+        Some(join_relative(base, dir.path))
+    }
+
+    /// Base directories as seen from inside a Flatpak sandbox.
+    fn flatpak(&self, app_id: &str) -> Self {
+        let root = self.home.join(".var").join("app").join(app_id);
+        Self {
+            xdg_config: root.join("config"),
+            xdg_data: root.join("data"),
+            home: root,
+            ..self.clone()
+        }
+    }
+
+    /// Base directories as seen from inside a Snap sandbox.
+    fn snap(&self, name: &str) -> Self {
+        let root = self.home.join("snap").join(name).join("current");
+        Self {
+            xdg_config: root.join(".config"),
+            xdg_data: root.join(".local").join("share"),
+            home: root,
+            ..self.clone()
+        }
+    }
+}
+
+/// Resolve the user data directory of a browser.
+///
+/// This is the directory that contains the browser's profiles, e.g.
+/// `~/.config/google-chrome` for Chrome or `~/.mozilla/firefox` for Firefox
+/// on Linux. Flatpak and Snap installs are relocated into their sandbox.
+///
+/// # Returns
+///
+/// `None` if the browser is not in the [registry](crate::registry), its data
+/// location is unknown on this platform, or the home directory is unknown.
+/// The directory is not required to exist.
+///
+/// # Example
+///
+/// ```no_run
+/// if let Some(chrome) = browserware_detect::detect_browser("chrome")
+///     && let Some(dir) = browserware_detect::user_data_dir(&chrome)
+/// {
+///     println!("Chrome profiles live in {}", dir.display());
+/// }
+/// ```
+#[must_use]
+pub fn user_data_dir(browser: &Browser) -> Option<PathBuf> {
+    let dirs = BaseDirs::from_env()?;
+    user_data_dir_in(browser, &dirs)
+}
+
+/// Resolve the user data directory of a browser against explicit base directories.
+///
+/// See [`user_data_dir`] for details.
+#[must_use]
+pub fn user_data_dir_in(browser: &Browser, dirs: &BaseDirs) -> Option<PathBuf> {
+    let meta = registry::find_by_id(&browser.id.0)?;
+    resolve_meta(meta, browser.packaging, dirs)
+}
+
+fn resolve_meta(meta: &BrowserMeta, packaging: Packaging, dirs: &BaseDirs) -> Option<PathBuf> {
+    match packaging {
+        Packaging::Native => dirs.resolve(meta.user_data_dir()?),
+        Packaging::Flatpak => dirs
+            .flatpak(meta.flatpak_id?)
+            .resolve(meta.linux_user_data_dir?),
+        Packaging::Snap => {
+            let name = meta.snap_name?;
+            if let Some(dir) = meta.snap_user_data_dir
+                && dir.root == DataRoot::SnapCommon
+            {
+                let common = dirs.home.join("snap").join(name).join("common");
+                return Some(join_relative(common, dir.path));
+            }
+            let dir = meta.snap_user_data_dir.or(meta.linux_user_data_dir)?;
+            dirs.snap(name).resolve(dir)
+        }
+    }
+}
+
+/// Join a `/`-separated relative path onto a base directory.
+fn join_relative(mut base: PathBuf, relative: &str) -> PathBuf {
+    base.extend(relative.split('/').filter(|c| !c.is_empty()).map(Path::new));
+    base
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use browserware_types::Packaging;
+
+    fn dirs() -> BaseDirs {
+        BaseDirs::with_home("/home/user")
+    }
+
+    fn browser(id: &str, packaging: Packaging) -> Browser {
+        Browser::new(id, id, PathBuf::from("/usr/bin/browser")).with_packaging(packaging)
+    }
+
+    #[test]
+    fn join_relative_splits_components() {
+        let path = join_relative(PathBuf::from("/base"), "Google/Chrome Beta/User Data");
+        assert_eq!(
+            path,
+            Path::new("/base")
+                .join("Google")
+                .join("Chrome Beta")
+                .join("User Data")
+        );
+    }
+
+    #[test]
+    fn unknown_browser_has_no_data_dir() {
+        let unknown = browser("com.example.browser", Packaging::Native);
+        assert_eq!(user_data_dir_in(&unknown, &dirs()), None);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn native_linux_data_dirs() {
+        let chrome = browser("chrome", Packaging::Native);
+        assert_eq!(
+            user_data_dir_in(&chrome, &dirs()),
+            Some(PathBuf::from("/home/user/.config/google-chrome"))
+        );
+
+        let beta = browser("chrome-beta", Packaging::Native);
+        assert_eq!(
+            user_data_dir_in(&beta, &dirs()),
+            Some(PathBuf::from("/home/user/.config/google-chrome-beta"))
+        );
+
+        let firefox = browser("firefox", Packaging::Native);
+        assert_eq!(
+            user_data_dir_in(&firefox, &dirs()),
+            Some(PathBuf::from("/home/user/.mozilla/firefox"))
+        );
+
+        let epiphany = browser("gnome-web", Packaging::Native);
+        assert_eq!(
+            user_data_dir_in(&epiphany, &dirs()),
+            Some(PathBuf::from("/home/user/.local/share/epiphany"))
+        );
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn xdg_overrides_are_honored() {
+        let dirs = BaseDirs::from_vars(PathBuf::from("/home/user"), |key| match key {
+            "XDG_CONFIG_HOME" => Some("/cfg".into()),
+            "XDG_DATA_HOME" => Some("relative/ignored".into()),
+            _ => None,
+        });
+        assert_eq!(dirs.xdg_config, PathBuf::from("/cfg"));
+        assert_eq!(dirs.xdg_data, PathBuf::from("/home/user/.local/share"));
+
+        let brave = browser("brave", Packaging::Native);
+        assert_eq!(
+            user_data_dir_in(&brave, &dirs),
+            Some(PathBuf::from("/cfg/BraveSoftware/Brave-Browser"))
+        );
+    }
+
+    #[test]
+    fn flatpak_data_dirs_are_relocated() {
+        let chrome = browser("chrome", Packaging::Flatpak);
+        assert_eq!(
+            user_data_dir_in(&chrome, &dirs()),
+            Some(PathBuf::from(
+                "/home/user/.var/app/com.google.Chrome/config/google-chrome"
+            ))
+        );
+
+        let firefox = browser("firefox", Packaging::Flatpak);
+        assert_eq!(
+            user_data_dir_in(&firefox, &dirs()),
+            Some(PathBuf::from(
+                "/home/user/.var/app/org.mozilla.firefox/.mozilla/firefox"
+            ))
+        );
+
+        let epiphany = browser("gnome-web", Packaging::Flatpak);
+        assert_eq!(
+            user_data_dir_in(&epiphany, &dirs()),
+            Some(PathBuf::from(
+                "/home/user/.var/app/org.gnome.Epiphany/data/epiphany"
+            ))
+        );
+    }
+
+    #[test]
+    fn snap_data_dirs_are_relocated() {
+        let chromium = browser("chromium", Packaging::Snap);
+        assert_eq!(
+            user_data_dir_in(&chromium, &dirs()),
+            Some(PathBuf::from("/home/user/snap/chromium/common/chromium"))
+        );
+
+        let firefox = browser("firefox", Packaging::Snap);
+        assert_eq!(
+            user_data_dir_in(&firefox, &dirs()),
+            Some(PathBuf::from(
+                "/home/user/snap/firefox/common/.mozilla/firefox"
+            ))
+        );
+
+        let brave = browser("brave", Packaging::Snap);
+        assert_eq!(
+            user_data_dir_in(&brave, &dirs()),
+            Some(PathBuf::from(
+                "/home/user/snap/brave/current/.config/BraveSoftware/Brave-Browser"
+            ))
+        );
+    }
+
+    #[test]
+    fn sandboxed_without_package_id_has_no_data_dir() {
+        // Chrome Beta is not published on Flathub or the Snap Store
+        let beta = browser("chrome-beta", Packaging::Flatpak);
+        assert_eq!(user_data_dir_in(&beta, &dirs()), None);
+
+        let beta = browser("chrome-beta", Packaging::Snap);
+        assert_eq!(user_data_dir_in(&beta, &dirs()), None);
+    }
+}
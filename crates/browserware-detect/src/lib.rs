@@ -14,6 +14,10 @@
 //! provide consistent metadata (IDs, display names, engine families).
 //! Unknown browsers are still detected with derived metadata.
 //!
+//! The registry also records where each browser keeps its user data, so
+//! [`user_data_dir`] is the single place that knows about per-channel
+//! directories and Flatpak/Snap relocations.
+//!
 //! # Example
 //!
 //! ```no_run
@@ -50,11 +54,18 @@
 #![allow(unsafe_code)]
 #![warn(missing_docs)]
 
+mod data_dir;
 mod platform;
+mod process;
 pub mod registry;
 
+pub use data_dir::{BaseDirs, user_data_dir, user_data_dir_in};
+pub use process::{
+    ProfileArg, RunningInstance, flatpak_exported_binary, running_instances, running_instances_in,
+};
+
 // Re-export types from browserware-types for convenience
-pub use browserware_types::{Browser, BrowserFamily, BrowserId, BrowserVariant};
+pub use browserware_types::{Browser, BrowserFamily, BrowserId, BrowserVariant, Packaging};
 
 /// Detect all installed browsers on the system.
 ///
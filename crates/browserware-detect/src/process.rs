@@ -0,0 +1,548 @@
+//! Running browser instance discovery.
+//!
+//! Discovery strategy (Linux):
+//! 1. Enumerate numeric entries in `/proc`
+//! 2. Read `/proc/<pid>/exe` and `/proc/<pid>/cmdline`
+//! 3. Identify the browser:
+//!    a. Flatpak apps by their `app-flatpak-<app-id>-*.scope` cgroup
+//!    b. Snaps by their `/snap/<name>/<revision>/...` executable
+//!    c. Native installs by `BrowserMeta::linux_executables`
+//! 4. Skip helper processes (Chromium `--type=...`, Firefox `-contentproc`)
+//! 5. Extract the profile selection from the command line
+//! 6. Read the start time from `/proc/<pid>/stat`
+//!
+//! Processes of other users are skipped silently since their `exe` link
+//! cannot be read.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use browserware_types::{Browser, BrowserFamily, Packaging};
+
+use crate::registry::{self, BrowserMeta};
+
+/// A browser main process that is currently running.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunningInstance {
+    /// Process ID of the browser's main process
+    pub pid: u32,
+    /// The browser this process belongs to
+    pub browser: Browser,
+    /// Profile selected on the command line, if any
+    pub profile: Option<ProfileArg>,
+    /// User data directory selected on the command line (Chromium `--user-data-dir`)
+    pub user_data_dir: Option<PathBuf>,
+    /// Start time in clock ticks since boot (if known), for ordering instances
+    pub start_time: Option<u64>,
+}
+
+/// A profile selection found on a browser's command line.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "lowercase")]
+pub enum ProfileArg {
+    /// Chromium `--profile-directory=<dir>`, relative to the user data directory
+    Directory(String),
+    /// Firefox `-P <name>`, a profile name from `profiles.ini`
+    Name(String),
+    /// Firefox `--profile <path>` or GNOME Web `--profile=<path>`
+    Path(PathBuf),
+}
+
+/// List browser instances that are currently running.
+///
+/// Each instance is matched to a browser from `browsers` (typically the
+/// result of [`detect_browsers`](crate::detect_browsers)). Processes of known
+/// browsers that were not detected are described from registry metadata.
+///
+/// # Platform Behavior
+///
+/// - **Linux**: Scans `/proc`
+/// - **Other platforms**: Not yet implemented, returns an empty list
+///
+/// # Example
+///
+/// ```no_run
+/// let browsers = browserware_detect::detect_browsers();
+/// for instance in browserware_detect::running_instances(&browsers) {
+///     println!("{} (pid {})", instance.browser.name, instance.pid);
+/// }
+/// ```
+#[tracing::instrument(level = "debug", skip_all)]
+#[must_use]
+pub fn running_instances(browsers: &[Browser]) -> Vec<RunningInstance> {
+    if cfg!(target_os = "linux") {
+        running_instances_in(Path::new("/proc"), browsers)
+    } else {
+        tracing::debug!("Running instance discovery not implemented for this platform");
+        Vec::new()
+    }
+}
+
+/// List running browser instances from a procfs mounted at `proc_root`.
+///
+/// See [`running_instances`] for details.
+#[must_use]
+pub fn running_instances_in(proc_root: &Path, browsers: &[Browser]) -> Vec<RunningInstance> {
+    let Ok(entries) = fs::read_dir(proc_root) else {
+        tracing::warn!(?proc_root, "Cannot read procfs");
+        return Vec::new();
+    };
+
+    let mut instances: Vec<RunningInstance> = entries
+        .flatten()
+        .filter_map(|entry| entry.file_name().to_str()?.parse::<u32>().ok())
+        .filter_map(|pid| inspect_process(&proc_root.join(pid.to_string()), pid, browsers))
+        .collect();
+
+    instances.sort_by_key(|instance| instance.pid);
+    tracing::debug!(count = instances.len(), "Found running browser instances");
+    instances
+}
+
+/// Inspect a single `/proc/<pid>` directory.
+fn inspect_process(dir: &Path, pid: u32, browsers: &[Browser]) -> Option<RunningInstance> {
+    let exe = read_exe(dir)?;
+    let args = read_cmdline(dir)?;
+
+    let (meta, packaging) = identify(dir, &exe)?;
+    if is_helper_process(meta.family(), &args) {
+        return None;
+    }
+    if packaging == Packaging::Flatpak && is_sandbox_tooling(&exe) {
+        return None;
+    }
+
+    let (profile, user_data_dir) = parse_profile_args(meta.family(), &args);
+    tracing::trace!(pid, browser_id = meta.id, "Found browser process");
+
+    Some(RunningInstance {
+        pid,
+        browser: browser_for(meta, packaging, &exe, browsers),
+        profile,
+        user_data_dir,
+        start_time: read_start_time(dir),
+    })
+}
+
+/// Read the process start time (field 22 of `stat`).
+fn read_start_time(dir: &Path) -> Option<u64> {
+    let stat = fs::read_to_string(dir.join("stat")).ok()?;
+    // The command name (field 2) is parenthesized and may contain spaces
+    let (_, rest) = stat.rsplit_once(')')?;
+    rest.split_whitespace().nth(19)?.parse().ok()
+}
+
+/// Read the process executable, dropping the ` (deleted)` suffix left after upgrades.
+fn read_exe(dir: &Path) -> Option<PathBuf> {
+    let exe = fs::read_link(dir.join("exe")).ok()?;
+    let stripped = exe
+        .to_str()
+        .and_then(|s| s.strip_suffix(" (deleted)"))
+        .map(PathBuf::from);
+    Some(stripped.unwrap_or(exe))
+}
+
+/// Read the NUL-separated process arguments, excluding `argv[0]`.
+fn read_cmdline(dir: &Path) -> Option<Vec<String>> {
+    let raw = fs::read(dir.join("cmdline")).ok()?;
+    let mut args: Vec<String> = raw
+        .split(|b| *b == 0)
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .collect();
+    // Trailing NUL yields an empty final element
+    if args.last().is_some_and(String::is_empty) {
+        args.pop();
+    }
+    if args.is_empty() {
+        // Kernel threads and zombies have an empty cmdline
+        return None;
+    }
+    args.remove(0);
+    Some(args)
+}
+
+/// Identify the browser and its packaging from a process.
+fn identify(dir: &Path, exe: &Path) -> Option<(&'static BrowserMeta, Packaging)> {
+    if let Some(app_id) = flatpak_app_id(dir) {
+        return registry::find_by_flatpak_id(&app_id).map(|meta| (meta, Packaging::Flatpak));
+    }
+
+    if let Ok(rest) = exe.strip_prefix("/snap")
+        && let Some(name) = rest.iter().next().and_then(|n| n.to_str())
+    {
+        return registry::find_by_snap_name(name).map(|meta| (meta, Packaging::Snap));
+    }
+
+    registry::find_by_linux_executable(exe).map(|meta| (meta, Packaging::Native))
+}
+
+/// Extract the Flatpak application ID from the process cgroup.
+///
+/// Flatpak places apps in a systemd scope named `app-flatpak-<app-id>-<n>.scope`
+/// (older versions use `flatpak-<app-id>-<n>.scope`).
+fn flatpak_app_id(dir: &Path) -> Option<String> {
+    let cgroup = fs::read_to_string(dir.join("cgroup")).ok()?;
+    cgroup.lines().find_map(|line| {
+        let scope = line.rsplit('/').next()?.strip_suffix(".scope")?;
+        let rest = scope
+            .strip_prefix("app-flatpak-")
+            .or_else(|| scope.strip_prefix("flatpak-"))?;
+        let (app_id, instance) = rest.rsplit_once('-')?;
+        instance
+            .chars()
+            .all(|c| c.is_ascii_digit())
+            .then(|| app_id.to_string())
+    })
+}
+
+/// Returns true for sandbox processes that share the app's cgroup.
+fn is_sandbox_tooling(exe: &Path) -> bool {
+    const TOOLING: &[&str] = &["bwrap", "zypak-helper", "zypak-sandbox", "flatpak-spawn"];
+    exe.file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|name| TOOLING.contains(&name))
+}
+
+/// Returns true for renderer, GPU, utility and content processes.
+fn is_helper_process(family: BrowserFamily, args: &[String]) -> bool {
+    match family {
+        BrowserFamily::Chromium => args.iter().any(|a| a.starts_with("--type=")),
+        BrowserFamily::Firefox => args
+            .iter()
+            .any(|a| a == "-contentproc" || a == "-forkserver"),
+        BrowserFamily::WebKit | BrowserFamily::Other => false,
+    }
+}
+
+/// Extract the profile and user data directory selected on the command line.
+fn parse_profile_args(
+    family: BrowserFamily,
+    args: &[String],
+) -> (Option<ProfileArg>, Option<PathBuf>) {
+    let mut profile = None;
+    let mut user_data_dir = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match family {
+            BrowserFamily::Chromium => {
+                if let Some(dir) = arg.strip_prefix("--profile-directory=") {
+                    profile = Some(ProfileArg::Directory(dir.to_string()));
+                } else if let Some(dir) = arg.strip_prefix("--user-data-dir=") {
+                    user_data_dir = Some(PathBuf::from(dir));
+                }
+            }
+            BrowserFamily::Firefox => match arg.as_str() {
+                "-P" | "--P" | "-p" => {
+                    if let Some(name) = iter.next().filter(|n| !n.starts_with('-')) {
+                        profile = Some(ProfileArg::Name(name.clone()));
+                    }
+                }
+                "-profile" | "--profile" => {
+                    if let Some(path) = iter.next() {
+                        profile = Some(ProfileArg::Path(PathBuf::from(path)));
+                    }
+                }
+                _ => {
+                    if let Some(path) = arg.strip_prefix("--profile=") {
+                        profile = Some(ProfileArg::Path(PathBuf::from(path)));
+                    }
+                }
+            },
+            // `-p` is Epiphany's `--private-instance`, not `--profile`
+            BrowserFamily::WebKit => match arg.as_str() {
+                "--profile" => {
+                    if let Some(path) = iter.next() {
+                        profile = Some(ProfileArg::Path(PathBuf::from(path)));
+                    }
+                }
+                _ => {
+                    if let Some(path) = arg.strip_prefix("--profile=") {
+                        profile = Some(ProfileArg::Path(PathBuf::from(path)));
+                    }
+                }
+            },
+            BrowserFamily::Other => {}
+        }
+    }
+
+    (profile, user_data_dir)
+}
+
+/// Find the detected browser for a process, or describe it from the registry.
+fn browser_for(
+    meta: &'static BrowserMeta,
+    packaging: Packaging,
+    exe: &Path,
+    browsers: &[Browser],
+) -> Browser {
+    if let Some(browser) = browsers
+        .iter()
+        .find(|b| b.id.0 == meta.id && b.packaging == packaging)
+    {
+        return browser.clone();
+    }
+
+    let executable = match packaging {
+        Packaging::Native => exe.to_path_buf(),
+        Packaging::Flatpak => meta
+            .flatpak_id
+            .map_or_else(|| exe.to_path_buf(), flatpak_exported_binary),
+        Packaging::Snap => meta.snap_name.map_or_else(
+            || exe.to_path_buf(),
+            |name| Path::new("/snap/bin").join(name),
+        ),
+    };
+
+    Browser::new(meta.id, meta.name, executable)
+        .with_variant(meta.variant)
+        .with_packaging(packaging)
+}
+
+/// Path of the launcher Flatpak exports for an app, preferring per-user installs.
+///
+/// This is `~/.local/share/flatpak/exports/bin/<app-id>` if it exists, and
+/// the system-wide `/var/lib/flatpak/exports/bin/<app-id>` otherwise.
+#[must_use]
+pub fn flatpak_exported_binary(app_id: &str) -> PathBuf {
+    let user = home::home_dir()
+        .map(|home| home.join(".local/share/flatpak/exports/bin").join(app_id))
+        .filter(|path| path.exists());
+    user.unwrap_or_else(|| Path::new("/var/lib/flatpak/exports/bin").join(app_id))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+
+    /// Builder for a fake procfs tree.
+    struct FakeProc {
+        root: tempfile::TempDir,
+    }
+
+    impl FakeProc {
+        fn new() -> Self {
+            Self {
+                root: tempfile::tempdir().unwrap(),
+            }
+        }
+
+        fn process(&self, pid: u32, exe: &str, args: &[&str]) -> &Self {
+            self.process_in_cgroup(pid, exe, args, "0::/user.slice/session-2.scope")
+        }
+
+        fn process_in_cgroup(&self, pid: u32, exe: &str, args: &[&str], cgroup: &str) -> &Self {
+            let dir = self.root.path().join(pid.to_string());
+            fs::create_dir_all(&dir).unwrap();
+            symlink(exe, dir.join("exe")).unwrap();
+
+            let mut cmdline = Vec::new();
+            for arg in std::iter::once(&exe).chain(args) {
+                cmdline.extend_from_slice(arg.as_bytes());
+                cmdline.push(0);
+            }
+            fs::write(dir.join("cmdline"), cmdline).unwrap();
+            fs::write(dir.join("cgroup"), format!("{cgroup}\n")).unwrap();
+            self
+        }
+
+        fn started(&self, pid: u32, start_time: u64) -> &Self {
+            let fields = ["S"; 19].join(" ");
+            fs::write(
+                self.root.path().join(pid.to_string()).join("stat"),
+                format!("{pid} (Web Content) {fields} {start_time} 0 0\n"),
+            )
+            .unwrap();
+            self
+        }
+
+        fn instances(&self, browsers: &[Browser]) -> Vec<RunningInstance> {
+            running_instances_in(self.root.path(), browsers)
+        }
+    }
+
+    #[test]
+    fn finds_chromium_main_process_and_skips_helpers() {
+        let proc = FakeProc::new();
+        proc.process(
+            100,
+            "/opt/google/chrome/chrome",
+            &["--profile-directory=Profile 2", "https://example.com"],
+        )
+        .process(101, "/opt/google/chrome/chrome", &["--type=renderer"])
+        .process(102, "/opt/google/chrome/chrome", &["--type=zygote"]);
+
+        let instances = proc.instances(&[]);
+        assert_eq!(instances.len(), 1);
+        assert_eq!(instances[0].pid, 100);
+        assert_eq!(instances[0].browser.id.0, "chrome");
+        assert_eq!(
+            instances[0].profile,
+            Some(ProfileArg::Directory("Profile 2".to_string()))
+        );
+        assert_eq!(instances[0].user_data_dir, None);
+    }
+
+    #[test]
+    fn extracts_chromium_user_data_dir() {
+        let proc = FakeProc::new();
+        proc.process(
+            7,
+            "/opt/google/chrome-beta/chrome",
+            &["--user-data-dir=/tmp/throwaway"],
+        );
+
+        let instances = proc.instances(&[]);
+        assert_eq!(instances[0].browser.id.0, "chrome-beta");
+        assert_eq!(
+            instances[0].user_data_dir,
+            Some(PathBuf::from("/tmp/throwaway"))
+        );
+    }
+
+    #[test]
+    fn finds_firefox_and_skips_content_processes() {
+        let proc = FakeProc::new();
+        proc.process(200, "/usr/lib/firefox/firefox", &["-P", "work"])
+            .process(
+                201,
+                "/usr/lib/firefox/firefox",
+                &["-contentproc", "-isForBrowser", "-prefsLen", "1", "tab"],
+            )
+            .process(
+                300,
+                "/usr/lib/firefox/firefox",
+                &["--profile", "/home/user/throwaway", "--new-instance"],
+            );
+
+        let instances = proc.instances(&[]);
+        assert_eq!(instances.len(), 2);
+        assert_eq!(
+            instances[0].profile,
+            Some(ProfileArg::Name("work".to_string()))
+        );
+        assert_eq!(
+            instances[1].profile,
+            Some(ProfileArg::Path(PathBuf::from("/home/user/throwaway")))
+        );
+    }
+
+    #[test]
+    fn firefox_profile_manager_flag_is_not_a_profile() {
+        let proc = FakeProc::new();
+        proc.process(5, "/usr/lib/firefox/firefox", &["-P", "--no-remote"]);
+
+        assert_eq!(proc.instances(&[])[0].profile, None);
+    }
+
+    #[test]
+    fn identifies_flatpak_by_cgroup() {
+        let proc = FakeProc::new();
+        let cgroup = "0::/user.slice/user-1000.slice/user@1000.service/app.slice/\
+                      app-flatpak-org.mozilla.firefox-41234.scope";
+        proc.process_in_cgroup(400, "/app/lib/firefox/firefox", &[], cgroup)
+            .process_in_cgroup(401, "/usr/bin/bwrap", &["--args", "42"], cgroup)
+            .process_in_cgroup(402, "/app/lib/firefox/firefox", &["-contentproc"], cgroup);
+
+        let instances = proc.instances(&[]);
+        assert_eq!(instances.len(), 1);
+        assert_eq!(instances[0].pid, 400);
+        assert_eq!(instances[0].browser.id.0, "firefox");
+        assert_eq!(instances[0].browser.packaging, Packaging::Flatpak);
+    }
+
+    #[test]
+    fn identifies_snap_by_executable() {
+        let proc = FakeProc::new();
+        proc.process(
+            500,
+            "/snap/chromium/2890/usr/lib/chromium-browser/chrome",
+            &["--profile-directory=Default"],
+        );
+
+        let instances = proc.instances(&[]);
+        assert_eq!(instances[0].browser.id.0, "chromium");
+        assert_eq!(instances[0].browser.packaging, Packaging::Snap);
+        assert_eq!(
+            instances[0].browser.executable,
+            PathBuf::from("/snap/bin/chromium")
+        );
+    }
+
+    #[test]
+    fn identifies_epiphany_profile() {
+        let proc = FakeProc::new();
+        proc.process(
+            600,
+            "/usr/bin/epiphany",
+            &["--profile=/home/user/.local/share/org.gnome.Epiphany.WebApp_abc"],
+        );
+
+        let instances = proc.instances(&[]);
+        assert_eq!(instances[0].browser.id.0, "gnome-web");
+        assert_eq!(
+            instances[0].profile,
+            Some(ProfileArg::Path(PathBuf::from(
+                "/home/user/.local/share/org.gnome.Epiphany.WebApp_abc"
+            )))
+        );
+    }
+
+    #[test]
+    fn epiphany_private_flag_is_not_a_profile() {
+        let proc = FakeProc::new();
+        proc.process(601, "/usr/bin/epiphany", &["-p", "https://example.com"]);
+
+        let instances = proc.instances(&[]);
+        assert_eq!(instances[0].browser.id.0, "gnome-web");
+        assert_eq!(instances[0].profile, None);
+    }
+
+    #[test]
+    fn prefers_detected_browser() {
+        let proc = FakeProc::new();
+        proc.process(700, "/opt/google/chrome/chrome", &[]);
+
+        let detected = Browser::new("chrome", "Google Chrome", "/usr/bin/google-chrome".into())
+            .with_version("131.0");
+        let instances = proc.instances(std::slice::from_ref(&detected));
+        assert_eq!(instances[0].browser, detected);
+    }
+
+    #[test]
+    fn ignores_unknown_and_unreadable_processes() {
+        let proc = FakeProc::new();
+        proc.process(800, "/usr/bin/bash", &["-c", "firefox"]);
+        // Kernel thread: no exe link, empty cmdline
+        let kthread = proc.root.path().join("2");
+        fs::create_dir_all(&kthread).unwrap();
+        fs::write(kthread.join("cmdline"), b"").unwrap();
+        // Non-process entries
+        fs::create_dir_all(proc.root.path().join("self")).unwrap();
+
+        assert!(proc.instances(&[]).is_empty());
+    }
+
+    #[test]
+    fn strips_deleted_suffix() {
+        let proc = FakeProc::new();
+        proc.process(900, "/usr/lib/firefox/firefox (deleted)", &[]);
+
+        assert_eq!(proc.instances(&[])[0].browser.id.0, "firefox");
+    }
+
+    #[test]
+    fn reads_start_time() {
+        let proc = FakeProc::new();
+        proc.process(1000, "/usr/lib/firefox/firefox", &[])
+            .started(1000, 123_456)
+            .process(1001, "/usr/lib/firefox/firefox", &[]);
+
+        let instances = proc.instances(&[]);
+        assert_eq!(instances[0].start_time, Some(123_456));
+        assert_eq!(instances[1].start_time, None);
+    }
+}
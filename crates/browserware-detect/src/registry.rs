@@ -11,6 +11,8 @@
 //! for metadata enrichment. Unknown browsers still get detected with derived
 //! metadata.
 
+use std::path::Path;
+
 use browserware_types::{
     BrowserFamily, BrowserVariant, ChromiumChannel, FirefoxChannel, WebKitChannel,
 };
@@ -49,6 +51,145 @@ pub struct BrowserMeta {
     /// Includes both native package names and Flatpak/Snap identifiers.
     /// Empty slice indicates the browser is not available on Linux.
     pub linux_desktop_ids: &'static [&'static str],
+
+    /// Linux executables of the browser's main process.
+    ///
+    /// Entries containing `/` match the full executable path (needed where
+    /// channels share a binary name, e.g. `/opt/google/chrome-beta/chrome`);
+    /// other entries match the executable's file name. Used to identify
+    /// running processes.
+    pub linux_executables: &'static [&'static str],
+
+    /// User data directory on macOS.
+    ///
+    /// `None` indicates the location is unknown or the browser is not available.
+    pub macos_user_data_dir: Option<UserDataDir>,
+
+    /// User data directory on Windows.
+    pub windows_user_data_dir: Option<UserDataDir>,
+
+    /// User data directory on Linux for native (non-sandboxed) installs.
+    ///
+    /// Flatpak and Snap installs relocate this path; see
+    /// [`user_data_dir`](crate::user_data_dir).
+    pub linux_user_data_dir: Option<UserDataDir>,
+
+    /// Flatpak application ID (e.g., "org.mozilla.firefox").
+    pub flatpak_id: Option<&'static str>,
+
+    /// Snap package name (e.g., "chromium").
+    pub snap_name: Option<&'static str>,
+
+    /// Snap user data directory, for snaps that keep their data in
+    /// `$SNAP_USER_COMMON` instead of the relocated home directory.
+    pub snap_user_data_dir: Option<UserDataDir>,
+}
+
+/// Base directory that a [`UserDataDir`] is relative to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DataRoot {
+    /// The user's home directory
+    Home,
+    /// `$XDG_CONFIG_HOME` (default `~/.config`)
+    XdgConfig,
+    /// `$XDG_DATA_HOME` (default `~/.local/share`)
+    XdgData,
+    /// `~/Library` (macOS)
+    Library,
+    /// `~/Library/Application Support` (macOS)
+    ApplicationSupport,
+    /// `%LOCALAPPDATA%` (Windows)
+    LocalAppData,
+    /// `%APPDATA%` (Windows)
+    RoamingAppData,
+    /// `$SNAP_USER_COMMON`, i.e. `~/snap/<name>/common` (Snap)
+    SnapCommon,
+}
+
+/// A browser's user data directory, relative to a platform base directory.
+///
+/// Paths use `/` as separator regardless of platform; they are split into
+/// components when resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct UserDataDir {
+    /// Base directory the path is relative to.
+    pub root: DataRoot,
+    /// Relative path below the base directory.
+    pub path: &'static str,
+}
+
+impl UserDataDir {
+    /// Path relative to the home directory.
+    #[must_use]
+    pub const fn home(path: &'static str) -> Self {
+        Self {
+            root: DataRoot::Home,
+            path,
+        }
+    }
+
+    /// Path relative to `$XDG_CONFIG_HOME`.
+    #[must_use]
+    pub const fn xdg_config(path: &'static str) -> Self {
+        Self {
+            root: DataRoot::XdgConfig,
+            path,
+        }
+    }
+
+    /// Path relative to `$XDG_DATA_HOME`.
+    #[must_use]
+    pub const fn xdg_data(path: &'static str) -> Self {
+        Self {
+            root: DataRoot::XdgData,
+            path,
+        }
+    }
+
+    /// Path relative to `~/Library`.
+    #[must_use]
+    pub const fn library(path: &'static str) -> Self {
+        Self {
+            root: DataRoot::Library,
+            path,
+        }
+    }
+
+    /// Path relative to `~/Library/Application Support`.
+    #[must_use]
+    pub const fn app_support(path: &'static str) -> Self {
+        Self {
+            root: DataRoot::ApplicationSupport,
+            path,
+        }
+    }
+
+    /// Path relative to `%LOCALAPPDATA%`.
+    #[must_use]
+    pub const fn local_app_data(path: &'static str) -> Self {
+        Self {
+            root: DataRoot::LocalAppData,
+            path,
+        }
+    }
+
+    /// Path relative to `%APPDATA%`.
+    #[must_use]
+    pub const fn roaming_app_data(path: &'static str) -> Self {
+        Self {
+            root: DataRoot::RoamingAppData,
+            path,
+        }
+    }
+
+    /// Path relative to `$SNAP_USER_COMMON`.
+    #[must_use]
+    pub const fn snap_common(path: &'static str) -> Self {
+        Self {
+            root: DataRoot::SnapCommon,
+            path,
+        }
+    }
 }
 
 impl BrowserMeta {
@@ -75,6 +216,18 @@ impl BrowserMeta {
     pub const fn family(&self) -> BrowserFamily {
         self.variant.family()
     }
+
+    /// Returns the native user data directory for the current platform.
+    #[must_use]
+    pub const fn user_data_dir(&self) -> Option<UserDataDir> {
+        if cfg!(target_os = "macos") {
+            self.macos_user_data_dir
+        } else if cfg!(target_os = "windows") {
+            self.windows_user_data_dir
+        } else {
+            self.linux_user_data_dir
+        }
+    }
 }
 
 /// Registry of known browsers with their platform-specific identifiers.
@@ -93,6 +246,13 @@ pub static KNOWN_BROWSERS: &[BrowserMeta] = &[
         macos_bundle_ids: &["com.google.Chrome"],
         windows_registry_keys: &["Google Chrome"],
         linux_desktop_ids: &["google-chrome", "google-chrome-stable"],
+        linux_executables: &["/opt/google/chrome/chrome"],
+        macos_user_data_dir: Some(UserDataDir::app_support("Google/Chrome")),
+        windows_user_data_dir: Some(UserDataDir::local_app_data("Google/Chrome/User Data")),
+        linux_user_data_dir: Some(UserDataDir::xdg_config("google-chrome")),
+        flatpak_id: Some("com.google.Chrome"),
+        snap_name: None,
+        snap_user_data_dir: None,
     },
     BrowserMeta {
         id: "chrome-beta",
@@ -101,6 +261,13 @@ pub static KNOWN_BROWSERS: &[BrowserMeta] = &[
         macos_bundle_ids: &["com.google.Chrome.beta"],
         windows_registry_keys: &["Google Chrome Beta"],
         linux_desktop_ids: &["google-chrome-beta"],
+        linux_executables: &["/opt/google/chrome-beta/chrome"],
+        macos_user_data_dir: Some(UserDataDir::app_support("Google/Chrome Beta")),
+        windows_user_data_dir: Some(UserDataDir::local_app_data("Google/Chrome Beta/User Data")),
+        linux_user_data_dir: Some(UserDataDir::xdg_config("google-chrome-beta")),
+        flatpak_id: None,
+        snap_name: None,
+        snap_user_data_dir: None,
     },
     BrowserMeta {
         id: "chrome-dev",
@@ -109,6 +276,13 @@ pub static KNOWN_BROWSERS: &[BrowserMeta] = &[
         macos_bundle_ids: &["com.google.Chrome.dev"],
         windows_registry_keys: &["Google Chrome Dev"],
         linux_desktop_ids: &["google-chrome-unstable"],
+        linux_executables: &["/opt/google/chrome-unstable/chrome"],
+        macos_user_data_dir: Some(UserDataDir::app_support("Google/Chrome Dev")),
+        windows_user_data_dir: Some(UserDataDir::local_app_data("Google/Chrome Dev/User Data")),
+        linux_user_data_dir: Some(UserDataDir::xdg_config("google-chrome-unstable")),
+        flatpak_id: Some("com.google.ChromeDev"),
+        snap_name: None,
+        snap_user_data_dir: None,
     },
     BrowserMeta {
         id: "chrome-canary",
@@ -117,6 +291,13 @@ pub static KNOWN_BROWSERS: &[BrowserMeta] = &[
         macos_bundle_ids: &["com.google.Chrome.canary"],
         windows_registry_keys: &["Google Chrome Canary"],
         linux_desktop_ids: &[], // Canary not available on Linux
+        linux_executables: &[],
+        macos_user_data_dir: Some(UserDataDir::app_support("Google/Chrome Canary")),
+        windows_user_data_dir: Some(UserDataDir::local_app_data("Google/Chrome SxS/User Data")),
+        linux_user_data_dir: None,
+        flatpak_id: None,
+        snap_name: None,
+        snap_user_data_dir: None,
     },
     // =========================================================================
     // CHROMIUM FAMILY - Microsoft Edge
@@ -128,6 +309,13 @@ pub static KNOWN_BROWSERS: &[BrowserMeta] = &[
         macos_bundle_ids: &["com.microsoft.edgemac"],
         windows_registry_keys: &["Microsoft Edge"],
         linux_desktop_ids: &["microsoft-edge", "microsoft-edge-stable"],
+        linux_executables: &["/opt/microsoft/msedge/msedge"],
+        macos_user_data_dir: Some(UserDataDir::app_support("Microsoft Edge")),
+        windows_user_data_dir: Some(UserDataDir::local_app_data("Microsoft/Edge/User Data")),
+        linux_user_data_dir: Some(UserDataDir::xdg_config("microsoft-edge")),
+        flatpak_id: Some("com.microsoft.Edge"),
+        snap_name: None,
+        snap_user_data_dir: None,
     },
     BrowserMeta {
         id: "edge-beta",
@@ -136,6 +324,13 @@ pub static KNOWN_BROWSERS: &[BrowserMeta] = &[
         macos_bundle_ids: &["com.microsoft.edgemac.Beta"],
         windows_registry_keys: &["Microsoft Edge Beta"],
         linux_desktop_ids: &["microsoft-edge-beta"],
+        linux_executables: &["/opt/microsoft/msedge-beta/msedge"],
+        macos_user_data_dir: Some(UserDataDir::app_support("Microsoft Edge Beta")),
+        windows_user_data_dir: Some(UserDataDir::local_app_data("Microsoft/Edge Beta/User Data")),
+        linux_user_data_dir: Some(UserDataDir::xdg_config("microsoft-edge-beta")),
+        flatpak_id: None,
+        snap_name: None,
+        snap_user_data_dir: None,
     },
     BrowserMeta {
         id: "edge-dev",
@@ -144,6 +339,13 @@ pub static KNOWN_BROWSERS: &[BrowserMeta] = &[
         macos_bundle_ids: &["com.microsoft.edgemac.Dev"],
         windows_registry_keys: &["Microsoft Edge Dev"],
         linux_desktop_ids: &["microsoft-edge-dev"],
+        linux_executables: &["/opt/microsoft/msedge-dev/msedge"],
+        macos_user_data_dir: Some(UserDataDir::app_support("Microsoft Edge Dev")),
+        windows_user_data_dir: Some(UserDataDir::local_app_data("Microsoft/Edge Dev/User Data")),
+        linux_user_data_dir: Some(UserDataDir::xdg_config("microsoft-edge-dev")),
+        flatpak_id: Some("com.microsoft.EdgeDev"),
+        snap_name: None,
+        snap_user_data_dir: None,
     },
     BrowserMeta {
         id: "edge-canary",
@@ -152,6 +354,13 @@ pub static KNOWN_BROWSERS: &[BrowserMeta] = &[
         macos_bundle_ids: &["com.microsoft.edgemac.Canary"],
         windows_registry_keys: &["Microsoft Edge Canary"],
         linux_desktop_ids: &[], // Canary not available on Linux
+        linux_executables: &[],
+        macos_user_data_dir: Some(UserDataDir::app_support("Microsoft Edge Canary")),
+        windows_user_data_dir: Some(UserDataDir::local_app_data("Microsoft/Edge SxS/User Data")),
+        linux_user_data_dir: None,
+        flatpak_id: None,
+        snap_name: None,
+        snap_user_data_dir: None,
     },
     // =========================================================================
     // CHROMIUM FAMILY - Brave
@@ -163,6 +372,15 @@ pub static KNOWN_BROWSERS: &[BrowserMeta] = &[
         macos_bundle_ids: &["com.brave.Browser"],
         windows_registry_keys: &["BraveSoftware Brave-Browser"],
         linux_desktop_ids: &["brave-browser", "brave"],
+        linux_executables: &["/opt/brave.com/brave/brave"],
+        macos_user_data_dir: Some(UserDataDir::app_support("BraveSoftware/Brave-Browser")),
+        windows_user_data_dir: Some(UserDataDir::local_app_data(
+            "BraveSoftware/Brave-Browser/User Data",
+        )),
+        linux_user_data_dir: Some(UserDataDir::xdg_config("BraveSoftware/Brave-Browser")),
+        flatpak_id: Some("com.brave.Browser"),
+        snap_name: Some("brave"),
+        snap_user_data_dir: None,
     },
     BrowserMeta {
         id: "brave-beta",
@@ -171,6 +389,15 @@ pub static KNOWN_BROWSERS: &[BrowserMeta] = &[
         macos_bundle_ids: &["com.brave.Browser.beta"],
         windows_registry_keys: &["BraveSoftware Brave-Browser-Beta"],
         linux_desktop_ids: &["brave-browser-beta"],
+        linux_executables: &["/opt/brave.com/brave-beta/brave"],
+        macos_user_data_dir: Some(UserDataDir::app_support("BraveSoftware/Brave-Browser-Beta")),
+        windows_user_data_dir: Some(UserDataDir::local_app_data(
+            "BraveSoftware/Brave-Browser-Beta/User Data",
+        )),
+        linux_user_data_dir: Some(UserDataDir::xdg_config("BraveSoftware/Brave-Browser-Beta")),
+        flatpak_id: None,
+        snap_name: None,
+        snap_user_data_dir: None,
     },
     BrowserMeta {
         id: "brave-nightly",
@@ -179,6 +406,19 @@ pub static KNOWN_BROWSERS: &[BrowserMeta] = &[
         macos_bundle_ids: &["com.brave.Browser.nightly"],
         windows_registry_keys: &["BraveSoftware Brave-Browser-Nightly"],
         linux_desktop_ids: &["brave-browser-nightly"],
+        linux_executables: &["/opt/brave.com/brave-nightly/brave"],
+        macos_user_data_dir: Some(UserDataDir::app_support(
+            "BraveSoftware/Brave-Browser-Nightly",
+        )),
+        windows_user_data_dir: Some(UserDataDir::local_app_data(
+            "BraveSoftware/Brave-Browser-Nightly/User Data",
+        )),
+        linux_user_data_dir: Some(UserDataDir::xdg_config(
+            "BraveSoftware/Brave-Browser-Nightly",
+        )),
+        flatpak_id: None,
+        snap_name: None,
+        snap_user_data_dir: None,
     },
     // =========================================================================
     // CHROMIUM FAMILY - Arc (Single channel)
@@ -190,6 +430,13 @@ pub static KNOWN_BROWSERS: &[BrowserMeta] = &[
         macos_bundle_ids: &["company.thebrowser.Browser"],
         windows_registry_keys: &["Arc"],
         linux_desktop_ids: &[], // Not available on Linux
+        linux_executables: &[],
+        macos_user_data_dir: Some(UserDataDir::app_support("Arc/User Data")),
+        windows_user_data_dir: None,
+        linux_user_data_dir: None,
+        flatpak_id: None,
+        snap_name: None,
+        snap_user_data_dir: None,
     },
     // =========================================================================
     // CHROMIUM FAMILY - Vivaldi
@@ -201,6 +448,13 @@ pub static KNOWN_BROWSERS: &[BrowserMeta] = &[
         macos_bundle_ids: &["com.vivaldi.Vivaldi"],
         windows_registry_keys: &["Vivaldi"],
         linux_desktop_ids: &["vivaldi", "vivaldi-stable"],
+        linux_executables: &["/opt/vivaldi/vivaldi-bin"],
+        macos_user_data_dir: Some(UserDataDir::app_support("Vivaldi")),
+        windows_user_data_dir: Some(UserDataDir::local_app_data("Vivaldi/User Data")),
+        linux_user_data_dir: Some(UserDataDir::xdg_config("vivaldi")),
+        flatpak_id: Some("com.vivaldi.Vivaldi"),
+        snap_name: Some("vivaldi"),
+        snap_user_data_dir: None,
     },
     BrowserMeta {
         id: "vivaldi-snapshot",
@@ -209,6 +463,13 @@ pub static KNOWN_BROWSERS: &[BrowserMeta] = &[
         macos_bundle_ids: &["com.vivaldi.Vivaldi.snapshot"],
         windows_registry_keys: &["Vivaldi Snapshot"],
         linux_desktop_ids: &["vivaldi-snapshot"],
+        linux_executables: &["/opt/vivaldi-snapshot/vivaldi-bin"],
+        macos_user_data_dir: Some(UserDataDir::app_support("Vivaldi Snapshot")),
+        windows_user_data_dir: Some(UserDataDir::local_app_data("Vivaldi Snapshot/User Data")),
+        linux_user_data_dir: Some(UserDataDir::xdg_config("vivaldi-snapshot")),
+        flatpak_id: None,
+        snap_name: None,
+        snap_user_data_dir: None,
     },
     // =========================================================================
     // CHROMIUM FAMILY - Opera
@@ -220,6 +481,13 @@ pub static KNOWN_BROWSERS: &[BrowserMeta] = &[
         macos_bundle_ids: &["com.operasoftware.Opera"],
         windows_registry_keys: &["Opera Stable"],
         linux_desktop_ids: &["opera"],
+        linux_executables: &["opera"],
+        macos_user_data_dir: Some(UserDataDir::app_support("com.operasoftware.Opera")),
+        windows_user_data_dir: Some(UserDataDir::roaming_app_data("Opera Software/Opera Stable")),
+        linux_user_data_dir: Some(UserDataDir::xdg_config("opera")),
+        flatpak_id: Some("com.opera.Opera"),
+        snap_name: Some("opera"),
+        snap_user_data_dir: None,
     },
     BrowserMeta {
         id: "opera-beta",
@@ -228,6 +496,13 @@ pub static KNOWN_BROWSERS: &[BrowserMeta] = &[
         macos_bundle_ids: &["com.operasoftware.OperaNext"],
         windows_registry_keys: &["Opera Beta"],
         linux_desktop_ids: &["opera-beta"],
+        linux_executables: &["opera-beta"],
+        macos_user_data_dir: Some(UserDataDir::app_support("com.operasoftware.OperaNext")),
+        windows_user_data_dir: Some(UserDataDir::roaming_app_data("Opera Software/Opera Next")),
+        linux_user_data_dir: Some(UserDataDir::xdg_config("opera-beta")),
+        flatpak_id: None,
+        snap_name: None,
+        snap_user_data_dir: None,
     },
     BrowserMeta {
         id: "opera-developer",
@@ -236,6 +511,15 @@ pub static KNOWN_BROWSERS: &[BrowserMeta] = &[
         macos_bundle_ids: &["com.operasoftware.OperaDeveloper"],
         windows_registry_keys: &["Opera Developer"],
         linux_desktop_ids: &["opera-developer"],
+        linux_executables: &["opera-developer"],
+        macos_user_data_dir: Some(UserDataDir::app_support("com.operasoftware.OperaDeveloper")),
+        windows_user_data_dir: Some(UserDataDir::roaming_app_data(
+            "Opera Software/Opera Developer",
+        )),
+        linux_user_data_dir: Some(UserDataDir::xdg_config("opera-developer")),
+        flatpak_id: None,
+        snap_name: None,
+        snap_user_data_dir: None,
     },
     BrowserMeta {
         id: "opera-gx",
@@ -244,6 +528,15 @@ pub static KNOWN_BROWSERS: &[BrowserMeta] = &[
         macos_bundle_ids: &["com.operasoftware.OperaGX"],
         windows_registry_keys: &["Opera GX Stable"],
         linux_desktop_ids: &[], // Not available on Linux
+        linux_executables: &[],
+        macos_user_data_dir: Some(UserDataDir::app_support("com.operasoftware.OperaGX")),
+        windows_user_data_dir: Some(UserDataDir::roaming_app_data(
+            "Opera Software/Opera GX Stable",
+        )),
+        linux_user_data_dir: None,
+        flatpak_id: None,
+        snap_name: None,
+        snap_user_data_dir: None,
     },
     // =========================================================================
     // CHROMIUM FAMILY - Chromium (open source)
@@ -255,6 +548,13 @@ pub static KNOWN_BROWSERS: &[BrowserMeta] = &[
         macos_bundle_ids: &["org.chromium.Chromium"],
         windows_registry_keys: &["Chromium"],
         linux_desktop_ids: &["chromium", "chromium-browser"],
+        linux_executables: &["chromium", "chromium-browser"],
+        macos_user_data_dir: Some(UserDataDir::app_support("Chromium")),
+        windows_user_data_dir: Some(UserDataDir::local_app_data("Chromium/User Data")),
+        linux_user_data_dir: Some(UserDataDir::xdg_config("chromium")),
+        flatpak_id: Some("org.chromium.Chromium"),
+        snap_name: Some("chromium"),
+        snap_user_data_dir: Some(UserDataDir::snap_common("chromium")),
     },
     // =========================================================================
     // FIREFOX FAMILY - Mozilla Firefox
@@ -266,6 +566,13 @@ pub static KNOWN_BROWSERS: &[BrowserMeta] = &[
         macos_bundle_ids: &["org.mozilla.firefox"],
         windows_registry_keys: &["Firefox"],
         linux_desktop_ids: &["firefox"],
+        linux_executables: &["firefox", "firefox-bin"],
+        macos_user_data_dir: Some(UserDataDir::app_support("Firefox")),
+        windows_user_data_dir: Some(UserDataDir::roaming_app_data("Mozilla/Firefox")),
+        linux_user_data_dir: Some(UserDataDir::home(".mozilla/firefox")),
+        flatpak_id: Some("org.mozilla.firefox"),
+        snap_name: Some("firefox"),
+        snap_user_data_dir: Some(UserDataDir::snap_common(".mozilla/firefox")),
     },
     BrowserMeta {
         id: "firefox-beta",
@@ -274,6 +581,13 @@ pub static KNOWN_BROWSERS: &[BrowserMeta] = &[
         macos_bundle_ids: &["org.mozilla.firefoxbeta"],
         windows_registry_keys: &["Firefox Beta"],
         linux_desktop_ids: &["firefox-beta"],
+        linux_executables: &[],
+        macos_user_data_dir: Some(UserDataDir::app_support("Firefox")),
+        windows_user_data_dir: Some(UserDataDir::roaming_app_data("Mozilla/Firefox")),
+        linux_user_data_dir: Some(UserDataDir::home(".mozilla/firefox")),
+        flatpak_id: None,
+        snap_name: None,
+        snap_user_data_dir: None,
     },
     BrowserMeta {
         id: "firefox-dev",
@@ -282,6 +596,13 @@ pub static KNOWN_BROWSERS: &[BrowserMeta] = &[
         macos_bundle_ids: &["org.mozilla.firefoxdeveloperedition"],
         windows_registry_keys: &["Firefox Developer Edition"],
         linux_desktop_ids: &["firefox-developer-edition", "firefoxdeveloperedition"],
+        linux_executables: &[],
+        macos_user_data_dir: Some(UserDataDir::app_support("Firefox")),
+        windows_user_data_dir: Some(UserDataDir::roaming_app_data("Mozilla/Firefox")),
+        linux_user_data_dir: Some(UserDataDir::home(".mozilla/firefox")),
+        flatpak_id: None,
+        snap_name: None,
+        snap_user_data_dir: None,
     },
     BrowserMeta {
         id: "firefox-nightly",
@@ -290,6 +611,13 @@ pub static KNOWN_BROWSERS: &[BrowserMeta] = &[
         macos_bundle_ids: &["org.mozilla.nightly"],
         windows_registry_keys: &["Firefox Nightly"],
         linux_desktop_ids: &["firefox-nightly"],
+        linux_executables: &[],
+        macos_user_data_dir: Some(UserDataDir::app_support("Firefox")),
+        windows_user_data_dir: Some(UserDataDir::roaming_app_data("Mozilla/Firefox")),
+        linux_user_data_dir: Some(UserDataDir::home(".mozilla/firefox")),
+        flatpak_id: None,
+        snap_name: None,
+        snap_user_data_dir: None,
     },
     BrowserMeta {
         id: "firefox-esr",
@@ -298,6 +626,13 @@ pub static KNOWN_BROWSERS: &[BrowserMeta] = &[
         macos_bundle_ids: &["org.mozilla.firefoxesr"],
         windows_registry_keys: &["Firefox ESR"],
         linux_desktop_ids: &["firefox-esr"],
+        linux_executables: &["firefox-esr"],
+        macos_user_data_dir: Some(UserDataDir::app_support("Firefox")),
+        windows_user_data_dir: Some(UserDataDir::roaming_app_data("Mozilla/Firefox")),
+        linux_user_data_dir: Some(UserDataDir::home(".mozilla/firefox")),
+        flatpak_id: None,
+        snap_name: None,
+        snap_user_data_dir: None,
     },
     // =========================================================================
     // FIREFOX FAMILY - LibreWolf (privacy-focused fork)
@@ -309,6 +644,13 @@ pub static KNOWN_BROWSERS: &[BrowserMeta] = &[
         macos_bundle_ids: &["io.gitlab.LibreWolf"],
         windows_registry_keys: &["LibreWolf"],
         linux_desktop_ids: &["librewolf", "io.gitlab.librewolf"],
+        linux_executables: &["librewolf"],
+        macos_user_data_dir: Some(UserDataDir::app_support("librewolf")),
+        windows_user_data_dir: Some(UserDataDir::roaming_app_data("librewolf")),
+        linux_user_data_dir: Some(UserDataDir::home(".librewolf")),
+        flatpak_id: Some("io.gitlab.librewolf-community"),
+        snap_name: None,
+        snap_user_data_dir: None,
     },
     // =========================================================================
     // FIREFOX FAMILY - Waterfox
@@ -320,6 +662,13 @@ pub static KNOWN_BROWSERS: &[BrowserMeta] = &[
         macos_bundle_ids: &["net.waterfox.waterfox"],
         windows_registry_keys: &["Waterfox"],
         linux_desktop_ids: &["waterfox", "waterfox-current"],
+        linux_executables: &["waterfox"],
+        macos_user_data_dir: Some(UserDataDir::app_support("Waterfox")),
+        windows_user_data_dir: Some(UserDataDir::roaming_app_data("Waterfox")),
+        linux_user_data_dir: Some(UserDataDir::home(".waterfox")),
+        flatpak_id: Some("net.waterfox.waterfox"),
+        snap_name: None,
+        snap_user_data_dir: None,
     },
     // =========================================================================
     // FIREFOX FAMILY - Floorp
@@ -331,6 +680,13 @@ pub static KNOWN_BROWSERS: &[BrowserMeta] = &[
         macos_bundle_ids: &["one.ablaze.floorp"],
         windows_registry_keys: &["Floorp"],
         linux_desktop_ids: &["floorp", "one.ablaze.floorp"],
+        linux_executables: &["floorp"],
+        macos_user_data_dir: Some(UserDataDir::app_support("Floorp")),
+        windows_user_data_dir: Some(UserDataDir::roaming_app_data("Floorp")),
+        linux_user_data_dir: Some(UserDataDir::home(".floorp")),
+        flatpak_id: Some("one.ablaze.floorp"),
+        snap_name: None,
+        snap_user_data_dir: None,
     },
     // =========================================================================
     // WEBKIT FAMILY - Safari (macOS only)
@@ -342,6 +698,13 @@ pub static KNOWN_BROWSERS: &[BrowserMeta] = &[
         macos_bundle_ids: &["com.apple.Safari"],
         windows_registry_keys: &[], // Discontinued on Windows
         linux_desktop_ids: &[],     // Never available on Linux
+        linux_executables: &[],
+        macos_user_data_dir: Some(UserDataDir::library("Safari")),
+        windows_user_data_dir: None,
+        linux_user_data_dir: None,
+        flatpak_id: None,
+        snap_name: None,
+        snap_user_data_dir: None,
     },
     BrowserMeta {
         id: "safari-preview",
@@ -350,6 +713,13 @@ pub static KNOWN_BROWSERS: &[BrowserMeta] = &[
         macos_bundle_ids: &["com.apple.SafariTechnologyPreview"],
         windows_registry_keys: &[],
         linux_desktop_ids: &[],
+        linux_executables: &[],
+        macos_user_data_dir: Some(UserDataDir::library("SafariTechnologyPreview")),
+        windows_user_data_dir: None,
+        linux_user_data_dir: None,
+        flatpak_id: None,
+        snap_name: None,
+        snap_user_data_dir: None,
     },
     // =========================================================================
     // WEBKIT FAMILY - GNOME Web (Linux only)
@@ -361,6 +731,13 @@ pub static KNOWN_BROWSERS: &[BrowserMeta] = &[
         macos_bundle_ids: &[],
         windows_registry_keys: &[],
         linux_desktop_ids: &["org.gnome.Epiphany", "epiphany", "epiphany-browser"],
+        linux_executables: &["epiphany", "epiphany-browser"],
+        macos_user_data_dir: None,
+        windows_user_data_dir: None,
+        linux_user_data_dir: Some(UserDataDir::xdg_data("epiphany")),
+        flatpak_id: Some("org.gnome.Epiphany"),
+        snap_name: None,
+        snap_user_data_dir: None,
     },
 ];
 
@@ -466,6 +843,75 @@ pub fn find_by_desktop_id(desktop_id: &str) -> Option<&'static BrowserMeta> {
         .find(|meta| meta.linux_desktop_ids.contains(&desktop_id))
 }
 
+/// Find browser metadata by the executable of a running Linux process.
+///
+/// Full-path entries in [`BrowserMeta::linux_executables`] take precedence
+/// over file-name entries, so channel-specific installs are told apart.
+///
+/// # Example
+///
+/// ```
+/// use browserware_detect::registry::find_by_linux_executable;
+/// use std::path::Path;
+///
+/// let beta = find_by_linux_executable(Path::new("/opt/google/chrome-beta/chrome"));
+/// assert_eq!(beta.map(|m| m.id), Some("chrome-beta"));
+/// ```
+#[must_use]
+pub fn find_by_linux_executable(executable: &Path) -> Option<&'static BrowserMeta> {
+    let by_path = KNOWN_BROWSERS.iter().find(|meta| {
+        meta.linux_executables
+            .iter()
+            .any(|e| e.contains('/') && executable == Path::new(e))
+    });
+    if by_path.is_some() {
+        return by_path;
+    }
+
+    let file_name = executable.file_name()?.to_str()?;
+    KNOWN_BROWSERS.iter().find(|meta| {
+        meta.linux_executables
+            .iter()
+            .any(|e| !e.contains('/') && *e == file_name)
+    })
+}
+
+/// Find browser metadata by Flatpak application ID.
+///
+/// # Example
+///
+/// ```
+/// use browserware_detect::registry::find_by_flatpak_id;
+///
+/// if let Some(firefox) = find_by_flatpak_id("org.mozilla.firefox") {
+///     assert_eq!(firefox.id, "firefox");
+/// }
+/// ```
+#[must_use]
+pub fn find_by_flatpak_id(app_id: &str) -> Option<&'static BrowserMeta> {
+    KNOWN_BROWSERS
+        .iter()
+        .find(|meta| meta.flatpak_id == Some(app_id))
+}
+
+/// Find browser metadata by Snap package name.
+///
+/// # Example
+///
+/// ```
+/// use browserware_detect::registry::find_by_snap_name;
+///
+/// if let Some(chromium) = find_by_snap_name("chromium") {
+///     assert_eq!(chromium.id, "chromium");
+/// }
+/// ```
+#[must_use]
+pub fn find_by_snap_name(name: &str) -> Option<&'static BrowserMeta> {
+    KNOWN_BROWSERS
+        .iter()
+        .find(|meta| meta.snap_name == Some(name))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -572,6 +1018,60 @@ mod tests {
         assert!(chrome.available_on_linux());
     }
 
+    #[test]
+    fn find_by_linux_executable_prefers_full_paths() {
+        let stable = find_by_linux_executable(Path::new("/opt/google/chrome/chrome"));
+        assert_eq!(stable.unwrap().id, "chrome");
+
+        let dev = find_by_linux_executable(Path::new("/opt/google/chrome-unstable/chrome"));
+        assert_eq!(dev.unwrap().id, "chrome-dev");
+
+        let firefox = find_by_linux_executable(Path::new("/usr/lib64/firefox/firefox"));
+        assert_eq!(firefox.unwrap().id, "firefox");
+
+        // A bare "chrome" binary elsewhere is not enough to identify a channel
+        assert!(find_by_linux_executable(Path::new("/tmp/chrome")).is_none());
+    }
+
+    #[test]
+    fn sandbox_ids_are_unique() {
+        for meta in KNOWN_BROWSERS {
+            if let Some(app_id) = meta.flatpak_id {
+                assert_eq!(find_by_flatpak_id(app_id).unwrap().id, meta.id);
+            }
+            if let Some(name) = meta.snap_name {
+                assert_eq!(find_by_snap_name(name).unwrap().id, meta.id);
+            }
+        }
+    }
+
+    #[test]
+    fn sandboxed_browsers_have_linux_data_dir() {
+        for meta in KNOWN_BROWSERS {
+            if meta.flatpak_id.is_some() || meta.snap_name.is_some() {
+                assert!(
+                    meta.linux_user_data_dir.is_some(),
+                    "Browser '{}' is sandboxed but has no Linux data dir",
+                    meta.id
+                );
+            }
+            if meta.snap_user_data_dir.is_some() {
+                assert!(meta.snap_name.is_some(), "'{}' has no snap name", meta.id);
+            }
+        }
+    }
+
+    #[test]
+    fn chrome_channels_have_distinct_data_dirs() {
+        let stable = find_by_id("chrome").unwrap().linux_user_data_dir.unwrap();
+        let beta = find_by_id("chrome-beta")
+            .unwrap()
+            .linux_user_data_dir
+            .unwrap();
+        assert_eq!(stable.path, "google-chrome");
+        assert_eq!(beta.path, "google-chrome-beta");
+    }
+
     #[test]
     fn alternative_desktop_ids_work() {
         // Chrome has multiple desktop IDs
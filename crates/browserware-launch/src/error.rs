@@ -0,0 +1,151 @@
+//! Error types for browser launching.
+
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+/// Result type alias using the launch Error type.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Errors that can occur while launching a browser.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// Browser executable could not be started
+    #[error("failed to start {browser} ({}): {source}", .executable.display())]
+    Spawn {
+        /// Browser ID
+        browser: String,
+        /// Executable that was run
+        executable: PathBuf,
+        /// Underlying I/O error
+        source: std::io::Error,
+    },
+
+    /// Browser does not support a requested option
+    #[error("{browser} does not support {feature}")]
+    Unsupported {
+        /// Browser ID
+        browser: String,
+        /// The unsupported option, e.g. "guest mode"
+        feature: String,
+    },
+
+    /// Browser exited with a failure status right after starting
+    #[error("{browser} exited during startup ({status}){}", output_tail(.output))]
+    EarlyExit {
+        /// Browser ID
+        browser: String,
+        /// Exit status of the browser process
+        status: std::process::ExitStatus,
+        /// Output the browser wrote before exiting (possibly truncated)
+        output: String,
+    },
+
+    /// Browser process could not be waited for
+    #[error("failed to wait for {browser}: {source}")]
+    Wait {
+        /// Browser ID
+        browser: String,
+        /// Underlying I/O error
+        source: std::io::Error,
+    },
+
+    /// Launch log could not be opened
+    #[error("cannot open launch log {}: {source}", .path.display())]
+    Log {
+        /// Log file
+        path: PathBuf,
+        /// Underlying I/O error
+        source: std::io::Error,
+    },
+
+    /// Launch options contradict each other or the URLs
+    #[error("invalid launch options: {reason}")]
+    InvalidOptions {
+        /// Description of the problem
+        reason: String,
+    },
+}
+
+impl From<Error> for browserware_types::Error {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::Spawn {
+                browser,
+                executable,
+                source,
+            } => Self::LaunchFailed {
+                browser,
+                message: format!("could not run {}", executable.display()),
+                source: Some(Box::new(source)),
+            },
+            Error::EarlyExit {
+                browser,
+                status,
+                output,
+            } => Self::LaunchFailed {
+                browser,
+                message: format!("exited during startup ({status}){}", output_tail(&output)),
+                source: None,
+            },
+            Error::Wait { browser, source } => Self::LaunchFailed {
+                browser,
+                message: "could not wait for the browser process".to_string(),
+                source: Some(Box::new(source)),
+            },
+            Error::Log { path, source } => Self::io_at(path, source),
+            // An option combination the user chose, not a platform limitation
+            Error::Unsupported { .. } => Self::InvalidArgument {
+                argument: "launch options".to_string(),
+                message: err.to_string(),
+            },
+            Error::InvalidOptions { reason } => Self::InvalidArgument {
+                argument: "launch options".to_string(),
+                message: reason,
+            },
+        }
+    }
+}
+
+/// The last line of a browser's output, for error messages.
+fn output_tail(output: &str) -> String {
+    output
+        .lines()
+        .rev()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(|line| format!(": {line}"))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use browserware_types::ErrorCode;
+
+    #[test]
+    fn spawn_failure_keeps_source() {
+        let err: browserware_types::Error = Error::Spawn {
+            browser: "chrome".into(),
+            executable: "/usr/bin/google-chrome".into(),
+            source: std::io::Error::from(std::io::ErrorKind::NotFound),
+        }
+        .into();
+        assert_eq!(err.code(), ErrorCode::LaunchFailed);
+        assert!(std::error::Error::source(&err).is_some());
+    }
+
+    #[test]
+    fn unsupported_option_is_a_usage_error() {
+        let err: browserware_types::Error = Error::Unsupported {
+            browser: "firefox".into(),
+            feature: "app mode".into(),
+        }
+        .into();
+        assert_eq!(err.code(), ErrorCode::InvalidArgument);
+        assert_eq!(
+            err.to_string(),
+            "invalid launch options: firefox does not support app mode"
+        );
+    }
+}
@@ -1,10 +1,63 @@
 //! Browser launching for the browserware ecosystem.
 //!
-//! # Status
+//! A [`LaunchPlan`] is the command line that opens URLs in a browser, built
+//! from the browser's family and variant. Plans are plain values, separate
+//! from spawning, so they can be inspected, serialized and tested.
+//! [`LaunchOptions`] add launch modes (new tab or window, private, guest,
+//! app, kiosk) and window geometry; combinations a browser cannot honour
+//! are errors rather than being dropped.
 //!
-//! This crate is under development. See the repository for progress.
+//! Flatpak and Snap installs are run through `flatpak run` and `snap run`,
+//! with Flatpak profile paths translated to the paths the sandbox sees.
+//! Local file URLs the sandbox cannot reach are listed in
+//! [`LaunchPlan::warnings`].
+//!
+//! [`spawn`] starts a plan detached from the calling process: in a new
+//! session, with stdin from `/dev/null` and output discarded or appended
+//! to a log file, so the browser survives the terminal it was opened from.
+//! The returned [`LaunchHandle`] exposes the process ID and exit status;
+//! with a crash window, a browser failing during startup is reported as
+//! [`Error::EarlyExit`] with its output.
+//!
+//! # Example
+//!
+//! ```
+//! use browserware_launch::LaunchPlan;
+//! use browserware_types::{Browser, BrowserVariant, LaunchMode};
+//!
+//! let chrome = Browser::new("chrome", "Google Chrome", "/usr/bin/google-chrome".into())
+//!     .with_variant(BrowserVariant::chromium_stable());
+//! let plan = LaunchPlan::builder(&chrome)
+//!     .with_mode(LaunchMode::NewWindow)
+//!     .with_url("https://example.com".parse()?)
+//!     .build()?;
+//! assert_eq!(plan.args, ["--new-window", "--", "https://example.com/"]);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! # Browser Support
+//!
+//! | Browser  | Profile | Modes | Window |
+//! |----------|---------|-------|--------|
+//! | Chromium | `--profile-directory=<dir>`, `--user-data-dir=<path>` | all (guest without a profile) | size and position |
+//! | Firefox  | `--profile <path>` or `-P <name>` | new tab/window, private, kiosk | size |
+//! | Epiphany | `--profile=<path>` | new tab/window, private, app (web app profiles) | - |
+//! | Others   | - | new tab | - |
 
-#![forbid(unsafe_code)]
+#![deny(unsafe_code)]
 #![warn(missing_docs)]
 
-// TODO: Implement in Milestone 3
+mod error;
+mod options;
+mod plan;
+mod sandbox;
+mod spawn;
+// Allow unsafe code for detaching spawned browsers between fork and exec
+#[cfg(unix)]
+#[allow(unsafe_code)]
+mod sys;
+
+pub use error::{Error, Result};
+pub use options::LaunchOptions;
+pub use plan::{LaunchPlan, LaunchPlanBuilder};
+pub use spawn::{DEFAULT_CRASH_WINDOW, LaunchHandle, SpawnOptions, spawn};
@@ -0,0 +1,132 @@
+//! How a browser window is opened.
+
+use browserware_types::{LaunchMode, WindowGeometry};
+use serde::{Deserialize, Serialize};
+
+/// Launch modes and window geometry for a [`LaunchPlan`](crate::LaunchPlan).
+///
+/// Modes form a set: each is present at most once, and new tab and new
+/// window replace each other. Modes combine where browsers allow it, e.g.
+/// private with kiosk.
+///
+/// # Example
+///
+/// ```
+/// use browserware_launch::LaunchOptions;
+/// use browserware_types::{LaunchMode, WindowGeometry};
+///
+/// let options = LaunchOptions::new()
+///     .with_mode(LaunchMode::NewTab)
+///     .with_mode(LaunchMode::Private)
+///     .with_mode(LaunchMode::NewWindow)
+///     .with_window(WindowGeometry::new().with_size(1280, 800));
+/// assert_eq!(options.modes(), [LaunchMode::NewWindow, LaunchMode::Private]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "RawLaunchOptions")]
+pub struct LaunchOptions {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    modes: Vec<LaunchMode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    window: Option<WindowGeometry>,
+}
+
+/// [`LaunchOptions`] as written, before the modes are normalized.
+#[derive(Deserialize)]
+struct RawLaunchOptions {
+    #[serde(default)]
+    modes: Vec<LaunchMode>,
+    #[serde(default)]
+    window: Option<WindowGeometry>,
+}
+
+impl From<RawLaunchOptions> for LaunchOptions {
+    fn from(raw: RawLaunchOptions) -> Self {
+        let options = raw.modes.into_iter().fold(Self::new(), Self::with_mode);
+        Self {
+            window: raw.window,
+            ..options
+        }
+    }
+}
+
+impl LaunchOptions {
+    /// Options that leave everything to the browser.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            modes: Vec::new(),
+            window: None,
+        }
+    }
+
+    /// Add a launch mode.
+    #[must_use]
+    pub fn with_mode(mut self, mode: LaunchMode) -> Self {
+        let replaced = match mode {
+            LaunchMode::NewTab => Some(LaunchMode::NewWindow),
+            LaunchMode::NewWindow => Some(LaunchMode::NewTab),
+            _ => None,
+        };
+        self.modes.retain(|m| Some(*m) != replaced && *m != mode);
+        self.modes.push(mode);
+        self.modes.sort_by_key(|m| *m as u8);
+        self
+    }
+
+    /// Set the window size and position.
+    #[must_use]
+    pub const fn with_window(mut self, window: WindowGeometry) -> Self {
+        self.window = Some(window);
+        self
+    }
+
+    /// The launch modes, in declaration order of [`LaunchMode`].
+    #[must_use]
+    pub fn modes(&self) -> &[LaunchMode] {
+        &self.modes
+    }
+
+    /// Whether `mode` is set.
+    #[must_use]
+    pub fn contains(&self, mode: LaunchMode) -> bool {
+        self.modes.contains(&mode)
+    }
+
+    /// The window geometry, if set.
+    #[must_use]
+    pub const fn window(&self) -> Option<WindowGeometry> {
+        self.window
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deserialize_normalizes_modes() {
+        let options: LaunchOptions = serde_json::from_str(
+            r#"{"modes": ["private", "new-tab", "private", "new-window"], "window": "800x600"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            options.modes(),
+            [LaunchMode::NewWindow, LaunchMode::Private]
+        );
+        assert_eq!(
+            options.window(),
+            Some(WindowGeometry::new().with_size(800, 600))
+        );
+
+        let json = serde_json::to_string(&options).unwrap();
+        assert_eq!(
+            serde_json::from_str::<LaunchOptions>(&json).unwrap(),
+            options
+        );
+        assert_eq!(
+            serde_json::from_str::<LaunchOptions>("{}").unwrap(),
+            LaunchOptions::new()
+        );
+    }
+}
@@ -0,0 +1,948 @@
+//! Command lines for launching a browser.
+//!
+//! A [`LaunchPlan`] is the program and arguments that open URLs in a browser,
+//! computed from the browser's family and variant without running anything.
+//!
+//! URLs are taken as parsed [`Url`]s, whose serialization always starts
+//! with a scheme, so no URL can be mistaken for a command-line flag.
+//! Chromium and Epiphany additionally get a `--` separator before them.
+//!
+//! Flatpak and Snap browsers are run through `flatpak run` and `snap run`,
+//! with paths translated to what the sandbox sees. Local files the sandbox
+//! cannot reach are reported in [`LaunchPlan::warnings`].
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use browserware_types::{
+    Browser, BrowserFamily, BrowserVariant, LaunchMode, Profile, WindowGeometry,
+};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::sandbox::Sandbox;
+use crate::{Error, LaunchOptions, Result};
+
+/// The program and arguments for one browser invocation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LaunchPlan {
+    /// Browser ID
+    pub browser: String,
+    /// Program to run
+    pub program: PathBuf,
+    /// Arguments, in order
+    pub args: Vec<String>,
+    /// Problems the browser may run into, e.g. local files outside its sandbox
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+impl LaunchPlan {
+    /// Start building a plan for `browser`.
+    pub fn builder(browser: &Browser) -> LaunchPlanBuilder {
+        LaunchPlanBuilder::new(browser)
+    }
+
+    /// A [`Command`] that runs this plan, ready to be configured and spawned.
+    #[must_use]
+    pub fn command(&self) -> Command {
+        let mut command = Command::new(&self.program);
+        command.args(&self.args);
+        command
+    }
+}
+
+/// How the profile to open was chosen.
+#[derive(Debug, Clone)]
+enum Selection {
+    /// A profile directory
+    Profile(Profile),
+    /// A profile name known to the browser
+    Name(String),
+}
+
+/// Builder for a [`LaunchPlan`].
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct LaunchPlanBuilder {
+    browser: String,
+    variant: BrowserVariant,
+    program: PathBuf,
+    selection: Option<Selection>,
+    user_data_dir: Option<PathBuf>,
+    default_user_data_dir: Option<PathBuf>,
+    options: LaunchOptions,
+    urls: Vec<Url>,
+    sandbox: Option<Sandbox>,
+    home: Option<PathBuf>,
+}
+
+impl LaunchPlanBuilder {
+    /// Start building a plan for `browser`.
+    ///
+    /// Without further options the plan opens the browser's default profile
+    /// the way the browser itself chooses. For Flatpak installs, the
+    /// exported wrapper script is read for the branch and architecture.
+    pub fn new(browser: &Browser) -> Self {
+        Self {
+            browser: browser.id.0.clone(),
+            variant: browser.variant,
+            program: browser.executable.clone(),
+            selection: None,
+            user_data_dir: None,
+            default_user_data_dir: browserware_detect::user_data_dir(browser),
+            options: LaunchOptions::new(),
+            urls: Vec::new(),
+            sandbox: Sandbox::for_browser(browser),
+            home: std::env::var_os("HOME").map(PathBuf::from),
+        }
+    }
+
+    /// Open `profile`.
+    ///
+    /// Chromium selects it with `--profile-directory`, adding
+    /// `--user-data-dir` for profiles outside the browser's own data
+    /// directory (such as ephemeral profiles); a profile that is the user
+    /// data directory itself needs neither. Firefox selects it with
+    /// `--profile <path>` and Epiphany with `--profile=<path>` (except for
+    /// its default profile, which needs no flag).
+    pub fn with_profile(mut self, profile: &Profile) -> Self {
+        self.selection = Some(Selection::Profile(profile.clone()));
+        self
+    }
+
+    /// Open the Firefox profile called `name` (`-P <name>`).
+    pub fn with_profile_name(mut self, name: impl Into<String>) -> Self {
+        self.selection = Some(Selection::Name(name.into()));
+        self
+    }
+
+    /// Use `dir` as the Chromium user data directory (`--user-data-dir`).
+    pub fn with_user_data_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.user_data_dir = Some(dir.into());
+        self
+    }
+
+    /// Replace the launch options.
+    pub fn with_options(mut self, options: LaunchOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Add a launch mode (see [`LaunchOptions::with_mode`]).
+    pub fn with_mode(mut self, mode: LaunchMode) -> Self {
+        self.options = self.options.with_mode(mode);
+        self
+    }
+
+    /// Open `url`.
+    pub fn with_url(mut self, url: Url) -> Self {
+        self.urls.push(url);
+        self
+    }
+
+    /// Open each of `urls`, in order.
+    pub fn with_urls(mut self, urls: impl IntoIterator<Item = Url>) -> Self {
+        self.urls.extend(urls);
+        self
+    }
+
+    /// Compute the command line.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::Unsupported`] if the browser cannot honour an option, e.g.
+    ///   Chromium guest mode with a profile or Firefox app mode
+    /// - [`Error::InvalidOptions`] for options no browser can honour, e.g.
+    ///   private guest mode or app mode with several URLs
+    pub fn build(self) -> Result<LaunchPlan> {
+        if self.options.contains(LaunchMode::Private) && self.options.contains(LaunchMode::Guest) {
+            return Err(invalid_options(
+                "private and guest modes cannot be combined",
+            ));
+        }
+        let args = match self.variant {
+            BrowserVariant::Chromium(_) | BrowserVariant::Single(BrowserFamily::Chromium) => {
+                self.chromium_args()?
+            }
+            BrowserVariant::Firefox(_) | BrowserVariant::Single(BrowserFamily::Firefox) => {
+                self.firefox_args()?
+            }
+            BrowserVariant::Single(BrowserFamily::WebKit) => self.epiphany_args()?,
+            // Safari and unknown browsers take nothing but URLs
+            BrowserVariant::WebKit(_) | BrowserVariant::Single(BrowserFamily::Other) => {
+                self.plain_args()?
+            }
+        };
+        let (program, args, warnings) = match &self.sandbox {
+            Some(sandbox) => sandbox.wrap(args, &self.urls, self.home.as_deref()),
+            None => (self.program, args, Vec::new()),
+        };
+        for warning in &warnings {
+            tracing::warn!("{warning}");
+        }
+        Ok(LaunchPlan {
+            browser: self.browser,
+            program,
+            args,
+            warnings,
+        })
+    }
+
+    fn chromium_args(&self) -> Result<Vec<String>> {
+        let mut args = Vec::new();
+        if let Some(dir) = &self.user_data_dir {
+            args.push(format!("--user-data-dir={}", self.path_arg(dir)));
+        }
+        match &self.selection {
+            Some(Selection::Profile(_)) if self.options.contains(LaunchMode::Guest) => {
+                return Err(self.unsupported("guest mode with a profile"));
+            }
+            Some(Selection::Profile(profile))
+                if self
+                    .user_data_dir
+                    .as_ref()
+                    .or(self.default_user_data_dir.as_ref())
+                    == Some(&profile.path) =>
+            {
+                // Single-profile browsers (Opera) keep the profile in the
+                // user data directory itself, which needs no selection
+            }
+            Some(Selection::Profile(profile)) => {
+                if self.user_data_dir.is_none()
+                    && let Some(parent) = profile.path.parent()
+                    && self.default_user_data_dir.as_deref() != Some(parent)
+                {
+                    args.push(format!("--user-data-dir={}", self.path_arg(parent)));
+                }
+                // The directory name, which for ephemeral profiles differs
+                // from the profile ID
+                let dir = profile.path.file_name().map_or_else(
+                    || profile.id.0.clone(),
+                    |n| n.to_string_lossy().into_owned(),
+                );
+                args.push(format!("--profile-directory={dir}"));
+            }
+            Some(Selection::Name(_)) => return Err(self.unsupported("profile selection by name")),
+            None => {}
+        }
+        for mode in self.options.modes() {
+            match mode {
+                LaunchMode::NewTab | LaunchMode::App => {}
+                LaunchMode::NewWindow => args.push("--new-window".to_string()),
+                LaunchMode::Private => args.push("--incognito".to_string()),
+                LaunchMode::Guest => args.push("--guest".to_string()),
+                LaunchMode::Kiosk => args.push("--kiosk".to_string()),
+            }
+        }
+        if let Some(WindowGeometry { size, position }) = self.options.window() {
+            if let Some((width, height)) = size {
+                args.push(format!("--window-size={width},{height}"));
+            }
+            if let Some((x, y)) = position {
+                args.push(format!("--window-position={x},{y}"));
+            }
+        }
+        if self.options.contains(LaunchMode::App) {
+            let [url] = self.urls.as_slice() else {
+                return Err(invalid_options("app mode opens exactly one URL"));
+            };
+            args.push(format!("--app={url}"));
+        } else {
+            push_separated_urls(&mut args, &self.urls);
+        }
+        Ok(args)
+    }
+
+    fn firefox_args(&self) -> Result<Vec<String>> {
+        self.reject_user_data_dir()?;
+        let mut args = Vec::new();
+        match &self.selection {
+            Some(Selection::Profile(profile)) => {
+                args.push("--profile".to_string());
+                args.push(self.path_arg(&profile.path));
+            }
+            Some(Selection::Name(name)) => {
+                args.push("-P".to_string());
+                args.push(name.clone());
+            }
+            None => {}
+        }
+        for &mode in self.options.modes() {
+            match mode {
+                LaunchMode::Guest | LaunchMode::App => return Err(self.unsupported_mode(mode)),
+                LaunchMode::Kiosk => args.push("--kiosk".to_string()),
+                LaunchMode::NewTab | LaunchMode::NewWindow | LaunchMode::Private => {}
+            }
+        }
+        if let Some(WindowGeometry { size, position }) = self.options.window() {
+            if position.is_some() {
+                return Err(self.unsupported("window position"));
+            }
+            if let Some((width, height)) = size {
+                args.extend([
+                    "-width".to_string(),
+                    width.to_string(),
+                    "-height".to_string(),
+                    height.to_string(),
+                ]);
+            }
+        }
+
+        let urls = self.urls.iter().map(|url| url.as_str().to_string());
+        if self.options.contains(LaunchMode::Private) {
+            // Every URL gets its own flag, so none opens outside private browsing
+            if self.urls.is_empty() {
+                args.push("-private-window".to_string());
+            }
+            for url in urls {
+                args.push("-private-window".to_string());
+                args.push(url);
+            }
+        } else if self.options.contains(LaunchMode::NewWindow) {
+            // `-new-window` takes at most one URL; the rest join it as tabs
+            let mut urls = urls;
+            args.push("-new-window".to_string());
+            args.extend(urls.next());
+            for url in urls {
+                args.push("-new-tab".to_string());
+                args.push(url);
+            }
+        } else if self.options.contains(LaunchMode::NewTab) {
+            for url in urls {
+                args.push("-new-tab".to_string());
+                args.push(url);
+            }
+        } else {
+            args.extend(urls);
+        }
+        Ok(args)
+    }
+
+    fn epiphany_args(&self) -> Result<Vec<String>> {
+        self.reject_user_data_dir()?;
+        let private = self.options.contains(LaunchMode::Private);
+        let mut args = Vec::new();
+        let mut web_app = false;
+        match &self.selection {
+            // Incognito mode reads the profile it starts from, so even the
+            // default one is named
+            Some(Selection::Profile(profile)) if private || !profile.is_default => {
+                args.push(format!("--profile={}", self.path_arg(&profile.path)));
+                web_app = !profile.is_default;
+            }
+            Some(Selection::Profile(_)) | None => {}
+            Some(Selection::Name(_)) => return Err(self.unsupported("profile selection by name")),
+        }
+        for &mode in self.options.modes() {
+            match mode {
+                LaunchMode::NewTab => args.push("--new-tab".to_string()),
+                LaunchMode::NewWindow => args.push("--new-window".to_string()),
+                LaunchMode::Private if self.selection.is_some() => {
+                    args.push("--incognito-mode".to_string());
+                }
+                LaunchMode::Private => args.push("--private-instance".to_string()),
+                LaunchMode::App if private => {
+                    return Err(self.unsupported("app mode in a private window"));
+                }
+                LaunchMode::App if web_app => args.push("--application-mode".to_string()),
+                LaunchMode::App => {
+                    return Err(self.unsupported("app mode without a web app profile"));
+                }
+                LaunchMode::Guest | LaunchMode::Kiosk => return Err(self.unsupported_mode(mode)),
+            }
+        }
+        if self.options.window().is_some() {
+            return Err(self.unsupported("window geometry"));
+        }
+        push_separated_urls(&mut args, &self.urls);
+        Ok(args)
+    }
+
+    fn plain_args(&self) -> Result<Vec<String>> {
+        self.reject_user_data_dir()?;
+        if self.selection.is_some() {
+            return Err(self.unsupported("profile selection"));
+        }
+        if let Some(&mode) = self
+            .options
+            .modes()
+            .iter()
+            .find(|&&mode| mode != LaunchMode::NewTab)
+        {
+            return Err(self.unsupported_mode(mode));
+        }
+        if self.options.window().is_some() {
+            return Err(self.unsupported("window geometry"));
+        }
+        Ok(self
+            .urls
+            .iter()
+            .map(|url| url.as_str().to_string())
+            .collect())
+    }
+
+    /// `path` as an argument, as the browser sees it from its sandbox.
+    fn path_arg(&self, path: &Path) -> String {
+        let path = self
+            .sandbox
+            .as_ref()
+            .map_or_else(|| path.to_path_buf(), |sandbox| sandbox.sandbox_path(path));
+        path.to_string_lossy().into_owned()
+    }
+
+    fn reject_user_data_dir(&self) -> Result<()> {
+        match self.user_data_dir {
+            Some(_) => Err(self.unsupported("user data directories")),
+            None => Ok(()),
+        }
+    }
+
+    fn unsupported_mode(&self, mode: LaunchMode) -> Error {
+        self.unsupported(&format!("{mode} mode"))
+    }
+
+    fn unsupported(&self, feature: &str) -> Error {
+        Error::Unsupported {
+            browser: self.browser.clone(),
+            feature: feature.to_string(),
+        }
+    }
+}
+
+fn invalid_options(reason: &str) -> Error {
+    Error::InvalidOptions {
+        reason: reason.to_string(),
+    }
+}
+
+/// Append `urls` after a `--` separator, so they are never parsed as flags.
+fn push_separated_urls(args: &mut Vec<String>, urls: &[Url]) {
+    if !urls.is_empty() {
+        args.push("--".to_string());
+        args.extend(urls.iter().map(|url| url.as_str().to_string()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use browserware_types::{ChromiumChannel, FirefoxChannel, Packaging, WebKitChannel};
+
+    fn browser(variant: BrowserVariant) -> Browser {
+        Browser::new("test", "Test", "/usr/bin/test-browser".into()).with_variant(variant)
+    }
+
+    fn chrome() -> Browser {
+        browser(BrowserVariant::Chromium(ChromiumChannel::Stable))
+    }
+
+    fn firefox() -> Browser {
+        browser(BrowserVariant::Firefox(FirefoxChannel::Stable))
+    }
+
+    fn epiphany() -> Browser {
+        browser(BrowserVariant::Single(BrowserFamily::WebKit))
+    }
+
+    fn url(s: &str) -> Url {
+        Url::parse(s).unwrap()
+    }
+
+    fn args(builder: LaunchPlanBuilder) -> Vec<String> {
+        builder.build().unwrap().args
+    }
+
+    #[test]
+    fn chromium_selects_profile_directory() {
+        let profile = Profile::new(
+            "Profile 1",
+            "Work",
+            "/home/u/.config/google-chrome/Profile 1".into(),
+            BrowserFamily::Chromium,
+        );
+        let plan = LaunchPlan::builder(&chrome())
+            .with_user_data_dir("/tmp/udd")
+            .with_profile(&profile)
+            .with_mode(LaunchMode::NewWindow)
+            .with_url(url("https://example.com/"))
+            .build()
+            .unwrap();
+        assert_eq!(plan.program, PathBuf::from("/usr/bin/test-browser"));
+        assert_eq!(
+            plan.args,
+            [
+                "--user-data-dir=/tmp/udd",
+                "--profile-directory=Profile 1",
+                "--new-window",
+                "--",
+                "https://example.com/",
+            ]
+        );
+    }
+
+    #[test]
+    fn chromium_uses_directory_name_over_profile_id() {
+        // Ephemeral profiles are named after their user data directory
+        let profile = Profile::new(
+            "test-1234",
+            "Ephemeral",
+            "/tmp/test-1234/Default".into(),
+            BrowserFamily::Chromium,
+        );
+        let plan = args(LaunchPlan::builder(&chrome()).with_profile(&profile));
+        assert_eq!(
+            plan,
+            [
+                "--user-data-dir=/tmp/test-1234",
+                "--profile-directory=Default"
+            ]
+        );
+
+        // Profiles in the browser's own data directory need no flag
+        let mut builder = LaunchPlan::builder(&chrome()).with_profile(&profile);
+        builder.default_user_data_dir = Some("/tmp/test-1234".into());
+        assert_eq!(args(builder), ["--profile-directory=Default"]);
+    }
+
+    #[test]
+    fn chromium_profile_in_user_data_dir_needs_no_profile_directory() {
+        // Opera-style: the only profile is the user data directory itself
+        let profile = Profile::new(
+            "Default",
+            "Default",
+            "/home/u/.config/opera".into(),
+            BrowserFamily::Chromium,
+        );
+        let mut builder = LaunchPlan::builder(&chrome()).with_profile(&profile);
+        builder.default_user_data_dir = Some("/home/u/.config/opera".into());
+        assert!(args(builder).is_empty());
+
+        let builder = LaunchPlan::builder(&chrome())
+            .with_user_data_dir("/home/u/.config/opera")
+            .with_profile(&profile);
+        assert_eq!(args(builder), ["--user-data-dir=/home/u/.config/opera"]);
+    }
+
+    #[test]
+    fn firefox_selects_profile_by_path_or_name() {
+        let profile = Profile::new(
+            "abc.default",
+            "default",
+            "/home/u/.mozilla/firefox/abc.default".into(),
+            BrowserFamily::Firefox,
+        );
+        assert_eq!(
+            args(LaunchPlan::builder(&firefox()).with_profile(&profile)),
+            ["--profile", "/home/u/.mozilla/firefox/abc.default"]
+        );
+        assert_eq!(
+            args(LaunchPlan::builder(&firefox()).with_profile_name("work")),
+            ["-P", "work"]
+        );
+    }
+
+    #[test]
+    fn firefox_modes_pair_each_url_with_a_flag() {
+        let urls = [url("https://a.example/"), url("https://b.example/")];
+        assert_eq!(
+            args(LaunchPlan::builder(&firefox()).with_urls(urls.clone())),
+            ["https://a.example/", "https://b.example/"]
+        );
+        assert_eq!(
+            args(
+                LaunchPlan::builder(&firefox())
+                    .with_mode(LaunchMode::NewTab)
+                    .with_urls(urls.clone())
+            ),
+            [
+                "-new-tab",
+                "https://a.example/",
+                "-new-tab",
+                "https://b.example/"
+            ]
+        );
+        assert_eq!(
+            args(
+                LaunchPlan::builder(&firefox())
+                    .with_mode(LaunchMode::NewWindow)
+                    .with_urls(urls)
+            ),
+            [
+                "-new-window",
+                "https://a.example/",
+                "-new-tab",
+                "https://b.example/"
+            ]
+        );
+        assert_eq!(
+            args(LaunchPlan::builder(&firefox()).with_mode(LaunchMode::NewWindow)),
+            ["-new-window"]
+        );
+    }
+
+    #[test]
+    fn epiphany_passes_profile_except_default() {
+        let web_app = Profile::new(
+            "org.gnome.Epiphany.WebApp_abc",
+            "App",
+            "/home/u/.local/share/org.gnome.Epiphany.WebApp_abc".into(),
+            BrowserFamily::WebKit,
+        );
+        let default = Profile::new(
+            "default",
+            "Default",
+            "/home/u/.local/share/epiphany".into(),
+            BrowserFamily::WebKit,
+        )
+        .with_default(true);
+        assert_eq!(
+            args(
+                LaunchPlan::builder(&epiphany())
+                    .with_profile(&web_app)
+                    .with_url(url("https://example.com/"))
+            ),
+            [
+                "--profile=/home/u/.local/share/org.gnome.Epiphany.WebApp_abc",
+                "--",
+                "https://example.com/",
+            ]
+        );
+        assert!(args(LaunchPlan::builder(&epiphany()).with_profile(&default)).is_empty());
+    }
+
+    #[test]
+    fn other_browsers_get_plain_urls() {
+        let other = browser(BrowserVariant::Single(BrowserFamily::Other));
+        let safari = browser(BrowserVariant::WebKit(WebKitChannel::Stable));
+        for browser in [other, safari] {
+            assert_eq!(
+                args(LaunchPlan::builder(&browser).with_url(url("https://example.com/"))),
+                ["https://example.com/"]
+            );
+            let profile = Profile::new("p", "p", "/p".into(), browser.family());
+            let err = LaunchPlan::builder(&browser)
+                .with_profile(&profile)
+                .build()
+                .unwrap_err();
+            assert!(matches!(err, Error::Unsupported { .. }));
+        }
+    }
+
+    #[test]
+    fn chromium_modes_and_window() {
+        let options = LaunchOptions::new()
+            .with_mode(LaunchMode::Private)
+            .with_mode(LaunchMode::Kiosk)
+            .with_window(
+                WindowGeometry::new()
+                    .with_size(800, 600)
+                    .with_position(10, -20),
+            );
+        assert_eq!(
+            args(
+                LaunchPlan::builder(&chrome())
+                    .with_options(options)
+                    .with_url(url("https://example.com/"))
+            ),
+            [
+                "--incognito",
+                "--kiosk",
+                "--window-size=800,600",
+                "--window-position=10,-20",
+                "--",
+                "https://example.com/",
+            ]
+        );
+        assert_eq!(
+            args(
+                LaunchPlan::builder(&chrome())
+                    .with_mode(LaunchMode::App)
+                    .with_url(url("https://app.example/"))
+            ),
+            ["--app=https://app.example/"]
+        );
+        assert_eq!(
+            args(LaunchPlan::builder(&chrome()).with_mode(LaunchMode::Guest)),
+            ["--guest"]
+        );
+    }
+
+    #[test]
+    fn chromium_rejects_guest_profile_and_multi_url_apps() {
+        let profile = Profile::new(
+            "Default",
+            "Me",
+            "/c/Default".into(),
+            BrowserFamily::Chromium,
+        );
+        let err = LaunchPlan::builder(&chrome())
+            .with_profile(&profile)
+            .with_mode(LaunchMode::Guest)
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "test does not support guest mode with a profile"
+        );
+
+        for urls in [
+            vec![],
+            vec![url("https://a.example/"), url("https://b.example/")],
+        ] {
+            let err = LaunchPlan::builder(&chrome())
+                .with_mode(LaunchMode::App)
+                .with_urls(urls)
+                .build()
+                .unwrap_err();
+            assert!(matches!(err, Error::InvalidOptions { .. }), "{err}");
+        }
+    }
+
+    #[test]
+    fn firefox_private_kiosk_and_size() {
+        let options = LaunchOptions::new()
+            .with_mode(LaunchMode::NewWindow)
+            .with_mode(LaunchMode::Private)
+            .with_mode(LaunchMode::Kiosk)
+            .with_window(WindowGeometry::new().with_size(800, 600));
+        assert_eq!(
+            args(
+                LaunchPlan::builder(&firefox())
+                    .with_options(options)
+                    .with_urls([url("https://a.example/"), url("https://b.example/")])
+            ),
+            [
+                "--kiosk",
+                "-width",
+                "800",
+                "-height",
+                "600",
+                "-private-window",
+                "https://a.example/",
+                "-private-window",
+                "https://b.example/",
+            ]
+        );
+        assert_eq!(
+            args(LaunchPlan::builder(&firefox()).with_mode(LaunchMode::Private)),
+            ["-private-window"]
+        );
+    }
+
+    #[test]
+    fn firefox_rejects_app_guest_and_position() {
+        for mode in [LaunchMode::App, LaunchMode::Guest] {
+            let err = LaunchPlan::builder(&firefox())
+                .with_mode(mode)
+                .with_url(url("https://example.com/"))
+                .build()
+                .unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!("test does not support {mode} mode")
+            );
+        }
+        let err = LaunchPlan::builder(&firefox())
+            .with_options(
+                LaunchOptions::new().with_window(WindowGeometry::new().with_position(0, 0)),
+            )
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, Error::Unsupported { .. }));
+    }
+
+    #[test]
+    fn epiphany_private_and_app_modes() {
+        let default = Profile::new(
+            "default",
+            "Default",
+            "/home/u/.local/share/epiphany".into(),
+            BrowserFamily::WebKit,
+        )
+        .with_default(true);
+        let web_app = Profile::new(
+            "org.gnome.Epiphany.WebApp_abc",
+            "App",
+            "/home/u/.local/share/org.gnome.Epiphany.WebApp_abc".into(),
+            BrowserFamily::WebKit,
+        );
+
+        assert_eq!(
+            args(LaunchPlan::builder(&epiphany()).with_mode(LaunchMode::Private)),
+            ["--private-instance"]
+        );
+        assert_eq!(
+            args(
+                LaunchPlan::builder(&epiphany())
+                    .with_profile(&default)
+                    .with_mode(LaunchMode::Private)
+            ),
+            [
+                "--profile=/home/u/.local/share/epiphany",
+                "--incognito-mode"
+            ]
+        );
+        assert_eq!(
+            args(
+                LaunchPlan::builder(&epiphany())
+                    .with_profile(&web_app)
+                    .with_mode(LaunchMode::App)
+            ),
+            [
+                "--profile=/home/u/.local/share/org.gnome.Epiphany.WebApp_abc",
+                "--application-mode",
+            ]
+        );
+        for builder in [
+            LaunchPlan::builder(&epiphany()).with_mode(LaunchMode::App),
+            LaunchPlan::builder(&epiphany()).with_mode(LaunchMode::Kiosk),
+        ] {
+            assert!(matches!(builder.build(), Err(Error::Unsupported { .. })));
+        }
+    }
+
+    #[test]
+    fn private_guest_is_invalid_everywhere() {
+        for browser in [chrome(), firefox(), epiphany()] {
+            let err = LaunchPlan::builder(&browser)
+                .with_mode(LaunchMode::Private)
+                .with_mode(LaunchMode::Guest)
+                .build()
+                .unwrap_err();
+            assert!(matches!(err, Error::InvalidOptions { .. }), "{err}");
+        }
+    }
+
+    #[test]
+    fn rejects_options_the_browser_lacks() {
+        let cases = [
+            LaunchPlan::builder(&chrome()).with_profile_name("work"),
+            LaunchPlan::builder(&firefox()).with_user_data_dir("/tmp/udd"),
+            LaunchPlan::builder(&epiphany()).with_profile_name("work"),
+            LaunchPlan::builder(&browser(BrowserVariant::Single(BrowserFamily::Other)))
+                .with_mode(LaunchMode::NewWindow),
+            LaunchPlan::builder(&browser(BrowserVariant::WebKit(WebKitChannel::Stable)))
+                .with_mode(LaunchMode::Private),
+        ];
+        for builder in cases {
+            assert!(matches!(builder.build(), Err(Error::Unsupported { .. })));
+        }
+    }
+
+    #[test]
+    fn urls_are_never_flags() {
+        // A URL cannot start with `-`; anything that does is not a URL
+        assert!(Url::parse("--remote-debugging-port=9222").is_err());
+
+        let url = url("https://example.com/--new-window");
+        for browser in [chrome(), firefox(), epiphany()] {
+            let args = args(LaunchPlan::builder(&browser).with_url(url.clone()));
+            assert_eq!(args.last().map(String::as_str), Some(url.as_str()));
+            assert!(!args.iter().any(|a| a == "--new-window"));
+        }
+    }
+
+    #[test]
+    fn flatpak_runs_through_wrapper_with_sandbox_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let wrapper = dir.path().join("org.mozilla.firefox");
+        std::fs::write(
+            &wrapper,
+            "#!/bin/sh\nexec /usr/bin/flatpak run --branch=stable --arch=x86_64 \
+             --command=firefox --file-forwarding org.mozilla.firefox @@u \"$@\"\n",
+        )
+        .unwrap();
+        let page = dir.path().join("page.html");
+        std::fs::write(&page, "<p>hi</p>").unwrap();
+        let page = Url::from_file_path(&page).unwrap();
+        let missing = Url::from_file_path(dir.path().join("missing.html")).unwrap();
+
+        let firefox = Browser::new("firefox", "Firefox", wrapper)
+            .with_variant(BrowserVariant::Firefox(FirefoxChannel::Stable))
+            .with_packaging(Packaging::Flatpak);
+        let profile = Profile::new(
+            "abc.default",
+            "default",
+            "/home/u/.var/app/org.mozilla.firefox/.mozilla/firefox/abc.default".into(),
+            BrowserFamily::Firefox,
+        );
+        let plan = LaunchPlan::builder(&firefox)
+            .with_profile(&profile)
+            .with_mode(LaunchMode::NewTab)
+            .with_urls([page.clone(), missing.clone()])
+            .build()
+            .unwrap();
+        assert_eq!(plan.program, PathBuf::from("flatpak"));
+        assert_eq!(
+            plan.args,
+            [
+                "run",
+                "--branch=stable",
+                "--arch=x86_64",
+                "--command=firefox",
+                "--file-forwarding",
+                "org.mozilla.firefox",
+                "--profile",
+                "/home/u/.mozilla/firefox/abc.default",
+                "-new-tab",
+                "@@u",
+                page.as_str(),
+                "@@",
+                "-new-tab",
+                missing.as_str(),
+            ]
+        );
+        assert_eq!(plan.warnings.len(), 1);
+        assert!(
+            plan.warnings[0].contains("missing.html"),
+            "{:?}",
+            plan.warnings
+        );
+    }
+
+    #[test]
+    fn snap_runs_through_snap_and_warns_about_hidden_files() {
+        let chromium = Browser::new("chromium", "Chromium", "/snap/bin/chromium".into())
+            .with_variant(BrowserVariant::Single(BrowserFamily::Chromium))
+            .with_packaging(Packaging::Snap);
+        let mut builder = LaunchPlan::builder(&chromium)
+            .with_user_data_dir("/home/u/snap/chromium/common/chromium")
+            .with_urls([
+                url("file:///home/u/Documents/a.html"),
+                url("file:///tmp/b.html"),
+            ]);
+        builder.home = Some("/home/u".into());
+        let plan = builder.build().unwrap();
+        assert_eq!(plan.program, PathBuf::from("snap"));
+        assert_eq!(
+            plan.args,
+            [
+                "run",
+                "chromium",
+                "--user-data-dir=/home/u/snap/chromium/common/chromium",
+                "--",
+                "file:///home/u/Documents/a.html",
+                "file:///tmp/b.html",
+            ]
+        );
+        assert_eq!(
+            plan.warnings,
+            ["file:///tmp/b.html is not reachable inside the Snap sandbox of chromium"]
+        );
+    }
+
+    #[test]
+    fn plan_round_trips_through_json() {
+        let plan = LaunchPlan::builder(&chrome())
+            .with_url(url("https://example.com/"))
+            .build()
+            .unwrap();
+        let json = serde_json::to_string(&plan).unwrap();
+        assert_eq!(serde_json::from_str::<LaunchPlan>(&json).unwrap(), plan);
+
+        let command = plan.command();
+        assert_eq!(command.get_program(), "/usr/bin/test-browser");
+        assert_eq!(command.get_args().count(), 2);
+    }
+}
@@ -0,0 +1,302 @@
+//! Flatpak and Snap sandboxes.
+//!
+//! Sandboxed browsers are started through their packaging tool and see the
+//! host file system only in part:
+//!
+//! | Packaging | Invocation | Visible paths |
+//! |-----------|------------|---------------|
+//! | Flatpak   | `flatpak run --branch=<b> --arch=<a> <app-id>` | `~/.var/app/<app-id>`, with persisted directories such as `~/.var/app/<app-id>/.mozilla` at `~/.mozilla`; single files shared through the document portal |
+//! | Snap      | `snap run <app>` | `~/snap/<name>`, non-hidden files in `~`, removable media |
+//!
+//! Both have a private `/tmp`.
+
+use std::path::{Component, Path, PathBuf};
+
+use browserware_types::{Browser, Packaging};
+use url::Url;
+
+/// Directories of a Flatpak app's data directory that the sandbox uses at
+/// their host path (as `$XDG_CONFIG_HOME`, `$XDG_DATA_HOME` and
+/// `$XDG_CACHE_HOME`); anything else there is a persisted home directory.
+const FLATPAK_XDG_DIRS: [&str; 3] = ["config", "data", "cache"];
+
+/// Mount points covered by the Snap `removable-media` interface.
+const SNAP_REMOVABLE_MEDIA: [&str; 3] = ["/media", "/mnt", "/run/media"];
+
+/// The sandbox a browser runs in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Sandbox {
+    /// A Flatpak app, run with `flatpak run`
+    Flatpak {
+        /// Application ID, e.g. `org.mozilla.firefox`
+        app_id: String,
+        /// Branch to run, when known
+        branch: Option<String>,
+        /// Architecture to run, when known
+        arch: Option<String>,
+        /// Command to run instead of the app's default, when known
+        command: Option<String>,
+    },
+    /// A Snap app, run with `snap run`
+    Snap {
+        /// Snap name, e.g. `chromium`
+        name: String,
+        /// App within the snap, e.g. `chromium` or `brave.browser`
+        app: String,
+    },
+}
+
+impl Sandbox {
+    /// The sandbox `browser` runs in, or `None` for native installs.
+    ///
+    /// Flatpak browsers are identified by their exported wrapper script,
+    /// whose `flatpak run` line gives the branch and architecture; Snap
+    /// browsers by their `/snap/bin` entry.
+    pub(crate) fn for_browser(browser: &Browser) -> Option<Self> {
+        let file_name = browser.executable.file_name()?.to_str()?.to_string();
+        match browser.packaging {
+            Packaging::Native => None,
+            Packaging::Flatpak => {
+                // Without the script, `flatpak run` picks the default branch
+                let wrapper = std::fs::read_to_string(&browser.executable).unwrap_or_default();
+                Some(Self::flatpak(file_name, &wrapper))
+            }
+            Packaging::Snap => Some(Self::Snap {
+                name: file_name
+                    .split_once('.')
+                    .map_or_else(|| file_name.clone(), |(name, _)| name.to_string()),
+                app: file_name,
+            }),
+        }
+    }
+
+    /// A Flatpak app, with the options of the `flatpak run` line in its
+    /// exported `wrapper` script.
+    pub(crate) fn flatpak(app_id: String, wrapper: &str) -> Self {
+        let option = |name: &str| {
+            wrapper
+                .lines()
+                .filter(|line| line.contains("flatpak run"))
+                .flat_map(str::split_whitespace)
+                .find_map(|token| token.trim_matches(['\'', '"']).strip_prefix(name))
+                .map(str::to_string)
+        };
+        Self::Flatpak {
+            branch: option("--branch="),
+            arch: option("--arch="),
+            command: option("--command="),
+            app_id,
+        }
+    }
+
+    /// `path` as the browser sees it inside the sandbox.
+    ///
+    /// Flatpak persisted directories move to the sandbox home, so that a
+    /// browser recognizes its own profiles; other paths are unchanged.
+    pub(crate) fn sandbox_path(&self, path: &Path) -> PathBuf {
+        let Self::Flatpak { app_id, .. } = self else {
+            return path.to_path_buf();
+        };
+        let Some((home, rest)) = split_at(path, &[".var", "app", app_id]) else {
+            return path.to_path_buf();
+        };
+        match rest.components().next() {
+            Some(Component::Normal(first)) if !FLATPAK_XDG_DIRS.iter().any(|dir| first == *dir) => {
+                home.join(rest)
+            }
+            _ => path.to_path_buf(),
+        }
+    }
+
+    /// Wrap the browser arguments `args` in the sandbox invocation.
+    ///
+    /// Local files among `urls` that the sandbox cannot see are shared
+    /// through the document portal where possible (Flatpak, for URLs
+    /// passed as arguments of their own); the others are returned as
+    /// warnings. `home` is the user's home directory, if known.
+    pub(crate) fn wrap(
+        &self,
+        args: Vec<String>,
+        urls: &[Url],
+        home: Option<&Path>,
+    ) -> (PathBuf, Vec<String>, Vec<String>) {
+        let mut warnings = Vec::new();
+        let files: Vec<(&Url, PathBuf)> = urls
+            .iter()
+            .filter(|url| url.scheme() == "file")
+            .filter_map(|url| Some((url, url.to_file_path().ok()?)))
+            .collect();
+
+        match self {
+            Self::Flatpak {
+                app_id,
+                branch,
+                arch,
+                command,
+            } => {
+                let hidden: Vec<&str> = files
+                    .iter()
+                    .filter(|(_, path)| split_at(path, &[".var", "app", app_id]).is_none())
+                    .map(|(url, _)| url.as_str())
+                    .collect();
+                let forwardable = |arg: &str| {
+                    hidden.contains(&arg)
+                        && Url::parse(arg)
+                            .ok()
+                            .and_then(|url| url.to_file_path().ok())
+                            .is_some_and(|path| path.is_file())
+                };
+                for url in hidden
+                    .iter()
+                    .filter(|url| !args.iter().any(|a| a == *url && forwardable(a)))
+                {
+                    warnings.push(format!(
+                        "{url} is not reachable inside the Flatpak sandbox of {app_id}"
+                    ));
+                }
+
+                let mut wrapped = vec!["run".to_string()];
+                let options = [
+                    ("--branch=", branch),
+                    ("--arch=", arch),
+                    ("--command=", command),
+                ];
+                for (option, value) in options {
+                    if let Some(value) = value {
+                        wrapped.push(format!("{option}{value}"));
+                    }
+                }
+                if args.iter().any(|arg| forwardable(arg)) {
+                    wrapped.push("--file-forwarding".to_string());
+                }
+                wrapped.push(app_id.clone());
+                for arg in args {
+                    if forwardable(&arg) {
+                        wrapped.extend(["@@u".to_string(), arg, "@@".to_string()]);
+                    } else {
+                        wrapped.push(arg);
+                    }
+                }
+                (PathBuf::from("flatpak"), wrapped, warnings)
+            }
+            Self::Snap { name, app } => {
+                for (url, path) in &files {
+                    if !snap_can_see(name, path, home) {
+                        warnings.push(format!(
+                            "{url} is not reachable inside the Snap sandbox of {name}"
+                        ));
+                    }
+                }
+                let mut wrapped = vec!["run".to_string(), app.clone()];
+                wrapped.extend(args);
+                (PathBuf::from("snap"), wrapped, warnings)
+            }
+        }
+    }
+}
+
+/// Whether a snap can see `path`: its own directory under `~/snap`,
+/// non-hidden files in the home directory, or removable media.
+fn snap_can_see(name: &str, path: &Path, home: Option<&Path>) -> bool {
+    if SNAP_REMOVABLE_MEDIA.iter().any(|dir| path.starts_with(dir)) {
+        return true;
+    }
+    let Some(rest) = home.and_then(|home| path.strip_prefix(home).ok()) else {
+        return false;
+    };
+    if rest.starts_with(Path::new("snap").join(name)) {
+        return true;
+    }
+    match rest.components().next() {
+        Some(Component::Normal(first)) => {
+            !first.to_string_lossy().starts_with('.') && first != "snap"
+        }
+        _ => false,
+    }
+}
+
+/// Split `path` around the first occurrence of the components `marker`,
+/// returning what precedes and what follows it.
+fn split_at<'a>(path: &'a Path, marker: &[&str]) -> Option<(PathBuf, &'a Path)> {
+    let components: Vec<Component<'_>> = path.components().collect();
+    let start = components.windows(marker.len()).position(|window| {
+        window
+            .iter()
+            .zip(marker)
+            .all(|(component, name)| component.as_os_str() == *name)
+    })?;
+    let prefix: PathBuf = components[..start].iter().collect();
+    let mut rest = path.components();
+    for _ in 0..start + marker.len() {
+        rest.next();
+    }
+    Some((prefix, rest.as_path()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WRAPPER: &str = "#!/bin/sh\n\
+        exec /usr/bin/flatpak run --branch=stable --arch=x86_64 --command=firefox \
+        --file-forwarding org.mozilla.firefox @@u \"$@\"\n";
+
+    fn firefox() -> Sandbox {
+        Sandbox::flatpak("org.mozilla.firefox".to_string(), WRAPPER)
+    }
+
+    #[test]
+    fn reads_flatpak_wrapper_options() {
+        assert_eq!(
+            firefox(),
+            Sandbox::Flatpak {
+                app_id: "org.mozilla.firefox".to_string(),
+                branch: Some("stable".to_string()),
+                arch: Some("x86_64".to_string()),
+                command: Some("firefox".to_string()),
+            }
+        );
+        let bare = Sandbox::flatpak("com.google.Chrome".to_string(), "");
+        let (program, args, _) = bare.wrap(vec!["--new-window".to_string()], &[], None);
+        assert_eq!(program, PathBuf::from("flatpak"));
+        assert_eq!(args, ["run", "com.google.Chrome", "--new-window"]);
+    }
+
+    #[test]
+    fn persisted_flatpak_directories_move_to_sandbox_home() {
+        let sandbox = firefox();
+        assert_eq!(
+            sandbox.sandbox_path(Path::new(
+                "/home/u/.var/app/org.mozilla.firefox/.mozilla/firefox/abc.default"
+            )),
+            PathBuf::from("/home/u/.mozilla/firefox/abc.default")
+        );
+        // XDG directories and other apps keep their host paths
+        for path in [
+            "/home/u/.var/app/org.mozilla.firefox/config/x",
+            "/home/u/.var/app/org.chromium.Chromium/.mozilla",
+            "/tmp/profile",
+        ] {
+            assert_eq!(sandbox.sandbox_path(Path::new(path)), PathBuf::from(path));
+        }
+    }
+
+    #[test]
+    fn snap_visibility() {
+        let home = Some(Path::new("/home/u"));
+        for (path, visible) in [
+            ("/home/u/Documents/a.html", true),
+            ("/home/u/snap/firefox/common/a.html", true),
+            ("/media/usb/a.html", true),
+            ("/home/u/.cache/a.html", false),
+            ("/home/u/snap/chromium/common/a.html", false),
+            ("/tmp/a.html", false),
+        ] {
+            assert_eq!(
+                snap_can_see("firefox", Path::new(path), home),
+                visible,
+                "{path}"
+            );
+        }
+    }
+}
@@ -0,0 +1,386 @@
+//! Starting browsers from a [`LaunchPlan`].
+//!
+//! Browsers are started detached: in a new session (a new process group on
+//! Windows), with stdin from `/dev/null`, output to a log file or
+//! discarded, and no other inherited descriptors. They outlive the
+//! terminal that ran `brw` and never read from or write to it.
+
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
+
+use crate::{Error, LaunchPlan, Result};
+
+/// Default time a browser is watched for crashing during startup.
+pub const DEFAULT_CRASH_WINDOW: Duration = Duration::from_millis(500);
+
+/// Most output kept for an [`Error::EarlyExit`].
+const MAX_CAPTURED_OUTPUT: usize = 16 * 1024;
+
+/// Time allowed for captured output to arrive after an early exit.
+const OUTPUT_GRACE: Duration = Duration::from_millis(100);
+
+/// Interval between exit checks while waiting.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// How a browser process is started.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[must_use]
+pub struct SpawnOptions {
+    log: Option<PathBuf>,
+    crash_window: Option<Duration>,
+}
+
+impl SpawnOptions {
+    /// Discard the browser's output and return as soon as it is started.
+    pub const fn new() -> Self {
+        Self {
+            log: None,
+            crash_window: None,
+        }
+    }
+
+    /// Append the browser's stdout and stderr to `path`.
+    pub fn with_log(mut self, path: impl Into<PathBuf>) -> Self {
+        self.log = Some(path.into());
+        self
+    }
+
+    /// Watch the browser for `window` after starting it, and report a
+    /// failure exit in that time as [`Error::EarlyExit`].
+    ///
+    /// A successful exit is not an error: browsers that hand URLs to an
+    /// already running instance exit right away.
+    pub const fn with_crash_window(mut self, window: Duration) -> Self {
+        self.crash_window = Some(window);
+        self
+    }
+}
+
+/// A started browser process.
+///
+/// Dropping the handle does not stop the browser. If it is still running,
+/// a background thread waits for it so it does not linger as a zombie.
+#[derive(Debug)]
+pub struct LaunchHandle {
+    browser: String,
+    pid: u32,
+    child: Option<Child>,
+    status: Option<ExitStatus>,
+}
+
+impl LaunchHandle {
+    /// Process ID of the browser.
+    #[must_use]
+    pub const fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    /// Exit status of the browser, or `None` while it is running.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Wait`] if the process cannot be queried.
+    pub fn status(&mut self) -> Result<Option<ExitStatus>> {
+        if self.status.is_none()
+            && let Some(child) = &mut self.child
+        {
+            self.status = child.try_wait().map_err(|source| Error::Wait {
+                browser: self.browser.clone(),
+                source,
+            })?;
+        }
+        Ok(self.status)
+    }
+
+    /// Wait up to `timeout` for the browser to exit.
+    ///
+    /// Returns the exit status, or `None` if the browser is still running.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Wait`] if the process cannot be queried.
+    pub fn wait_timeout(&mut self, timeout: Duration) -> Result<Option<ExitStatus>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(status) = self.status()? {
+                return Ok(Some(status));
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Ok(None);
+            }
+            std::thread::sleep(POLL_INTERVAL.min(deadline - now));
+        }
+    }
+}
+
+impl Drop for LaunchHandle {
+    fn drop(&mut self) {
+        if self.status.is_none()
+            && let Some(mut child) = self.child.take()
+        {
+            std::thread::spawn(move || child.wait());
+        }
+    }
+}
+
+/// Start the browser described by `plan`, detached from this process.
+///
+/// # Errors
+///
+/// - [`Error::Spawn`] if the executable cannot be run
+/// - [`Error::Log`] if the log file cannot be opened
+/// - [`Error::EarlyExit`] if the browser fails within the crash window,
+///   with the output it wrote
+/// - [`Error::Wait`] if the process cannot be queried
+pub fn spawn(plan: &LaunchPlan, options: &SpawnOptions) -> Result<LaunchHandle> {
+    let mut command = plan.command();
+    command.stdin(Stdio::null());
+    let capture = redirect_output(&mut command, options)?;
+    if matches!(capture, Some(Capture::Pipe)) {
+        ignore_sigpipe(&mut command);
+    }
+    detach(&mut command);
+
+    let mut child = command.spawn().map_err(|source| Error::Spawn {
+        browser: plan.browser.clone(),
+        executable: plan.program.clone(),
+        source,
+    })?;
+    let stderr = child.stderr.take().map(drain);
+    tracing::debug!(browser = %plan.browser, pid = child.id(), "Started browser");
+    let mut handle = LaunchHandle {
+        browser: plan.browser.clone(),
+        pid: child.id(),
+        child: Some(child),
+        status: None,
+    };
+
+    if let Some(window) = options.crash_window
+        && let Some(status) = handle.wait_timeout(window)?
+        && !status.success()
+    {
+        let output = match (capture, stderr) {
+            (Some(Capture::Log(file, start)), _) => read_tail(file, start),
+            (_, Some(stderr)) => collect(&stderr),
+            _ => String::new(),
+        };
+        return Err(Error::EarlyExit {
+            browser: plan.browser.clone(),
+            status,
+            output,
+        });
+    }
+    Ok(handle)
+}
+
+/// Where the browser's output can be read back from for an early exit.
+enum Capture {
+    /// The log file, and the offset the browser's output starts at
+    Log(File, u64),
+    /// A pipe on stderr, read by [`drain`]
+    Pipe,
+}
+
+/// Send the browser's output to the log file, or discard it.
+///
+/// Without a log, stderr goes to a pipe while a crash window is watched.
+/// The pipe is drained for the browser's lifetime but only read during the
+/// window, so nothing is kept once the browser is up.
+fn redirect_output(command: &mut Command, options: &SpawnOptions) -> Result<Option<Capture>> {
+    if let Some(path) = &options.log {
+        let log = open_log(path)?;
+        let start = log.metadata().map_or(0, |meta| meta.len());
+        let log_error = |source| Error::Log {
+            path: path.clone(),
+            source,
+        };
+        command.stdout(log.try_clone().map_err(log_error)?);
+        command.stderr(log.try_clone().map_err(log_error)?);
+        return Ok(Some(Capture::Log(log, start)));
+    }
+
+    command.stdout(Stdio::null());
+    if options.crash_window.is_some() {
+        command.stderr(Stdio::piped());
+        return Ok(Some(Capture::Pipe));
+    }
+    command.stderr(Stdio::null());
+    Ok(None)
+}
+
+fn open_log(path: &Path) -> Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .read(true)
+        .open(path)
+        .map_err(|source| Error::Log {
+            path: path.to_path_buf(),
+            source,
+        })
+}
+
+/// Read `stderr` until it closes, passing what is read on to the returned
+/// receiver for as long as it exists.
+///
+/// Reading on after the receiver is dropped keeps the browser from
+/// blocking on a full pipe while `brw` runs.
+fn drain(mut stderr: impl Read + Send + 'static) -> Receiver<Vec<u8>> {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut buf = [0; 4096];
+        while let Ok(read) = stderr.read(&mut buf)
+            && read > 0
+        {
+            let _ = sender.send(buf[..read].to_vec());
+        }
+    });
+    receiver
+}
+
+/// The output received from [`drain`], at most [`MAX_CAPTURED_OUTPUT`]
+/// bytes from its end.
+///
+/// Waits at most [`OUTPUT_GRACE`] for the pipe to close, as processes the
+/// browser started may still hold it open.
+fn collect(output: &Receiver<Vec<u8>>) -> String {
+    let deadline = Instant::now() + OUTPUT_GRACE;
+    let mut bytes = Vec::new();
+    while let Some(timeout) = deadline.checked_duration_since(Instant::now())
+        && let Ok(chunk) = output.recv_timeout(timeout)
+    {
+        bytes.extend(chunk);
+        bytes.drain(..bytes.len().saturating_sub(MAX_CAPTURED_OUTPUT));
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// The output written to `file` after offset `start`, at most
+/// [`MAX_CAPTURED_OUTPUT`] bytes from its end.
+fn read_tail(mut file: File, start: u64) -> String {
+    let end = file.metadata().map_or(0, |meta| meta.len());
+    let from = start.max(end.saturating_sub(MAX_CAPTURED_OUTPUT as u64));
+    let mut output = Vec::new();
+    if file.seek(SeekFrom::Start(from)).is_ok() {
+        let _ = file.read_to_end(&mut output);
+    }
+    String::from_utf8_lossy(&output).into_owned()
+}
+
+#[cfg(unix)]
+use crate::sys::{detach, ignore_sigpipe};
+
+/// Without `SIGPIPE`, nothing to ignore.
+#[cfg(not(unix))]
+const fn ignore_sigpipe(_command: &mut Command) {}
+
+/// Start the process without a console, in its own process group.
+#[cfg(windows)]
+fn detach(command: &mut Command) {
+    use std::os::windows::process::CommandExt;
+
+    const DETACHED_PROCESS: u32 = 0x0000_0008;
+    const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+    command.creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP);
+}
+
+#[cfg(not(any(unix, windows)))]
+const fn detach(_command: &mut Command) {}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn shell(script: &str) -> LaunchPlan {
+        LaunchPlan {
+            browser: "sh".to_string(),
+            program: "/bin/sh".into(),
+            args: vec!["-c".to_string(), script.to_string()],
+            warnings: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn reports_early_exit_with_output() {
+        let options = SpawnOptions::new().with_crash_window(Duration::from_secs(10));
+        let err = spawn(&shell("echo 'cannot open display' >&2; exit 3"), &options).unwrap_err();
+        let Error::EarlyExit { status, output, .. } = &err else {
+            panic!("unexpected error: {err}");
+        };
+        assert_eq!(status.code(), Some(3));
+        assert_eq!(output.trim(), "cannot open display");
+        assert_eq!(
+            err.to_string(),
+            "sh exited during startup (exit status: 3): cannot open display"
+        );
+
+        // Handing off to a running instance exits successfully
+        let mut handle = spawn(&shell("exit 0"), &options).unwrap();
+        assert!(handle.status().unwrap().unwrap().success());
+    }
+
+    #[test]
+    fn starts_in_new_session_with_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("browser.log");
+        std::fs::write(&log, "earlier\n").unwrap();
+
+        // Field 6 of /proc/<pid>/stat is the session ID
+        let script = "read pid comm state ppid pgrp sid rest < /proc/self/stat; \
+                      echo \"$$ $sid\"; echo bad >&2; exit 1";
+        let options = SpawnOptions::new()
+            .with_log(&log)
+            .with_crash_window(Duration::from_secs(10));
+        let err = spawn(&shell(script), &options).unwrap_err();
+        let Error::EarlyExit { output, .. } = err else {
+            panic!("unexpected error: {err}");
+        };
+        if std::path::Path::new("/proc/self/stat").exists() {
+            let ids: Vec<&str> = output.lines().next().unwrap().split(' ').collect();
+            assert_eq!(ids[0], ids[1], "not a session leader: {output}");
+        }
+        assert!(!output.contains("earlier"));
+        let log = std::fs::read_to_string(&log).unwrap();
+        assert!(
+            log.starts_with("earlier\n") && log.ends_with("bad\n"),
+            "{log}"
+        );
+    }
+
+    #[test]
+    fn output_after_the_crash_window_is_discarded() {
+        // Far more than a pipe buffer holds, written after the window
+        let script = "sleep 0.1; i=0; while [ $i -lt 20000 ]; do \
+                      echo 'some browser warning' >&2; i=$((i+1)); done";
+        let options = SpawnOptions::new().with_crash_window(Duration::from_millis(10));
+        let mut handle = spawn(&shell(script), &options).unwrap();
+        let status = handle.wait_timeout(Duration::from_secs(30)).unwrap();
+        assert!(status.unwrap().success());
+    }
+
+    #[test]
+    fn waits_with_timeout() {
+        let mut handle = spawn(&shell("sleep 0.2"), &SpawnOptions::new()).unwrap();
+        assert!(handle.pid() > 0);
+        assert_eq!(handle.wait_timeout(Duration::ZERO).unwrap(), None);
+        let status = handle.wait_timeout(Duration::from_secs(10)).unwrap();
+        assert!(status.unwrap().success());
+    }
+
+    #[test]
+    fn missing_executable_is_a_spawn_error() {
+        let plan = LaunchPlan {
+            browser: "nope".to_string(),
+            program: "/nonexistent/browser".into(),
+            args: Vec::new(),
+            warnings: Vec::new(),
+        };
+        let err = spawn(&plan, &SpawnOptions::new()).unwrap_err();
+        assert!(matches!(err, Error::Spawn { .. }), "{err}");
+    }
+}
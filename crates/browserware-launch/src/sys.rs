@@ -0,0 +1,85 @@
+//! Detaching browser processes on Unix.
+//!
+//! This is the only module of the crate that uses `unsafe`: the session and
+//! descriptor setup has to run in the child between `fork` and `exec`.
+
+use std::io;
+use std::os::unix::process::CommandExt;
+use std::process::Command;
+
+/// Highest descriptor marked close-on-exec when `close_range` is unavailable.
+const MAX_FD_SCAN: libc::c_int = 65_536;
+
+/// Start `command` in a new session, with only its standard streams
+/// surviving `exec`.
+///
+/// The new session has no controlling terminal, so closing the terminal
+/// `brw` runs in does not send the browser `SIGHUP`.
+pub(crate) fn detach(command: &mut Command) {
+    let max_fd = open_max();
+    // SAFETY: the closure runs in the child between fork and exec; it does
+    // not allocate and only makes async-signal-safe system calls.
+    unsafe {
+        command.pre_exec(move || {
+            if libc::setsid() == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            cloexec_from(3, max_fd);
+            Ok(())
+        });
+    }
+}
+
+/// Let `command` ignore `SIGPIPE`, so that writing to its stderr pipe after
+/// `brw` has exited fails with `EPIPE` instead of killing it.
+pub(crate) fn ignore_sigpipe(command: &mut Command) {
+    // SAFETY: the closure runs in the child between fork and exec and only
+    // calls the async-signal-safe `signal`.
+    unsafe {
+        command.pre_exec(|| {
+            libc::signal(libc::SIGPIPE, libc::SIG_IGN);
+            Ok(())
+        });
+    }
+}
+
+/// The descriptor limit, computed before forking.
+fn open_max() -> libc::c_int {
+    // SAFETY: sysconf has no preconditions.
+    let max = unsafe { libc::sysconf(libc::_SC_OPEN_MAX) };
+    libc::c_int::try_from(max)
+        .ok()
+        .filter(|&max| max > 0)
+        .map_or(MAX_FD_SCAN, |max| max.min(MAX_FD_SCAN))
+}
+
+/// Mark every descriptor from `first` on close-on-exec.
+///
+/// # Safety
+///
+/// Must only be called in a forked child before `exec`, where no other
+/// code uses the descriptors.
+unsafe fn cloexec_from(first: libc::c_int, max_fd: libc::c_int) {
+    #[cfg(target_os = "linux")]
+    {
+        // SAFETY: close_range only changes descriptor flags (Linux 5.11+;
+        // older kernels fail with ENOSYS or EINVAL and take the loop below).
+        let done = unsafe {
+            libc::syscall(
+                libc::SYS_close_range,
+                first.unsigned_abs(),
+                libc::c_uint::MAX,
+                libc::CLOSE_RANGE_CLOEXEC,
+            )
+        } == 0;
+        if done {
+            return;
+        }
+    }
+    for fd in first..max_fd {
+        // SAFETY: F_SETFD on a closed descriptor fails harmlessly with EBADF.
+        unsafe {
+            libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC);
+        }
+    }
+}
@@ -0,0 +1,486 @@
+//! Profile backup archives.
+//!
+//! A backup is a gzip-compressed tar archive holding a JSON manifest
+//! ([`MANIFEST_NAME`]) followed by the profile directory under `profile/`.
+//! Caches, lock files and symbolic links are left out: browsers rebuild the
+//! former and the latter only mean something on the machine they were made on.
+//!
+//! Restoring registers a new profile the way [`create_profile`] does, then
+//! fills its directory from the archive. Backups only restore into a browser
+//! of the same family and, for the same browser, a version at least the one
+//! that made them, since browsers cannot read profiles migrated by a newer
+//! version. Versions of different browsers (Chrome and Brave, say) are not
+//! comparable and are not checked.
+
+use std::fs::File;
+use std::io::{BufReader, Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+use browserware_types::{Browser, BrowserFamily, Profile};
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+
+use crate::inspect::CACHE_DIRS;
+use crate::manage::{ensure_unused, user_data_dir};
+use crate::{
+    Error, Result, chromium, create_profile_in, firefox, list_profiles_in, select_profile,
+};
+
+/// Name of the manifest entry, the first entry of every backup.
+pub const MANIFEST_NAME: &str = "browserware-backup.json";
+
+/// Version of the backup format written by this crate.
+pub const BACKUP_FORMAT_VERSION: u32 = 1;
+
+/// Directory of the archive holding the profile's files.
+const PROFILE_DIR: &str = "profile";
+
+/// Profile-relative paths left out of backups besides caches.
+const EXCLUDED: [&str; 8] = [
+    // Crash data
+    "crashes",
+    "minidumps",
+    // Lock files
+    "lock",
+    ".parentlock",
+    "parent.lock",
+    "SingletonCookie",
+    "SingletonLock",
+    "SingletonSocket",
+];
+
+/// Description of a backup, stored in its manifest.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct BackupManifest {
+    /// Version of the backup format
+    pub format_version: u32,
+    /// ID of the browser the profile was backed up from
+    pub browser: String,
+    /// Engine family of that browser
+    pub family: BrowserFamily,
+    /// Version of that browser (if known)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub browser_version: Option<String>,
+    /// The profile at the time of the backup
+    pub profile: Profile,
+    /// When the backup was made, in seconds since the Unix epoch
+    pub created: u64,
+    /// Number of files in the backup
+    pub files: usize,
+}
+
+/// Back up the profile matching `query` (see [`find_profile`](crate::find_profile))
+/// to a new archive at `archive`.
+///
+/// # Example
+///
+/// ```no_run
+/// if let Some(chrome) = browserware_detect::detect_browser("chrome") {
+///     let manifest = browserware_profiles::export_profile(&chrome, "Work", "work.tar.gz".as_ref())?;
+///     println!("{} files", manifest.files);
+/// }
+/// # Ok::<(), browserware_profiles::Error>(())
+/// ```
+///
+/// # Errors
+///
+/// - [`Error::NotFound`] or [`Error::Ambiguous`] if the query does not select
+///   exactly one profile
+/// - [`Error::InUse`] if the profile is locked or its browser is running
+/// - [`Error::Io`] if `archive` exists, a file cannot be read or the
+///   archive written
+pub fn export_profile(browser: &Browser, query: &str, archive: &Path) -> Result<BackupManifest> {
+    export_profile_in(browser, &user_data_dir(browser)?, query, archive)
+}
+
+/// Back up a profile of a specific user data directory.
+///
+/// # Errors
+///
+/// See [`export_profile`].
+pub fn export_profile_in(
+    browser: &Browser,
+    user_data_dir: &Path,
+    query: &str,
+    archive: &Path,
+) -> Result<BackupManifest> {
+    let profiles = list_profiles_in(browser, user_data_dir)?;
+    let profile = select_profile(&browser.id.0, &profiles, query)?;
+    ensure_unused(browser, user_data_dir, &profile)?;
+    if archive.exists() {
+        return Err(Error::io(archive, std::io::ErrorKind::AlreadyExists.into()));
+    }
+
+    let mut files = Vec::new();
+    collect_files(&profile.path, Path::new(""), &mut files)?;
+    let manifest = BackupManifest {
+        format_version: BACKUP_FORMAT_VERSION,
+        browser: browser.id.0.clone(),
+        family: browser.family(),
+        browser_version: browser.version.clone(),
+        created: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs()),
+        files: files.len(),
+        profile,
+    };
+
+    // Write next to the archive and rename, so a failed backup leaves nothing
+    let mut temp = archive.as_os_str().to_owned();
+    temp.push(".partial");
+    let temp = PathBuf::from(temp);
+    let result = write_archive(&temp, &manifest, &files)
+        .and_then(|()| std::fs::rename(&temp, archive).map_err(|err| Error::io(archive, err)));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+    result?;
+    tracing::info!(
+        browser = %browser.id,
+        profile = %manifest.profile.id,
+        archive = %archive.display(),
+        files = manifest.files,
+        "Exported profile"
+    );
+    Ok(manifest)
+}
+
+/// Read the manifest of a backup.
+///
+/// # Errors
+///
+/// Returns [`Error::Io`] if the archive cannot be read and [`Error::Parse`]
+/// if it is not a backup or was made by a newer, unknown format version.
+pub fn read_backup_manifest(archive: &Path) -> Result<BackupManifest> {
+    let mut entries = open_archive(archive)?;
+    let mut entries = entries.entries().map_err(|err| Error::io(archive, err))?;
+    let not_a_backup = |message: String| Error::Parse {
+        path: archive.to_path_buf(),
+        line: None,
+        message,
+    };
+
+    let mut entry = entries
+        .next()
+        .transpose()
+        .map_err(|err| Error::io(archive, err))?
+        .ok_or_else(|| not_a_backup("archive is empty".to_string()))?;
+    if entry.path().ok().as_deref() != Some(Path::new(MANIFEST_NAME)) {
+        return Err(not_a_backup(format!("{MANIFEST_NAME} is missing")));
+    }
+    let mut text = String::new();
+    entry
+        .read_to_string(&mut text)
+        .map_err(|err| Error::io(archive, err))?;
+    let manifest: BackupManifest = serde_json::from_str(&text)
+        .map_err(|err| not_a_backup(format!("invalid {MANIFEST_NAME}: {err}")))?;
+    if manifest.format_version > BACKUP_FORMAT_VERSION {
+        return Err(not_a_backup(format!(
+            "backup format version {} is newer than the supported version {BACKUP_FORMAT_VERSION}",
+            manifest.format_version
+        )));
+    }
+    Ok(manifest)
+}
+
+/// Restore a backup as a new profile named `name` (the backed up profile's
+/// name if `None`).
+///
+/// Returns the restored profile.
+///
+/// # Errors
+///
+/// - [`Error::IncompatibleBackup`] if the backup was made by a browser of
+///   another family or a newer version of the same browser
+/// - any error of [`read_backup_manifest`] and [`create_profile`](crate::create_profile)
+/// - [`Error::Io`] if the files cannot be extracted
+pub fn restore_profile(browser: &Browser, archive: &Path, name: Option<&str>) -> Result<Profile> {
+    restore_profile_in(browser, &user_data_dir(browser)?, archive, name)
+}
+
+/// Restore a backup into a specific user data directory.
+///
+/// # Errors
+///
+/// See [`restore_profile`].
+pub fn restore_profile_in(
+    browser: &Browser,
+    user_data_dir: &Path,
+    archive: &Path,
+    name: Option<&str>,
+) -> Result<Profile> {
+    let manifest = read_backup_manifest(archive)?;
+    ensure_compatible(browser, archive, &manifest)?;
+    let name = name.unwrap_or(&manifest.profile.name);
+
+    let profile = create_profile_in(browser, user_data_dir, name)?;
+    if let Err(err) = extract_profile(archive, &profile.path) {
+        // Do not leave a half-restored profile registered
+        let cleanup = match profile.family {
+            BrowserFamily::Chromium => {
+                chromium::delete_profile(user_data_dir, &profile.id.0, false)
+            }
+            _ => firefox::delete_profile(user_data_dir, &profile.id.0, false),
+        };
+        if let Err(cleanup) = cleanup {
+            tracing::warn!(profile = %profile.id, "Failed to remove partly restored profile: {cleanup}");
+        }
+        return Err(err);
+    }
+    if profile.family == BrowserFamily::Chromium {
+        // The restored Preferences still carry the backed up name
+        chromium::rename_profile(user_data_dir, &profile.id.0, name)?;
+    }
+
+    tracing::info!(
+        browser = %browser.id,
+        profile = %profile.id,
+        archive = %archive.display(),
+        "Restored profile"
+    );
+    Ok(profile)
+}
+
+/// Refuse backups of another family or a newer version of the same browser.
+fn ensure_compatible(browser: &Browser, archive: &Path, manifest: &BackupManifest) -> Result<()> {
+    let incompatible = |reason: String| Error::IncompatibleBackup {
+        archive: archive.to_path_buf(),
+        reason,
+    };
+    if manifest.family != browser.family() {
+        return Err(incompatible(format!(
+            "backup of a {} profile cannot be restored into {} ({})",
+            manifest.family,
+            browser.name,
+            browser.family()
+        )));
+    }
+    if manifest.browser != browser.id.0 {
+        return Ok(());
+    }
+    match (&manifest.browser_version, &browser.version) {
+        (Some(backed_up), Some(installed))
+            if version_parts(installed) < version_parts(backed_up) =>
+        {
+            Err(incompatible(format!(
+                "backup was made by {} {backed_up}, newer than the installed {installed}",
+                manifest.browser
+            )))
+        }
+        (Some(backed_up), None) => {
+            tracing::warn!(
+                browser = %browser.id,
+                "Version unknown, cannot check it is at least {backed_up}"
+            );
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Numeric components of a version (`128.0b3` is `[128, 0]`).
+fn version_parts(version: &str) -> Vec<u64> {
+    version
+        .split('.')
+        .map_while(|part| {
+            let digits: String = part.chars().take_while(char::is_ascii_digit).collect();
+            digits.parse().ok()
+        })
+        .collect()
+}
+
+/// Whether a profile-relative path is left out of backups.
+fn is_excluded(relative: &Path) -> bool {
+    CACHE_DIRS
+        .iter()
+        .chain(&EXCLUDED)
+        .any(|excluded| relative == Path::new(excluded))
+}
+
+/// Collect the profile-relative paths of the regular files under `dir`.
+fn collect_files(root: &Path, relative: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    let dir = root.join(relative);
+    let mut entries = std::fs::read_dir(&dir)
+        .and_then(Iterator::collect::<std::io::Result<Vec<_>>>)
+        .map_err(|err| Error::io(&dir, err))?;
+    entries.sort_by_key(std::fs::DirEntry::file_name);
+    for entry in entries {
+        let path = relative.join(entry.file_name());
+        if is_excluded(&path) {
+            continue;
+        }
+        let file_type = entry
+            .file_type()
+            .map_err(|err| Error::io(entry.path(), err))?;
+        if file_type.is_dir() {
+            collect_files(root, &path, files)?;
+        } else if file_type.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn write_archive(path: &Path, manifest: &BackupManifest, files: &[PathBuf]) -> Result<()> {
+    let file = File::create(path).map_err(|err| Error::io(path, err))?;
+    let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+    let io = |err| Error::io(path, err);
+
+    let json = serde_json::to_vec_pretty(manifest).map_err(|err| Error::Parse {
+        path: path.to_path_buf(),
+        line: None,
+        message: err.to_string(),
+    })?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(json.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(manifest.created);
+    header.set_cksum();
+    builder
+        .append_data(&mut header, MANIFEST_NAME, json.as_slice())
+        .map_err(io)?;
+
+    for relative in files {
+        let source = manifest.profile.path.join(relative);
+        let mut file = File::open(&source).map_err(|err| Error::io(&source, err))?;
+        builder
+            .append_file(Path::new(PROFILE_DIR).join(relative), &mut file)
+            .map_err(|err| Error::io(&source, err))?;
+    }
+    builder
+        .into_inner()
+        .and_then(GzEncoder::finish)
+        .and_then(|mut file| file.flush().and_then(|()| file.sync_all()))
+        .map_err(io)
+}
+
+fn open_archive(archive: &Path) -> Result<tar::Archive<GzDecoder<BufReader<File>>>> {
+    let file = File::open(archive).map_err(|err| Error::io(archive, err))?;
+    Ok(tar::Archive::new(GzDecoder::new(BufReader::new(file))))
+}
+
+/// Extract the profile files of a backup into `dest`.
+fn extract_profile(archive: &Path, dest: &Path) -> Result<()> {
+    let mut tar = open_archive(archive)?;
+    for entry in tar.entries().map_err(|err| Error::io(archive, err))? {
+        let mut entry = entry.map_err(|err| Error::io(archive, err))?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = entry.path().map_err(|err| Error::io(archive, err))?;
+        let Ok(relative) = path.strip_prefix(PROFILE_DIR) else {
+            continue;
+        };
+        if !relative
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)))
+        {
+            return Err(Error::Parse {
+                path: archive.to_path_buf(),
+                line: None,
+                message: format!("unsafe path '{}' in backup", path.display()),
+            });
+        }
+
+        let target = dest.join(relative);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent).map_err(|err| Error::io(parent, err))?;
+        }
+        entry
+            .unpack(&target)
+            .map_err(|err| Error::io(&target, err))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use browserware_types::BrowserVariant;
+
+    fn chrome(version: &str) -> Browser {
+        Browser::new("chrome", "Google Chrome", "/usr/bin/google-chrome".into())
+            .with_variant(BrowserVariant::chromium_stable())
+            .with_version(version)
+    }
+
+    #[test]
+    fn exports_and_restores_without_caches() {
+        let source = tempfile::tempdir().unwrap();
+        let profile = create_profile_in(&chrome("131.0.6778.85"), source.path(), "Work").unwrap();
+        std::fs::write(profile.path.join("Bookmarks"), "{}").unwrap();
+        std::fs::create_dir_all(profile.path.join("Cache/Cache_Data")).unwrap();
+        std::fs::write(profile.path.join("Cache/Cache_Data/data_0"), "x").unwrap();
+
+        let out = tempfile::tempdir().unwrap();
+        let archive = out.path().join("work.tar.gz");
+        let manifest =
+            export_profile_in(&chrome("131.0.6778.85"), source.path(), "Work", &archive).unwrap();
+        assert_eq!(manifest.files, 2, "Preferences and Bookmarks");
+        assert_eq!(read_backup_manifest(&archive).unwrap(), manifest);
+        let err = export_profile_in(&chrome("131"), source.path(), "Work", &archive).unwrap_err();
+        let Error::Io { path, source } = &err else {
+            panic!("unexpected error: {err}");
+        };
+        assert_eq!(
+            (path, source.kind()),
+            (&archive, std::io::ErrorKind::AlreadyExists)
+        );
+
+        let target = tempfile::tempdir().unwrap();
+        create_profile_in(&chrome("132.0"), target.path(), "Personal").unwrap();
+        let restored =
+            restore_profile_in(&chrome("132.0"), target.path(), &archive, Some("Work copy"))
+                .unwrap();
+        assert_eq!(restored.id.0, "Profile 1");
+        assert_eq!(
+            std::fs::read_to_string(restored.path.join("Bookmarks")).unwrap(),
+            "{}"
+        );
+        assert!(!restored.path.join("Cache").exists());
+        let profiles = list_profiles_in(&chrome("132.0"), target.path()).unwrap();
+        assert_eq!(profiles[1].name, "Work copy");
+        let preferences = std::fs::read_to_string(restored.path.join("Preferences")).unwrap();
+        assert!(preferences.contains("Work copy"), "{preferences}");
+    }
+
+    #[test]
+    fn refuses_incompatible_browsers() {
+        let source = tempfile::tempdir().unwrap();
+        create_profile_in(&chrome("131.0.6778.85"), source.path(), "Work").unwrap();
+        let out = tempfile::tempdir().unwrap();
+        let archive = out.path().join("work.tar.gz");
+        export_profile_in(&chrome("131.0.6778.85"), source.path(), "Work", &archive).unwrap();
+
+        let target = tempfile::tempdir().unwrap();
+        let err =
+            restore_profile_in(&chrome("131.0.6723.0"), target.path(), &archive, None).unwrap_err();
+        assert!(matches!(err, Error::IncompatibleBackup { .. }), "{err}");
+
+        let firefox = Browser::new("firefox", "Firefox", "/usr/bin/firefox".into())
+            .with_variant(BrowserVariant::firefox_stable());
+        // Version numbers of other browsers are not compared
+        let brave = Browser::new("brave", "Brave", "/usr/bin/brave-browser".into())
+            .with_variant(BrowserVariant::chromium_stable())
+            .with_version("1.73.91");
+        restore_profile_in(&brave, target.path(), &archive, None).unwrap();
+
+        let err = restore_profile_in(&firefox, target.path(), &archive, None).unwrap_err();
+        assert!(err.to_string().contains("chromium profile"), "{err}");
+        assert!(
+            list_profiles_in(&firefox, target.path())
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn compares_versions_numerically() {
+        assert!(version_parts("131.0.10") > version_parts("131.0.9"));
+        assert_eq!(version_parts("128.0b3"), [128, 0]);
+        assert!(version_parts("128.0") < version_parts("128.0.1"));
+    }
+}
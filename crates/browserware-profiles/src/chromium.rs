@@ -0,0 +1,583 @@
+//! Chromium-family profiles.
+//!
+//! Chromium-based browsers list their profiles in the `Local State` JSON file
+//! at the root of the user data directory:
+//!
+//! ```json
+//! {
+//!   "profile": {
+//!     "info_cache": {
+//!       "Default":   { "name": "Person 1", "user_name": "me@example.com", ... },
+//!       "Profile 3": { "name": "Work", "avatar_icon": "chrome://theme/IDR_PROFILE_AVATAR_26", ... }
+//!     },
+//!     "last_used": "Profile 3",
+//!     "last_active_profiles": ["Profile 3"],
+//!     "profiles_order": ["Default", "Profile 3"]
+//!   }
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use browserware_types::{BrowserFamily, Profile};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+use crate::json::read_json;
+use crate::write::{remove_profile_dir, write_atomic};
+use crate::{Error, Result};
+
+/// Name of the file listing a Chromium browser's profiles.
+pub const LOCAL_STATE: &str = "Local State";
+
+/// Directory of the profile Chromium creates first.
+pub(crate) const DEFAULT_PROFILE_DIR: &str = "Default";
+
+/// Prefix of built-in avatar icon URLs; the suffix is the avatar index.
+const AVATAR_ICON_PREFIX: &str = "chrome://theme/IDR_PROFILE_AVATAR_";
+
+/// A Chromium profile with the browser-specific details from `Local State`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChromiumProfile {
+    /// Browser-independent profile information
+    #[serde(flatten)]
+    pub profile: Profile,
+    /// Name of the signed-in Google account (if any)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gaia_name: Option<String>,
+    /// Index of the built-in avatar icon (if a built-in avatar is used)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avatar_index: Option<u32>,
+    /// Whether the display name is the generated default ("Person 1")
+    pub is_using_default_name: bool,
+    /// Whether the profile is managed by an organization
+    pub is_managed: bool,
+    /// Whether the profile is a supervised (child) profile
+    pub is_supervised: bool,
+}
+
+/// The profile list of a Chromium user data directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LocalState {
+    /// Profiles, in the browser's display order
+    pub profiles: Vec<ChromiumProfile>,
+    /// Directory of the most recently used profile (if recorded)
+    pub last_used: Option<String>,
+    /// Directories of the profiles open when the browser last exited
+    pub last_active: Vec<String>,
+}
+
+impl LocalState {
+    /// Read the profile list of a Chromium user data directory.
+    ///
+    /// A missing user data directory (the browser has never run) yields an
+    /// empty list. Browsers that keep a single profile at the root of the
+    /// user data directory (like Opera) or have not written `Local State` yet
+    /// yield that single profile.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] if `Local State` cannot be read and
+    /// [`Error::Parse`] if it is not valid JSON.
+    pub fn read(user_data_dir: &Path) -> Result<Self> {
+        let path = user_data_dir.join(LOCAL_STATE);
+        let mut state = match std::fs::read_to_string(&path) {
+            Ok(text) => Self::parse(&text, user_data_dir)?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Self::default(),
+            Err(err) => return Err(Error::io(path, err)),
+        };
+
+        if state.profiles.is_empty()
+            && let Some(profile) = single_profile(user_data_dir)
+        {
+            tracing::debug!(path = %profile.path.display(), "No profile list, using single profile");
+            state.profiles.push(ChromiumProfile::from_profile(profile));
+        }
+        Ok(state)
+    }
+
+    /// Parse the contents of a `Local State` file.
+    ///
+    /// Profile paths are resolved relative to `user_data_dir`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Parse`] if `text` is not valid JSON.
+    pub fn parse(text: &str, user_data_dir: &Path) -> Result<Self> {
+        let raw: RawLocalState = serde_json::from_str(text).map_err(|err| Error::Parse {
+            path: user_data_dir.join(LOCAL_STATE),
+            line: Some(err.line()),
+            message: err.to_string(),
+        })?;
+        let section = raw.profile;
+
+        let default_dir = section
+            .last_used
+            .clone()
+            .filter(|dir| section.info_cache.contains_key(dir))
+            .unwrap_or_else(|| DEFAULT_PROFILE_DIR.to_string());
+
+        let mut entries = section.info_cache;
+        let mut profiles = Vec::with_capacity(entries.len());
+        // Browser display order first, then any profiles missing from it
+        for dir in &section.profiles_order {
+            if let Some(entry) = entries.remove(dir) {
+                profiles.push(entry.into_profile(dir, user_data_dir, &default_dir));
+            }
+        }
+        for (dir, entry) in entries {
+            profiles.push(entry.into_profile(&dir, user_data_dir, &default_dir));
+        }
+
+        Ok(Self {
+            profiles,
+            last_used: section.last_used,
+            last_active: section.last_active_profiles,
+        })
+    }
+}
+
+impl ChromiumProfile {
+    const fn from_profile(profile: Profile) -> Self {
+        Self {
+            profile,
+            gaia_name: None,
+            avatar_index: None,
+            is_using_default_name: false,
+            is_managed: false,
+            is_supervised: false,
+        }
+    }
+}
+
+/// List the profiles of a Chromium user data directory.
+///
+/// # Errors
+///
+/// See [`LocalState::read`].
+pub fn list_profiles(user_data_dir: &Path) -> Result<Vec<Profile>> {
+    let state = LocalState::read(user_data_dir)?;
+    Ok(state.profiles.into_iter().map(|p| p.profile).collect())
+}
+
+/// Add a profile to a Chromium user data directory.
+///
+/// Creates the profile directory (`Default` for the first profile, then
+/// `Profile <n>`) with a `Preferences` file naming it, and registers it in
+/// `Local State`. The browser must not be running, or it overwrites
+/// `Local State` when it exits.
+///
+/// # Errors
+///
+/// Returns [`Error::Io`] or [`Error::Parse`] if `Local State` cannot be read
+/// or written.
+pub fn create_profile(user_data_dir: &Path, name: &str) -> Result<Profile> {
+    let mut created = None;
+    edit_local_state(user_data_dir, |section| {
+        let cache = object_entry(section, "info_cache");
+        let taken = |dir: &str| cache.contains_key(dir) || user_data_dir.join(dir).exists();
+        let dir = if taken(DEFAULT_PROFILE_DIR) {
+            (1..=u32::MAX)
+                .map(|n| format!("Profile {n}"))
+                .find(|dir| !taken(dir))
+                .unwrap_or_default()
+        } else {
+            DEFAULT_PROFILE_DIR.to_string()
+        };
+        cache.insert(
+            dir.clone(),
+            json!({
+                "name": name,
+                "is_using_default_name": false,
+                "avatar_icon": format!("{AVATAR_ICON_PREFIX}26"),
+            }),
+        );
+        if let Some(Value::Array(order)) = section.get_mut("profiles_order") {
+            order.push(Value::String(dir.clone()));
+        }
+        created = Some(dir);
+        Ok(())
+    })?;
+    let dir = created.unwrap_or_default();
+
+    let path = user_data_dir.join(&dir);
+    std::fs::create_dir_all(&path).map_err(|err| Error::io(&path, err))?;
+    let preferences = json!({ "profile": { "name": name } });
+    write_atomic(
+        &path.join("Preferences"),
+        preferences.to_string().as_bytes(),
+    )?;
+    Ok(Profile::new(dir, name, path, BrowserFamily::Chromium))
+}
+
+/// Rename a profile of a Chromium user data directory.
+///
+/// Updates the name in `Local State` and the profile's `Preferences`.
+///
+/// # Errors
+///
+/// Returns [`Error::NotRegistered`] if `Local State` lists no profile `id`, and
+/// [`Error::Io`] or [`Error::Parse`] if a file cannot be read or written.
+pub fn rename_profile(user_data_dir: &Path, id: &str, name: &str) -> Result<()> {
+    edit_local_state(user_data_dir, |section| {
+        let Some(Value::Object(entry)) = object_entry(section, "info_cache").get_mut(id) else {
+            return Err(Error::NotRegistered {
+                path: user_data_dir.join(LOCAL_STATE),
+                profile: id.to_string(),
+            });
+        };
+        entry.insert("name".to_string(), json!(name));
+        entry.insert("is_using_default_name".to_string(), json!(false));
+        Ok(())
+    })?;
+
+    let preferences = user_data_dir.join(id).join("Preferences");
+    if let Some(mut value) = read_json::<Value>(&preferences)? {
+        if let Some(root) = value.as_object_mut() {
+            object_entry(root, "profile").insert("name".to_string(), json!(name));
+        }
+        write_atomic(&preferences, value.to_string().as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Remove a profile from a Chromium user data directory.
+///
+/// Unregisters the profile from `Local State` and, unless `keep_files` is
+/// set, deletes its directory.
+///
+/// # Errors
+///
+/// Returns [`Error::Io`] or [`Error::Parse`] if a file cannot be read or
+/// written, or the directory cannot be deleted.
+pub fn delete_profile(user_data_dir: &Path, id: &str, keep_files: bool) -> Result<()> {
+    edit_local_state(user_data_dir, |section| {
+        object_entry(section, "info_cache").remove(id);
+        for list in ["profiles_order", "last_active_profiles"] {
+            if let Some(Value::Array(dirs)) = section.get_mut(list) {
+                dirs.retain(|dir| dir != id);
+            }
+        }
+        if section.get("last_used").and_then(Value::as_str) == Some(id) {
+            section.remove("last_used");
+        }
+        Ok(())
+    })?;
+
+    if !keep_files {
+        remove_profile_dir(user_data_dir, &user_data_dir.join(id))?;
+    }
+    Ok(())
+}
+
+/// Apply a change to the `profile` section of `Local State`.
+fn edit_local_state(
+    user_data_dir: &Path,
+    edit: impl FnOnce(&mut Map<String, Value>) -> Result<()>,
+) -> Result<()> {
+    let path = user_data_dir.join(LOCAL_STATE);
+    let mut state = read_json::<Value>(&path)?.unwrap_or_else(|| json!({}));
+    let Some(root) = state.as_object_mut() else {
+        return Err(Error::Parse {
+            path,
+            line: None,
+            message: "expected a JSON object".to_string(),
+        });
+    };
+    edit(object_entry(root, "profile"))?;
+
+    std::fs::create_dir_all(user_data_dir).map_err(|err| Error::io(user_data_dir, err))?;
+    write_atomic(&path, state.to_string().as_bytes())
+}
+
+/// Get an object member, replacing it with an empty object if it is not one.
+fn object_entry<'a>(map: &'a mut Map<String, Value>, key: &str) -> &'a mut Map<String, Value> {
+    let value = map.entry(key).or_insert_with(|| Value::Object(Map::new()));
+    if !value.is_object() {
+        *value = Value::Object(Map::new());
+    }
+    match value {
+        Value::Object(map) => map,
+        _ => unreachable!("value was just made an object"),
+    }
+}
+
+/// The only profile of a user data directory without a profile list.
+fn single_profile(user_data_dir: &Path) -> Option<Profile> {
+    let default = user_data_dir.join(DEFAULT_PROFILE_DIR);
+    let path = if default.join("Preferences").is_file() {
+        default
+    } else if user_data_dir.join("Preferences").is_file() {
+        user_data_dir.to_path_buf()
+    } else {
+        return None;
+    };
+    Some(
+        Profile::new(
+            DEFAULT_PROFILE_DIR,
+            DEFAULT_PROFILE_DIR,
+            path,
+            BrowserFamily::Chromium,
+        )
+        .with_default(true),
+    )
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct RawLocalState {
+    profile: RawProfileSection,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct RawProfileSection {
+    info_cache: BTreeMap<String, RawProfileEntry>,
+    last_used: Option<String>,
+    last_active_profiles: Vec<String>,
+    profiles_order: Vec<String>,
+}
+
+// Mirrors the flags stored in `Local State`
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct RawProfileEntry {
+    name: Option<String>,
+    gaia_name: Option<String>,
+    user_name: Option<String>,
+    avatar_icon: Option<String>,
+    is_using_default_name: bool,
+    /// Seconds since the Unix epoch, with fractional part
+    active_time: Option<f64>,
+    hosted_domain: Option<String>,
+    user_accepted_account_management: bool,
+    is_supervised: bool,
+    supervised_user_id: Option<String>,
+    is_child_account: bool,
+}
+
+impl RawProfileEntry {
+    fn into_profile(self, dir: &str, user_data_dir: &Path, default_dir: &str) -> ChromiumProfile {
+        let non_empty = |s: Option<String>| s.filter(|s| !s.is_empty());
+
+        let name = non_empty(self.name).unwrap_or_else(|| dir.to_string());
+        let path: PathBuf = user_data_dir.join(dir);
+        let mut profile =
+            Profile::new(dir, name, path, BrowserFamily::Chromium).with_default(dir == default_dir);
+        if let Some(email) = non_empty(self.user_name) {
+            profile = profile.with_email(email);
+        }
+        if let Some(avatar) = non_empty(self.avatar_icon.clone()) {
+            profile = profile.with_avatar(avatar);
+        }
+        if let Some(time) = self.active_time.filter(|t| t.is_finite() && *t > 0.0) {
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let secs = time as u64;
+            profile = profile.with_last_used(secs);
+        }
+
+        let hosted_domain = non_empty(self.hosted_domain);
+        ChromiumProfile {
+            profile,
+            gaia_name: non_empty(self.gaia_name),
+            avatar_index: self
+                .avatar_icon
+                .as_deref()
+                .and_then(|icon| icon.strip_prefix(AVATAR_ICON_PREFIX))
+                .and_then(|index| index.parse().ok()),
+            is_using_default_name: self.is_using_default_name,
+            is_managed: self.user_accepted_account_management
+                || hosted_domain.is_some_and(|d| d != "NO_HOSTED_DOMAIN"),
+            is_supervised: self.is_supervised
+                || self.is_child_account
+                || non_empty(self.supervised_user_id).is_some(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOCAL_STATE_JSON: &str = r#"{
+        "browser": { "enabled_labs_experiments": [] },
+        "profile": {
+            "info_cache": {
+                "Default": {
+                    "name": "Person 1",
+                    "is_using_default_name": true,
+                    "avatar_icon": "chrome://theme/IDR_PROFILE_AVATAR_26",
+                    "active_time": 1700000000.75
+                },
+                "Profile 3": {
+                    "name": "Work",
+                    "gaia_name": "Ada Lovelace",
+                    "user_name": "ada@corp.example.com",
+                    "hosted_domain": "corp.example.com",
+                    "avatar_icon": "https://lh3.googleusercontent.com/photo.jpg",
+                    "active_time": 1710000000.0
+                },
+                "Profile 5": {
+                    "name": "Kid",
+                    "user_name": "",
+                    "hosted_domain": "NO_HOSTED_DOMAIN",
+                    "is_supervised": true
+                }
+            },
+            "last_used": "Profile 3",
+            "last_active_profiles": ["Profile 3", "Default"],
+            "profiles_order": ["Profile 3", "Default"]
+        }
+    }"#;
+
+    #[test]
+    fn parses_info_cache() {
+        let dir = Path::new("/home/user/.config/google-chrome");
+        let state = LocalState::parse(LOCAL_STATE_JSON, dir).unwrap();
+
+        let ids: Vec<_> = state
+            .profiles
+            .iter()
+            .map(|p| p.profile.id.0.as_str())
+            .collect();
+        assert_eq!(ids, ["Profile 3", "Default", "Profile 5"]);
+        assert_eq!(state.last_used.as_deref(), Some("Profile 3"));
+        assert_eq!(state.last_active, ["Profile 3", "Default"]);
+
+        let work = &state.profiles[0];
+        assert_eq!(work.profile.name, "Work");
+        assert_eq!(work.profile.path, dir.join("Profile 3"));
+        assert_eq!(work.profile.email.as_deref(), Some("ada@corp.example.com"));
+        assert_eq!(work.gaia_name.as_deref(), Some("Ada Lovelace"));
+        assert_eq!(work.avatar_index, None);
+        assert_eq!(work.profile.last_used, Some(1_710_000_000));
+        assert!(work.profile.is_default);
+        assert!(work.is_managed);
+
+        let person = &state.profiles[1];
+        assert!(person.is_using_default_name);
+        assert_eq!(person.avatar_index, Some(26));
+        assert_eq!(person.profile.last_used, Some(1_700_000_000));
+        assert!(!person.profile.is_default);
+
+        let kid = &state.profiles[2];
+        assert_eq!(kid.profile.email, None);
+        assert!(kid.is_supervised);
+        assert!(!kid.is_managed);
+    }
+
+    #[test]
+    fn default_directory_is_default_without_last_used() {
+        let state = LocalState::parse(
+            r#"{"profile": {"info_cache": {"Default": {"name": "Me"}, "Profile 1": {}}}}"#,
+            Path::new("/data"),
+        )
+        .unwrap();
+        let default: Vec<_> = state
+            .profiles
+            .iter()
+            .filter(|p| p.profile.is_default)
+            .map(|p| p.profile.id.0.as_str())
+            .collect();
+        assert_eq!(default, ["Default"]);
+        // Missing names fall back to the directory
+        assert_eq!(state.profiles[1].profile.name, "Profile 1");
+    }
+
+    #[test]
+    fn invalid_json_is_a_parse_error() {
+        let err = LocalState::parse("{\n  \"profile\": ", Path::new("/data")).unwrap_err();
+        assert!(matches!(err, Error::Parse { line: Some(2), .. }), "{err}");
+    }
+
+    #[test]
+    fn reads_user_data_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(LOCAL_STATE), LOCAL_STATE_JSON).unwrap();
+
+        let profiles = list_profiles(dir.path()).unwrap();
+        assert_eq!(profiles.len(), 3);
+    }
+
+    #[test]
+    fn missing_user_data_directory_has_no_profiles() {
+        let dir = tempfile::tempdir().unwrap();
+        let profiles = list_profiles(&dir.path().join("never-started")).unwrap();
+        assert!(profiles.is_empty());
+    }
+
+    #[test]
+    fn single_root_profile_without_local_state() {
+        // Opera keeps its only profile at the root of the user data directory
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("Preferences"), "{}").unwrap();
+
+        let profiles = list_profiles(dir.path()).unwrap();
+        assert_eq!(profiles.len(), 1);
+        assert_eq!(profiles[0].path, dir.path());
+        assert!(profiles[0].is_default);
+    }
+
+    #[test]
+    fn creates_renames_and_deletes_profiles() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(LOCAL_STATE), LOCAL_STATE_JSON).unwrap();
+        std::fs::create_dir(dir.path().join("Default")).unwrap();
+
+        let created = create_profile(dir.path(), "Personal").unwrap();
+        assert_eq!(created.id.0, "Profile 1");
+        assert!(created.path.join("Preferences").is_file());
+        let find = |state: &LocalState| {
+            state
+                .profiles
+                .iter()
+                .find(|p| p.profile.id.0 == "Profile 1")
+                .cloned()
+                .unwrap()
+        };
+        let state = LocalState::read(dir.path()).unwrap();
+        assert_eq!(find(&state).profile.name, "Personal");
+        assert!(!find(&state).is_using_default_name);
+        // Appended to the display order
+        assert_eq!(state.profiles[2].profile.id.0, "Profile 1");
+        // Unrelated settings survive
+        let text = std::fs::read_to_string(dir.path().join(LOCAL_STATE)).unwrap();
+        assert!(text.contains("enabled_labs_experiments"));
+        assert!(crate::write::backup_path(&dir.path().join(LOCAL_STATE)).exists());
+
+        rename_profile(dir.path(), "Profile 1", "Home").unwrap();
+        let state = LocalState::read(dir.path()).unwrap();
+        assert_eq!(find(&state).profile.name, "Home");
+        let preferences = std::fs::read_to_string(created.path.join("Preferences")).unwrap();
+        assert!(preferences.contains("Home"));
+        // Unknown profiles are not added
+        let err = rename_profile(dir.path(), "Profile 9", "Ghost").unwrap_err();
+        assert!(matches!(err, Error::NotRegistered { .. }), "{err}");
+        let text = std::fs::read_to_string(dir.path().join(LOCAL_STATE)).unwrap();
+        assert!(!text.contains("Profile 9"));
+
+        delete_profile(dir.path(), "Profile 3", false).unwrap();
+        let state = LocalState::read(dir.path()).unwrap();
+        let ids: Vec<_> = state
+            .profiles
+            .iter()
+            .map(|p| p.profile.id.0.as_str())
+            .collect();
+        assert_eq!(ids, ["Default", "Profile 1", "Profile 5"]);
+        assert_eq!(state.last_used, None);
+        assert_eq!(state.last_active, ["Default"]);
+
+        delete_profile(dir.path(), "Profile 1", false).unwrap();
+        assert!(!created.path.exists());
+    }
+
+    #[test]
+    fn first_profile_is_default() {
+        let dir = tempfile::tempdir().unwrap();
+        let created = create_profile(dir.path(), "Work").unwrap();
+        assert_eq!(created.id.0, "Default");
+        assert_eq!(list_profiles(dir.path()).unwrap()[0].name, "Work");
+    }
+}
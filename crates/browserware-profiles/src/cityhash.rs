@@ -0,0 +1,238 @@
+//! `CityHash64`, version 1.0.
+//!
+//! Firefox identifies each installation by the `CityHash64` of its install
+//! directory (as UTF-16LE bytes), using the 1.0 revision of the algorithm that
+//! ships in mozilla-central. Later revisions produce different values.
+
+// Variable names follow the reference implementation
+#![allow(clippy::many_single_char_names)]
+
+const K0: u64 = 0xc3a5_c85c_97cb_3127;
+const K1: u64 = 0xb492_b66f_be98_f273;
+const K2: u64 = 0x9ae1_6a3b_2f90_404f;
+const K3: u64 = 0xc949_d7c7_509e_6557;
+
+/// Hash a byte slice.
+pub(crate) fn city_hash64(s: &[u8]) -> u64 {
+    let len = s.len();
+    if len <= 16 {
+        return hash_len_0_to_16(s);
+    }
+    if len <= 32 {
+        return hash_len_17_to_32(s);
+    }
+    if len <= 64 {
+        return hash_len_33_to_64(s);
+    }
+
+    let mut x = fetch64(s, len - 40);
+    let mut y = fetch64(s, len - 16).wrapping_add(fetch64(s, len - 56));
+    let mut z = hash_len16(
+        fetch64(s, len - 48).wrapping_add(len as u64),
+        fetch64(s, len - 24),
+    );
+    let mut v = weak_hash_len32_with_seeds(s, len - 64, len as u64, z);
+    let mut w = weak_hash_len32_with_seeds(s, len - 32, y.wrapping_add(K1), x);
+    x = x.wrapping_mul(K1).wrapping_add(fetch64(s, 0));
+
+    let mut offset = 0;
+    let mut remaining = (len - 1) & !63;
+    loop {
+        x = rotate(
+            x.wrapping_add(y)
+                .wrapping_add(v.0)
+                .wrapping_add(fetch64(s, offset + 8)),
+            37,
+        )
+        .wrapping_mul(K1);
+        y = rotate(
+            y.wrapping_add(v.1).wrapping_add(fetch64(s, offset + 48)),
+            42,
+        )
+        .wrapping_mul(K1);
+        x ^= w.1;
+        y = y.wrapping_add(v.0).wrapping_add(fetch64(s, offset + 40));
+        z = rotate(z.wrapping_add(w.0), 33).wrapping_mul(K1);
+        v = weak_hash_len32_with_seeds(s, offset, v.1.wrapping_mul(K1), x.wrapping_add(w.0));
+        w = weak_hash_len32_with_seeds(
+            s,
+            offset + 32,
+            z.wrapping_add(w.1),
+            y.wrapping_add(fetch64(s, offset + 16)),
+        );
+        std::mem::swap(&mut z, &mut x);
+        offset += 64;
+        remaining -= 64;
+        if remaining == 0 {
+            break;
+        }
+    }
+
+    hash_len16(
+        hash_len16(v.0, w.0)
+            .wrapping_add(shift_mix(y).wrapping_mul(K1))
+            .wrapping_add(z),
+        hash_len16(v.1, w.1).wrapping_add(x),
+    )
+}
+
+fn fetch64(s: &[u8], i: usize) -> u64 {
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&s[i..i + 8]);
+    u64::from_le_bytes(bytes)
+}
+
+fn fetch32(s: &[u8], i: usize) -> u64 {
+    let mut bytes = [0; 4];
+    bytes.copy_from_slice(&s[i..i + 4]);
+    u64::from(u32::from_le_bytes(bytes))
+}
+
+const fn rotate(v: u64, shift: u32) -> u64 {
+    if shift == 0 { v } else { v.rotate_right(shift) }
+}
+
+const fn shift_mix(v: u64) -> u64 {
+    v ^ (v >> 47)
+}
+
+const fn hash_len16(u: u64, v: u64) -> u64 {
+    const MUL: u64 = 0x9ddf_ea08_eb38_2d69;
+    let mut a = (u ^ v).wrapping_mul(MUL);
+    a ^= a >> 47;
+    let mut b = (v ^ a).wrapping_mul(MUL);
+    b ^= b >> 47;
+    b.wrapping_mul(MUL)
+}
+
+fn hash_len_0_to_16(s: &[u8]) -> u64 {
+    let len = s.len();
+    if len > 8 {
+        let a = fetch64(s, 0);
+        let b = fetch64(s, len - 8);
+        // `len` is at most 16, so the rotation amount fits
+        #[allow(clippy::cast_possible_truncation)]
+        let shift = len as u32;
+        return hash_len16(a, b.wrapping_add(len as u64).rotate_right(shift)) ^ b;
+    }
+    if len >= 4 {
+        let a = fetch32(s, 0);
+        return hash_len16((len as u64).wrapping_add(a << 3), fetch32(s, len - 4));
+    }
+    if len > 0 {
+        let a = u32::from(s[0]);
+        let b = u32::from(s[len >> 1]);
+        let c = u32::from(s[len - 1]);
+        let y = a.wrapping_add(b << 8);
+        #[allow(clippy::cast_possible_truncation)]
+        let z = (len as u32).wrapping_add(c << 2);
+        return shift_mix(u64::from(y).wrapping_mul(K2) ^ u64::from(z).wrapping_mul(K3))
+            .wrapping_mul(K2);
+    }
+    K2
+}
+
+fn hash_len_17_to_32(s: &[u8]) -> u64 {
+    let len = s.len();
+    let a = fetch64(s, 0).wrapping_mul(K1);
+    let b = fetch64(s, 8);
+    let c = fetch64(s, len - 8).wrapping_mul(K2);
+    let d = fetch64(s, len - 16).wrapping_mul(K0);
+    hash_len16(
+        rotate(a.wrapping_sub(b), 43)
+            .wrapping_add(rotate(c, 30))
+            .wrapping_add(d),
+        a.wrapping_add(rotate(b ^ K3, 20))
+            .wrapping_sub(c)
+            .wrapping_add(len as u64),
+    )
+}
+
+fn hash_len_33_to_64(s: &[u8]) -> u64 {
+    let len = s.len();
+    let mut z = fetch64(s, 24);
+    let mut a = fetch64(s, 0).wrapping_add(
+        (len as u64)
+            .wrapping_add(fetch64(s, len - 16))
+            .wrapping_mul(K0),
+    );
+    let mut b = rotate(a.wrapping_add(z), 52);
+    let mut c = rotate(a, 37);
+    a = a.wrapping_add(fetch64(s, 8));
+    c = c.wrapping_add(rotate(a, 7));
+    a = a.wrapping_add(fetch64(s, 16));
+    let vf = a.wrapping_add(z);
+    let vs = b.wrapping_add(rotate(a, 31)).wrapping_add(c);
+
+    a = fetch64(s, 16).wrapping_add(fetch64(s, len - 32));
+    z = fetch64(s, len - 8);
+    b = rotate(a.wrapping_add(z), 52);
+    c = rotate(a, 37);
+    a = a.wrapping_add(fetch64(s, len - 24));
+    c = c.wrapping_add(rotate(a, 7));
+    a = a.wrapping_add(fetch64(s, len - 16));
+    let wf = a.wrapping_add(z);
+    let ws = b.wrapping_add(rotate(a, 31)).wrapping_add(c);
+
+    let r = shift_mix(
+        vf.wrapping_add(ws)
+            .wrapping_mul(K2)
+            .wrapping_add(wf.wrapping_add(vs).wrapping_mul(K0)),
+    );
+    shift_mix(r.wrapping_mul(K0).wrapping_add(vs)).wrapping_mul(K2)
+}
+
+fn weak_hash_len32_with_seeds(s: &[u8], offset: usize, a: u64, b: u64) -> (u64, u64) {
+    let w = fetch64(s, offset);
+    let x = fetch64(s, offset + 8);
+    let y = fetch64(s, offset + 16);
+    let z = fetch64(s, offset + 24);
+
+    let mut a = a.wrapping_add(w);
+    let mut b = rotate(b.wrapping_add(a).wrapping_add(z), 21);
+    let c = a;
+    a = a.wrapping_add(x).wrapping_add(y);
+    b = b.wrapping_add(rotate(a, 44));
+    (a.wrapping_add(z), b.wrapping_add(c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16(s: &str) -> Vec<u8> {
+        s.encode_utf16().flat_map(u16::to_le_bytes).collect()
+    }
+
+    #[test]
+    fn matches_firefox_install_hashes() {
+        // Hashes Firefox writes to installs.ini for these install directories
+        assert_eq!(
+            city_hash64(&utf16(r"C:\Program Files\Mozilla Firefox")),
+            0x3080_46B0_AF4A_39CB
+        );
+        assert_eq!(
+            city_hash64(&utf16("/usr/lib/firefox")),
+            0x4F96_D193_2A9F_858E
+        );
+    }
+
+    #[test]
+    fn covers_every_length_class() {
+        assert_eq!(city_hash64(b""), 0x9AE1_6A3B_2F90_404F);
+        assert_eq!(city_hash64(&utf16("a")), 0x8235_F11B_3662_0B8C);
+        assert_eq!(city_hash64(&utf16("abc")), 0x5E31_D62D_5C9F_FAAC);
+        assert_eq!(city_hash64(&utf16("abcdefgh")), 0x9360_FB1D_E751_8917);
+        assert_eq!(city_hash64(&utf16("/opt/firefox")), 0x06AF_DA46_A1A8_AD48);
+        assert_eq!(
+            city_hash64(&utf16("/usr/lib/firefox-esr")),
+            0x3B60_7381_1A6A_BF12
+        );
+        assert_eq!(
+            city_hash64(&utf16(
+                "/home/user/.local/share/applications/very/long/path/to/firefox/install"
+            )),
+            0xE899_2F73_9F6A_F317
+        );
+    }
+}
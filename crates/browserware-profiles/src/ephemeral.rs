@@ -0,0 +1,451 @@
+//! Throwaway profiles for untrusted links and clean repro sessions.
+//!
+//! An ephemeral profile is a fresh directory the browser is pointed at
+//! instead of its user data directory: a Chromium `--user-data-dir`, a
+//! Firefox `--profile` or a GNOME Web `--profile`. It can be seeded from a
+//! template directory (preferences, `user.js`, extensions) whose contents are
+//! copied into the new profile.
+//!
+//! The directory is removed when the [`EphemeralProfile`] is dropped or
+//! [waited on](EphemeralProfile::wait). A profile handed to a detached browser
+//! with [`EphemeralProfile::detach`] records the browser's process ID instead,
+//! and is removed by [`sweep_ephemeral`] once that process has exited. Every
+//! [`EphemeralProfileBuilder::create`] sweeps first, so leftovers disappear on
+//! the next start.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use browserware_profiles::ephemeral::EphemeralProfile;
+//!
+//! let chrome = browserware_detect::detect_browser("chrome").expect("chrome");
+//! let profile = EphemeralProfile::builder(&chrome).create()?;
+//! let mut child = std::process::Command::new(&chrome.executable)
+//!     .args(profile.launch_args())
+//!     .arg("https://example.com")
+//!     .spawn()?;
+//! profile.wait(&mut child)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::path::{Path, PathBuf};
+use std::process::{Child, ExitStatus};
+
+use browserware_types::{Browser, BrowserFamily, Packaging, Profile};
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, Result};
+
+/// Name of the directory holding ephemeral profiles.
+pub const EPHEMERAL_DIR: &str = "browserware-ephemeral";
+
+/// File in each ephemeral profile recording the processes that use it.
+const MARKER_FILE: &str = ".browserware-ephemeral.json";
+
+/// Chromium sentinel file that suppresses the first-run experience.
+const CHROMIUM_FIRST_RUN: &str = "First Run";
+
+/// Directories younger than this are never swept without a marker, as their
+/// creator may not have written it yet.
+const MARKER_GRACE: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Processes using an ephemeral profile.
+#[derive(Debug, Serialize, Deserialize)]
+struct Marker {
+    /// Process that created the profile
+    owner_pid: u32,
+    /// Browser process the profile was handed to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    browser_pid: Option<u32>,
+}
+
+impl Marker {
+    fn read(dir: &Path) -> Option<Self> {
+        let text = std::fs::read_to_string(dir.join(MARKER_FILE)).ok()?;
+        serde_json::from_str(&text).ok()
+    }
+
+    fn write(&self, dir: &Path) -> Result<()> {
+        let path = dir.join(MARKER_FILE);
+        serde_json::to_vec(self)
+            .map_err(std::io::Error::from)
+            .and_then(|json| std::fs::write(&path, json))
+            .map_err(|err| Error::io(&path, err))
+    }
+
+    /// Whether the process using the profile may still be running: the
+    /// browser once the profile is handed over, its creator until then.
+    fn is_live(&self) -> bool {
+        // Without procfs liveness is unknown, so the profile is kept
+        let pid = self.browser_pid.unwrap_or(self.owner_pid);
+        crate::lock::process_exists(pid) != Some(false)
+    }
+}
+
+/// Builder for an [`EphemeralProfile`].
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct EphemeralProfileBuilder {
+    family: BrowserFamily,
+    browser: String,
+    base_dir: PathBuf,
+    template: Option<PathBuf>,
+}
+
+impl EphemeralProfileBuilder {
+    /// Start building an ephemeral profile for `browser`.
+    ///
+    /// Profiles are created in [`default_base_dir`] unless
+    /// [`with_base_dir`](Self::with_base_dir) is used.
+    pub fn new(browser: &Browser) -> Self {
+        Self {
+            family: browser.family(),
+            browser: browser.id.0.clone(),
+            base_dir: default_base_dir(browser),
+            template: None,
+        }
+    }
+
+    /// Create the profile in `dir` instead of the default location.
+    pub fn with_base_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.base_dir = dir.into();
+        self
+    }
+
+    /// Copy the contents of `dir` into the new profile.
+    ///
+    /// For Chromium the template is the contents of a profile directory
+    /// (e.g. `Preferences`, `Extensions/`); for Firefox, of a profile
+    /// (e.g. `user.js`, `extensions/`).
+    pub fn with_template(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.template = Some(dir.into());
+        self
+    }
+
+    /// Sweep stale ephemeral profiles and create a new one.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::UnsupportedBrowser`] for browser families without profile directories
+    /// - [`Error::Io`] if the directory cannot be created or the template copied
+    pub fn create(self) -> Result<EphemeralProfile> {
+        if self.family == BrowserFamily::Other {
+            return Err(Error::UnsupportedBrowser {
+                browser: self.browser,
+            });
+        }
+        if let Err(err) = sweep_ephemeral(&self.base_dir) {
+            tracing::warn!("Cannot sweep ephemeral profiles: {err}");
+        }
+
+        std::fs::create_dir_all(&self.base_dir).map_err(|err| Error::io(&self.base_dir, err))?;
+        let dir = create_unique_dir(&self.base_dir, &self.browser)?;
+        let profile = EphemeralProfile {
+            profile: Profile::new(
+                dir.file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+                "Ephemeral",
+                profile_path(&dir, self.family),
+                self.family,
+            ),
+            dir,
+            detached: false,
+        };
+        // From here on, dropping `profile` removes the directory on failure
+        Marker {
+            owner_pid: std::process::id(),
+            browser_pid: None,
+        }
+        .write(&profile.dir)?;
+
+        std::fs::create_dir_all(&profile.profile.path)
+            .map_err(|err| Error::io(&profile.profile.path, err))?;
+        if self.family == BrowserFamily::Chromium {
+            let sentinel = profile.dir.join(CHROMIUM_FIRST_RUN);
+            std::fs::write(&sentinel, "").map_err(|err| Error::io(&sentinel, err))?;
+        }
+        if let Some(template) = &self.template {
+            copy_dir(template, &profile.profile.path)?;
+        }
+        tracing::debug!(dir = %profile.dir.display(), "Created ephemeral profile");
+        Ok(profile)
+    }
+}
+
+/// A temporary profile directory, removed when no longer used.
+#[derive(Debug)]
+pub struct EphemeralProfile {
+    profile: Profile,
+    dir: PathBuf,
+    detached: bool,
+}
+
+impl EphemeralProfile {
+    /// Start building an ephemeral profile for `browser`.
+    pub fn builder(browser: &Browser) -> EphemeralProfileBuilder {
+        EphemeralProfileBuilder::new(browser)
+    }
+
+    /// The profile to launch.
+    #[must_use]
+    pub const fn profile(&self) -> &Profile {
+        &self.profile
+    }
+
+    /// The directory removed with the profile (the user data directory for
+    /// Chromium, the profile directory otherwise).
+    #[must_use]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Command-line arguments that make the browser use this profile in a
+    /// new instance, without first-run prompts.
+    #[must_use]
+    pub fn launch_args(&self) -> Vec<String> {
+        let dir = self.dir.display();
+        match self.profile.family {
+            BrowserFamily::Chromium => vec![
+                format!("--user-data-dir={dir}"),
+                "--no-first-run".to_string(),
+                "--no-default-browser-check".to_string(),
+            ],
+            BrowserFamily::Firefox => vec![
+                "--profile".to_string(),
+                dir.to_string(),
+                "--no-remote".to_string(),
+            ],
+            BrowserFamily::WebKit | BrowserFamily::Other => vec![format!("--profile={dir}")],
+        }
+    }
+
+    /// Wait for the browser using the profile to exit, then remove it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] if waiting fails or the directory cannot be removed.
+    pub fn wait(self, child: &mut Child) -> Result<ExitStatus> {
+        let status = child.wait().map_err(|err| Error::io(&self.dir, err))?;
+        self.remove()?;
+        Ok(status)
+    }
+
+    /// Hand the profile to a browser process that outlives this one.
+    ///
+    /// The directory is kept and removed by the next [`sweep_ephemeral`]
+    /// after process `pid` has exited.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] if the marker file cannot be written.
+    pub fn detach(mut self, pid: u32) -> Result<PathBuf> {
+        Marker {
+            owner_pid: std::process::id(),
+            browser_pid: Some(pid),
+        }
+        .write(&self.dir)?;
+        self.detached = true;
+        Ok(self.dir.clone())
+    }
+
+    /// Remove the profile now.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] if the directory cannot be removed.
+    pub fn remove(mut self) -> Result<()> {
+        self.detached = true;
+        remove_dir(&self.dir)
+    }
+}
+
+impl Drop for EphemeralProfile {
+    fn drop(&mut self) {
+        if !self.detached
+            && let Err(err) = remove_dir(&self.dir)
+        {
+            tracing::warn!("Cannot remove ephemeral profile: {err}");
+        }
+    }
+}
+
+/// Where ephemeral profiles of `browser` are created.
+///
+/// Native browsers use the system temporary directory. Flatpak and Snap
+/// browsers cannot see the host's `/tmp`, so their profiles are created next
+/// to their user data directory, inside the sandbox's own home.
+#[must_use]
+pub fn default_base_dir(browser: &Browser) -> PathBuf {
+    let sandboxed = browser.packaging != Packaging::Native;
+    sandboxed
+        .then(|| browserware_detect::user_data_dir(browser))
+        .flatten()
+        .and_then(|dir| dir.parent().map(|parent| parent.join(EPHEMERAL_DIR)))
+        .unwrap_or_else(|| std::env::temp_dir().join(EPHEMERAL_DIR))
+}
+
+/// Remove ephemeral profiles in `base_dir` whose processes have exited.
+///
+/// Returns the removed directories. A missing `base_dir` is not an error.
+///
+/// # Errors
+///
+/// Returns [`Error::Io`] if `base_dir` cannot be listed.
+pub fn sweep_ephemeral(base_dir: &Path) -> Result<Vec<PathBuf>> {
+    let entries = match std::fs::read_dir(base_dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(Error::io(base_dir, err)),
+    };
+
+    let mut removed = Vec::new();
+    for dir in entries.flatten().map(|entry| entry.path()) {
+        if !dir.is_dir() {
+            continue;
+        }
+        let stale = Marker::read(&dir).map_or_else(|| is_old(&dir), |marker| !marker.is_live());
+        if !stale {
+            continue;
+        }
+        match remove_dir(&dir) {
+            Ok(()) => removed.push(dir),
+            Err(err) => tracing::warn!("Cannot remove stale ephemeral profile: {err}"),
+        }
+    }
+    if !removed.is_empty() {
+        tracing::debug!(count = removed.len(), "Swept stale ephemeral profiles");
+    }
+    Ok(removed)
+}
+
+/// The profile directory inside an ephemeral directory.
+fn profile_path(dir: &Path, family: BrowserFamily) -> PathBuf {
+    match family {
+        BrowserFamily::Chromium => dir.join(crate::chromium::DEFAULT_PROFILE_DIR),
+        _ => dir.to_path_buf(),
+    }
+}
+
+fn create_unique_dir(base_dir: &Path, browser: &str) -> Result<PathBuf> {
+    loop {
+        let dir = base_dir.join(format!("{browser}-{}", crate::firefox::random_salt()));
+        match std::fs::create_dir(&dir) {
+            Ok(()) => return Ok(dir),
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => {}
+            Err(err) => return Err(Error::io(&dir, err)),
+        }
+    }
+}
+
+fn is_old(dir: &Path) -> bool {
+    std::fs::metadata(dir)
+        .and_then(|meta| meta.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|age| age > MARKER_GRACE)
+}
+
+fn remove_dir(dir: &Path) -> Result<()> {
+    match std::fs::remove_dir_all(dir) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(Error::io(dir, err)),
+    }
+}
+
+/// Copy a directory tree, following symbolic links.
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    std::fs::create_dir_all(to).map_err(|err| Error::io(to, err))?;
+    for entry in std::fs::read_dir(from).map_err(|err| Error::io(from, err))? {
+        let entry = entry.map_err(|err| Error::io(from, err))?;
+        let source = entry.path();
+        let target = to.join(entry.file_name());
+        if source.is_dir() {
+            copy_dir(&source, &target)?;
+        } else {
+            std::fs::copy(&source, &target).map_err(|err| Error::io(&source, err))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use browserware_types::BrowserVariant;
+
+    fn browser(variant: BrowserVariant) -> Browser {
+        Browser::new("test", "Test", "/usr/bin/test".into()).with_variant(variant)
+    }
+
+    #[test]
+    fn creates_seeds_and_removes_chromium_profile() {
+        let base = tempfile::tempdir().unwrap();
+        let template = tempfile::tempdir().unwrap();
+        std::fs::create_dir(template.path().join("Extensions")).unwrap();
+        std::fs::write(template.path().join("Extensions/ext.crx"), "crx").unwrap();
+        std::fs::write(template.path().join("Preferences"), "{}").unwrap();
+
+        let profile = EphemeralProfile::builder(&browser(BrowserVariant::chromium_stable()))
+            .with_base_dir(base.path())
+            .with_template(template.path())
+            .create()
+            .unwrap();
+        let dir = profile.dir().to_path_buf();
+        assert!(dir.join("First Run").exists());
+        assert_eq!(profile.profile().path, dir.join("Default"));
+        assert!(dir.join("Default/Extensions/ext.crx").exists());
+        assert!(dir.join("Default/Preferences").exists());
+        assert_eq!(
+            profile.launch_args()[0],
+            format!("--user-data-dir={}", dir.display())
+        );
+
+        drop(profile);
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn detached_profiles_are_swept_after_the_browser_exits() {
+        let base = tempfile::tempdir().unwrap();
+        let firefox = browser(BrowserVariant::firefox_stable());
+        let create = || {
+            EphemeralProfile::builder(&firefox)
+                .with_base_dir(base.path())
+                .create()
+                .unwrap()
+        };
+
+        let current = create();
+        let running = create().detach(std::process::id()).unwrap();
+        // PIDs are capped well below this on every supported system
+        let exited = create().detach(u32::MAX - 1).unwrap();
+        assert_eq!(current.profile().path, current.dir());
+        assert_eq!(current.launch_args()[0], "--profile");
+
+        if crate::lock::process_exists(std::process::id()).is_none() {
+            return;
+        }
+        let removed = sweep_ephemeral(base.path()).unwrap();
+        assert_eq!(removed, std::slice::from_ref(&exited));
+        assert!(running.exists());
+        assert!(!exited.exists());
+        assert!(current.dir().exists());
+
+        // Creating a profile sweeps too
+        let later = create().detach(u32::MAX - 1).unwrap();
+        create();
+        assert!(!later.exists());
+    }
+
+    #[test]
+    fn sweep_of_missing_dir_is_empty() {
+        let base = tempfile::tempdir().unwrap();
+        assert!(
+            sweep_ephemeral(&base.path().join("none"))
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,304 @@
+//! Error types for profile management.
+
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+/// Result type alias using the profile management Error type.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Errors that can occur while reading or changing browser profiles.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// Profile was not found
+    #[error("profile '{profile}' not found in {browser}")]
+    NotFound {
+        /// Browser ID
+        browser: String,
+        /// Profile ID or name that was looked up
+        profile: String,
+    },
+
+    /// Profile directory is not registered with the browser
+    #[error("profile '{profile}' is not registered in {}", .path.display())]
+    NotRegistered {
+        /// Registry of the browser's profiles (`Local State`, `profiles.ini`)
+        path: PathBuf,
+        /// Profile ID
+        profile: String,
+    },
+
+    /// Profile name cannot be used
+    #[error("invalid profile name '{name}': {message}")]
+    InvalidName {
+        /// Rejected name
+        name: String,
+        /// Description of the problem
+        message: String,
+    },
+
+    /// Profile already exists
+    #[error("profile '{profile}' already exists in {browser}")]
+    AlreadyExists {
+        /// Browser ID
+        browser: String,
+        /// Name of the existing profile
+        profile: String,
+    },
+
+    /// Profile cannot be changed because a browser is using it
+    #[error("profile '{profile}' of {browser} is in use: {reason}")]
+    InUse {
+        /// Browser ID
+        browser: String,
+        /// Profile ID or name
+        profile: String,
+        /// What is using the profile
+        reason: String,
+    },
+
+    /// Profile query matches more than one profile
+    #[error("profile '{query}' is ambiguous in {browser}: matches {}", .candidates.join(", "))]
+    Ambiguous {
+        /// Browser ID
+        browser: String,
+        /// Profile query
+        query: String,
+        /// IDs of the matching profiles
+        candidates: Vec<String>,
+    },
+
+    /// Container was not found in a profile
+    #[error("container '{container}' not found in profile '{profile}'")]
+    ContainerNotFound {
+        /// Profile ID
+        profile: String,
+        /// Container name or ID that was looked up
+        container: String,
+    },
+
+    /// A browser add-on needed for the operation is not installed
+    #[error("add-on '{addon}' is not installed in profile '{profile}': {reason}")]
+    AddonMissing {
+        /// Profile ID
+        profile: String,
+        /// Add-on name
+        addon: String,
+        /// What the add-on is needed for
+        reason: String,
+    },
+
+    /// No candidate of an identity is available on this machine
+    #[error("identity '{identity}' is not available on this machine{}", tried_list(.tried))]
+    IdentityUnavailable {
+        /// Identity name
+        identity: String,
+        /// Candidates that were tried
+        tried: Vec<String>,
+    },
+
+    /// Backup cannot be restored into the browser
+    #[error("cannot restore {}: {reason}", .archive.display())]
+    IncompatibleBackup {
+        /// Backup archive
+        archive: PathBuf,
+        /// Why the backup is incompatible
+        reason: String,
+    },
+
+    /// Profile query is malformed
+    #[error("invalid profile '{query}': {message}")]
+    InvalidQuery {
+        /// Profile query
+        query: String,
+        /// Description of the problem
+        message: String,
+    },
+
+    /// Browser does not store profiles in a supported format
+    #[error("profiles of {browser} are not supported")]
+    UnsupportedBrowser {
+        /// Browser ID
+        browser: String,
+    },
+
+    /// Location of the browser's user data is unknown
+    #[error("user data directory of {browser} is unknown on this platform")]
+    UnknownDataDir {
+        /// Browser ID
+        browser: String,
+    },
+
+    /// Profile data file could not be parsed
+    #[error("failed to parse {}{}: {message}", .path.display(), .line.map(|l| format!(" at line {l}")).unwrap_or_default())]
+    Parse {
+        /// File being parsed
+        path: PathBuf,
+        /// 1-based line of the problem (if known)
+        line: Option<usize>,
+        /// Description of the problem
+        message: String,
+    },
+
+    /// Profile database could not be read
+    #[error("failed to read {}: {source}", .path.display())]
+    Database {
+        /// Database file
+        path: PathBuf,
+        /// Underlying SQLite error
+        source: rusqlite::Error,
+    },
+
+    /// I/O error on a profile file or directory
+    #[error("I/O error at {}: {source}", .path.display())]
+    Io {
+        /// File or directory involved
+        path: PathBuf,
+        /// Underlying I/O error
+        source: std::io::Error,
+    },
+}
+
+impl Error {
+    /// Create an I/O error for a specific path.
+    pub fn io(path: impl Into<PathBuf>, source: std::io::Error) -> Self {
+        Self::Io {
+            path: path.into(),
+            source,
+        }
+    }
+}
+
+fn tried_list(tried: &[String]) -> String {
+    if tried.is_empty() {
+        String::new()
+    } else {
+        format!(" (tried {})", tried.join(", "))
+    }
+}
+
+impl From<Error> for browserware_types::Error {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::NotFound { browser, profile } => Self::ProfileNotFound {
+                browser: Some(browser),
+                profile,
+            },
+            Error::NotRegistered { profile, .. } => Self::ProfileNotFound {
+                browser: None,
+                profile,
+            },
+            Error::InvalidName { .. } | Error::AlreadyExists { .. } => Self::InvalidArgument {
+                argument: "name".to_string(),
+                message: err.to_string(),
+            },
+            Error::InUse {
+                browser,
+                profile,
+                reason,
+            } => Self::ProfileInUse {
+                browser: Some(browser),
+                profile,
+                reason,
+            },
+            Error::Ambiguous {
+                browser,
+                query,
+                candidates,
+            } => Self::ProfileAmbiguous {
+                browser: Some(browser),
+                profile: query,
+                candidates,
+            },
+            Error::ContainerNotFound { profile, container } => {
+                Self::ContainerNotFound { profile, container }
+            }
+            Error::AddonMissing {
+                profile,
+                addon,
+                reason,
+            } => Self::AddonMissing {
+                profile,
+                addon,
+                reason,
+            },
+            Error::IdentityUnavailable { identity, .. } => Self::ProfileNotFound {
+                browser: None,
+                profile: format!("{}:{identity}", browserware_types::BrowserTarget::IDENTITY),
+            },
+            Error::IncompatibleBackup { .. } => Self::InvalidArgument {
+                argument: "backup".to_string(),
+                message: err.to_string(),
+            },
+            Error::InvalidQuery { query, message } => Self::InvalidTarget {
+                input: query,
+                reason: message,
+            },
+            Error::UnsupportedBrowser { .. } => Self::InvalidArgument {
+                argument: "browser".to_string(),
+                message: err.to_string(),
+            },
+            Error::UnknownDataDir { .. } => Self::UnsupportedPlatform(err.to_string()),
+            Error::Parse {
+                path,
+                line,
+                message,
+            } => Self::Parse {
+                path: Some(path),
+                line,
+                column: None,
+                message,
+                source: None,
+            },
+            Error::Database { path, source } => Self::Parse {
+                path: Some(path),
+                line: None,
+                column: None,
+                message: "failed to read profile database".to_string(),
+                source: Some(Box::new(source)),
+            },
+            Error::Io { path, source } => Self::io_at(path, source),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use browserware_types::ErrorCode;
+
+    #[test]
+    fn converts_into_shared_error() {
+        let err: browserware_types::Error = Error::NotFound {
+            browser: "chrome".into(),
+            profile: "Work".into(),
+        }
+        .into();
+        assert_eq!(err.code(), ErrorCode::ProfileNotFound);
+
+        let err: browserware_types::Error = Error::NotRegistered {
+            path: "Local State".into(),
+            profile: "Profile 9".into(),
+        }
+        .into();
+        assert_eq!(err.code(), ErrorCode::ProfileNotFound);
+
+        let err: browserware_types::Error = Error::Parse {
+            path: "profiles.ini".into(),
+            line: Some(4),
+            message: "missing Path".into(),
+        }
+        .into();
+        assert_eq!(err.code(), ErrorCode::Parse);
+        assert_eq!(
+            err.to_string(),
+            "failed to parse profiles.ini at line 4: missing Path"
+        );
+
+        let err: browserware_types::Error = Error::UnsupportedBrowser {
+            browser: "safari".into(),
+        }
+        .into();
+        assert_eq!(err.code(), ErrorCode::InvalidArgument);
+    }
+}
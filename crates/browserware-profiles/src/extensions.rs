@@ -0,0 +1,389 @@
+//! Extensions installed in a profile.
+//!
+//! - Chromium records extensions in the profile's `Preferences` and
+//!   `Secure Preferences` under `extensions.settings.<id>`, with the manifest
+//!   (or the path to it), an enabled state and an install location.
+//! - Firefox lists add-ons of every type in the profile's `extensions.json`;
+//!   only those of type `extension` are reported.
+
+use std::path::{Path, PathBuf};
+
+use browserware_types::{BrowserFamily, Profile};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::json::read_json;
+use crate::{Error, Result};
+
+/// Name of the file listing a Firefox profile's add-ons.
+pub const FIREFOX_EXTENSIONS_JSON: &str = "extensions.json";
+
+/// Chromium preference files holding extension settings, later ones winning.
+const CHROMIUM_PREFERENCE_FILES: [&str; 2] = ["Preferences", "Secure Preferences"];
+
+/// An extension installed in a profile.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Extension {
+    /// Extension ID (Chromium: 32 letters; Firefox: add-on ID such as `name@example.com`)
+    pub id: String,
+    /// Display name (the ID when the browser does not record one)
+    pub name: String,
+    /// Installed version
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Whether the extension is enabled
+    pub enabled: bool,
+    /// How the extension was installed
+    pub source: InstallSource,
+}
+
+/// How an extension was installed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum InstallSource {
+    /// From the browser's extension store (Chrome Web Store, addons.mozilla.org)
+    Store,
+    /// By the user from a file or another site
+    User,
+    /// By other software on the machine (external extension files or registry)
+    External,
+    /// By enterprise policy
+    Policy,
+    /// Loaded unpacked or temporarily for development
+    Development,
+    /// Shipped with the browser
+    Builtin,
+    /// Not recorded or not recognized
+    Unknown,
+}
+
+impl InstallSource {
+    /// Returns the canonical string name for this source.
+    #[must_use]
+    pub const fn canonical_name(self) -> &'static str {
+        match self {
+            Self::Store => "store",
+            Self::User => "user",
+            Self::External => "external",
+            Self::Policy => "policy",
+            Self::Development => "development",
+            Self::Builtin => "builtin",
+            Self::Unknown => "unknown",
+        }
+    }
+
+    /// Source of a Chromium `location` (`mojom::ManifestLocation`).
+    const fn from_chromium(location: i64, from_webstore: bool) -> Self {
+        match location {
+            1 if from_webstore => Self::Store,
+            1 => Self::User,
+            2 | 3 | 6 => Self::External,
+            4 | 8 => Self::Development,
+            5 | 10 => Self::Builtin,
+            7 | 9 => Self::Policy,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// Source of a Firefox add-on from its install location and telemetry.
+    fn from_firefox(location: &str, install_source: Option<&str>) -> Self {
+        match location {
+            "app-profile" => match install_source {
+                Some("amo" | "disco") => Self::Store,
+                Some("enterprise-policy") => Self::Policy,
+                _ => Self::User,
+            },
+            "app-temporary" => Self::Development,
+            "app-builtin" | "app-builtin-addons" | "app-system-defaults" | "app-system-addons" => {
+                Self::Builtin
+            }
+            "app-system-share" | "app-system-local" | "app-system-user" | "app-global"
+            | "winreg-app-user" | "winreg-app-global" => Self::External,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+impl std::fmt::Display for InstallSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.canonical_name())
+    }
+}
+
+/// List the extensions installed in a profile, ordered by ID.
+///
+/// # Errors
+///
+/// - [`Error::UnsupportedBrowser`] for profiles of other browser families
+/// - [`Error::Io`] or [`Error::Parse`] if a preference file cannot be read
+pub fn list_extensions(profile: &Profile) -> Result<Vec<Extension>> {
+    let mut extensions = match profile.family {
+        BrowserFamily::Chromium => read_chromium(&profile.path)?,
+        BrowserFamily::Firefox => read_firefox(&profile.path)?,
+        BrowserFamily::WebKit | BrowserFamily::Other => {
+            return Err(Error::UnsupportedBrowser {
+                browser: profile.family.to_string(),
+            });
+        }
+    };
+    extensions.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(extensions)
+}
+
+/// Read the extensions of a Chromium profile directory.
+///
+/// # Errors
+///
+/// Returns [`Error::Io`] or [`Error::Parse`] if a preference file cannot be read.
+pub fn read_chromium(profile_dir: &Path) -> Result<Vec<Extension>> {
+    // Secure Preferences holds the settings on most platforms; merge field
+    // by field so an entry split across both files is complete
+    let mut settings: Map<String, Value> = Map::new();
+    for file in CHROMIUM_PREFERENCE_FILES {
+        let Some(prefs) = read_json::<Value>(&profile_dir.join(file))? else {
+            continue;
+        };
+        let Some(Value::Object(entries)) = prefs.pointer("/extensions/settings") else {
+            continue;
+        };
+        for (id, entry) in entries {
+            let Value::Object(fields) = entry else {
+                continue;
+            };
+            let merged = settings
+                .entry(id.clone())
+                .or_insert_with(|| Value::Object(Map::new()));
+            if let Value::Object(merged) = merged {
+                merged.extend(fields.clone());
+            }
+        }
+    }
+
+    Ok(settings
+        .into_iter()
+        .map(|(id, entry)| chromium_extension(profile_dir, id, &entry))
+        .collect())
+}
+
+fn chromium_extension(profile_dir: &Path, id: String, entry: &Value) -> Extension {
+    let location = entry.get("location").and_then(Value::as_i64).unwrap_or(0);
+    let from_webstore = entry
+        .get("from_webstore")
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+    // Older releases record `state` (1 = enabled); newer ones only list
+    // the reasons an extension is disabled
+    let enabled = entry.get("state").and_then(Value::as_i64).map_or_else(
+        || match entry.get("disable_reasons") {
+            None | Some(Value::Null) => true,
+            Some(Value::Number(reasons)) => reasons.as_i64() == Some(0),
+            Some(Value::Array(reasons)) => reasons.is_empty(),
+            Some(_) => false,
+        },
+        |state| state == 1,
+    );
+
+    let manifest = entry.get("manifest").cloned().or_else(|| {
+        let path = entry.get("path").and_then(Value::as_str)?;
+        let dir = if Path::new(path).is_absolute() {
+            PathBuf::from(path)
+        } else {
+            profile_dir.join("Extensions").join(path)
+        };
+        read_json::<Value>(&dir.join("manifest.json"))
+            .ok()
+            .flatten()
+            .map(|manifest| localize_manifest(&dir, manifest))
+    });
+    let field = |key: &str| {
+        manifest
+            .as_ref()
+            .and_then(|m| m.get(key))
+            .and_then(Value::as_str)
+            .map(str::to_string)
+    };
+
+    Extension {
+        name: field("name").unwrap_or_else(|| id.clone()),
+        version: field("version"),
+        enabled,
+        source: InstallSource::from_chromium(location, from_webstore),
+        id,
+    }
+}
+
+/// Replace a `__MSG_name__` manifest name with its default-locale message.
+fn localize_manifest(dir: &Path, mut manifest: Value) -> Value {
+    let key = manifest
+        .get("name")
+        .and_then(Value::as_str)
+        .and_then(|name| name.strip_prefix("__MSG_")?.strip_suffix("__"))
+        .map(str::to_string);
+    let locale = manifest
+        .get("default_locale")
+        .and_then(Value::as_str)
+        .unwrap_or("en")
+        .to_string();
+    if let Some(key) = key {
+        let messages_path = dir.join("_locales").join(locale).join("messages.json");
+        if let Ok(Some(Value::Object(messages))) = read_json::<Value>(&messages_path) {
+            // Message keys are case-insensitive
+            let message = messages
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(&key))
+                .and_then(|(_, message)| message.get("message"))
+                .cloned();
+            if let Some(message) = message {
+                manifest["name"] = message;
+            }
+        }
+    }
+    manifest
+}
+
+#[derive(Deserialize)]
+struct ExtensionsFile {
+    #[serde(default)]
+    addons: Vec<FirefoxAddon>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FirefoxAddon {
+    id: String,
+    version: Option<String>,
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    #[serde(default)]
+    active: bool,
+    location: Option<String>,
+    default_locale: Option<FirefoxLocale>,
+    install_telemetry_info: Option<FirefoxInstallInfo>,
+}
+
+#[derive(Deserialize)]
+struct FirefoxLocale {
+    name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct FirefoxInstallInfo {
+    source: Option<String>,
+}
+
+/// Read the extensions of a Firefox profile directory.
+///
+/// A profile without `extensions.json` has no extensions.
+///
+/// # Errors
+///
+/// Returns [`Error::Io`] or [`Error::Parse`] if `extensions.json` cannot be read.
+pub fn read_firefox(profile_dir: &Path) -> Result<Vec<Extension>> {
+    let path = profile_dir.join(FIREFOX_EXTENSIONS_JSON);
+    let addons = read_json::<ExtensionsFile>(&path)?.map_or_else(Vec::new, |file| file.addons);
+    Ok(addons
+        .into_iter()
+        .filter(|addon| addon.kind.as_deref().is_none_or(|kind| kind == "extension"))
+        .map(|addon| Extension {
+            name: addon
+                .default_locale
+                .and_then(|locale| locale.name)
+                .unwrap_or_else(|| addon.id.clone()),
+            version: addon.version,
+            enabled: addon.active,
+            source: InstallSource::from_firefox(
+                addon.location.as_deref().unwrap_or_default(),
+                addon
+                    .install_telemetry_info
+                    .as_ref()
+                    .and_then(|info| info.source.as_deref()),
+            ),
+            id: addon.id,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_chromium_preferences() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("Preferences"),
+            r#"{"extensions": {"settings": {
+                "nngceckbapebfimnlniiiahkandclblb": {"location": 1, "from_webstore": true,
+                    "manifest": {"name": "Bitwarden", "version": "2025.1.0"}}
+            }}}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("Secure Preferences"),
+            r#"{"extensions": {"settings": {
+                "nngceckbapebfimnlniiiahkandclblb": {"disable_reasons": [1]},
+                "ssoextensionidaaaaaaaaaaaaaaaaaa": {"location": 9,
+                    "path": "ssoextensionidaaaaaaaaaaaaaaaaaa/1.2_0"}
+            }}}"#,
+        )
+        .unwrap();
+        let unpacked = dir
+            .path()
+            .join("Extensions/ssoextensionidaaaaaaaaaaaaaaaaaa/1.2_0");
+        std::fs::create_dir_all(unpacked.join("_locales/en")).unwrap();
+        std::fs::write(
+            unpacked.join("manifest.json"),
+            r#"{"name": "__MSG_appName__", "version": "1.2", "default_locale": "en"}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            unpacked.join("_locales/en/messages.json"),
+            r#"{"appname": {"message": "Corp SSO"}}"#,
+        )
+        .unwrap();
+
+        let extensions = read_chromium(dir.path()).unwrap();
+        assert_eq!(extensions.len(), 2);
+        let bitwarden = &extensions[0];
+        assert_eq!(bitwarden.name, "Bitwarden");
+        assert_eq!(bitwarden.version.as_deref(), Some("2025.1.0"));
+        assert!(!bitwarden.enabled);
+        assert_eq!(bitwarden.source, InstallSource::Store);
+
+        let sso = &extensions[1];
+        assert_eq!(sso.name, "Corp SSO");
+        assert_eq!(sso.version.as_deref(), Some("1.2"));
+        assert!(sso.enabled);
+        assert_eq!(sso.source, InstallSource::Policy);
+    }
+
+    #[test]
+    fn reads_firefox_extensions_json() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join(FIREFOX_EXTENSIONS_JSON),
+            r#"{"schemaVersion": 36, "addons": [
+                {"id": "uBlock0@raymondhill.net", "version": "1.62.0", "type": "extension",
+                 "active": true, "location": "app-profile",
+                 "defaultLocale": {"name": "uBlock Origin"},
+                 "installTelemetryInfo": {"source": "amo"}},
+                {"id": "firefox-compact-dark@mozilla.org", "type": "theme", "active": true,
+                 "location": "app-builtin"},
+                {"id": "sso@corp.example", "version": "3.0", "type": "extension",
+                 "active": false, "location": "app-system-share"}
+            ]}"#,
+        )
+        .unwrap();
+
+        let extensions = read_firefox(dir.path()).unwrap();
+        assert_eq!(extensions.len(), 2);
+        assert_eq!(extensions[0].name, "uBlock Origin");
+        assert_eq!(extensions[0].source, InstallSource::Store);
+        assert!(extensions[0].enabled);
+        assert_eq!(extensions[1].name, "sso@corp.example");
+        assert_eq!(extensions[1].source, InstallSource::External);
+        assert!(!extensions[1].enabled);
+    }
+}
@@ -0,0 +1,318 @@
+//! Profile lookup by ID, name, email, prefix or selector.
+
+use std::path::{Path, PathBuf};
+
+use browserware_detect::{ProfileArg, RunningInstance};
+use browserware_types::{Browser, BrowserFamily, Profile, ProfileSelector};
+
+use crate::{Error, Result, chromium};
+
+/// What a browser reports about recent profile use, for resolving selectors.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProfileActivity {
+    /// ID of the profile the browser recorded as last used (Chromium `profile.last_used`)
+    pub last_used: Option<String>,
+    /// ID of the profile the browser opens when started without a profile
+    /// argument, if it is not the default profile
+    pub implicit: Option<String>,
+    /// Running instances using the same user data directory
+    pub instances: Vec<RunningInstance>,
+}
+
+impl ProfileActivity {
+    /// Collect the activity of a browser's profiles in `user_data_dir`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] or [`Error::Parse`] if the browser's profile
+    /// state cannot be read.
+    pub fn read(browser: &Browser, user_data_dir: &Path) -> Result<Self> {
+        let last_used = match browser.family() {
+            BrowserFamily::Chromium => chromium::LocalState::read(user_data_dir)?.last_used,
+            _ => None,
+        };
+        // Chromium reopens the last used profile; others open their default
+        let implicit = match browser.family() {
+            BrowserFamily::Chromium => last_used.clone(),
+            _ => None,
+        };
+        let instances = browserware_detect::running_instances(std::slice::from_ref(browser))
+            .into_iter()
+            .filter(|instance| instance.browser.id == browser.id)
+            .filter(|instance| {
+                instance
+                    .user_data_dir
+                    .as_deref()
+                    .is_none_or(|dir| dir == user_data_dir)
+            })
+            .collect();
+        Ok(Self {
+            last_used,
+            implicit,
+            instances,
+        })
+    }
+}
+
+/// Pick the profile a selector refers to.
+///
+/// - `@default` is the profile the browser itself marks as default.
+/// - `@last-used` is the profile the browser recorded as last used, or the
+///   one with the latest [`last_used`](Profile::last_used) time, falling back
+///   to `@default`.
+/// - `@last-active` is the profile of the most recently started running
+///   instance, falling back to `@last-used` when none is running.
+///
+/// # Errors
+///
+/// Returns [`Error::NotFound`] if the selector matches no profile.
+pub fn resolve_selector(
+    browser: &str,
+    profiles: &[Profile],
+    selector: ProfileSelector,
+    activity: &ProfileActivity,
+) -> Result<Profile> {
+    let default = || profiles.iter().find(|p| p.is_default);
+    let by_id = |id: &str| profiles.iter().find(|p| p.id.0 == id);
+    let last_used = || {
+        activity
+            .last_used
+            .as_deref()
+            .and_then(by_id)
+            .or_else(|| {
+                profiles
+                    .iter()
+                    .filter(|p| p.last_used.is_some())
+                    .max_by_key(|p| p.last_used)
+            })
+            .or_else(default)
+    };
+    let of_instance = |instance: &RunningInstance| match &instance.profile {
+        Some(ProfileArg::Directory(dir)) => by_id(dir),
+        Some(ProfileArg::Name(name)) => {
+            profiles.iter().find(|p| p.name == *name || p.id.0 == *name)
+        }
+        Some(ProfileArg::Path(path)) => profiles.iter().find(|p| same_path(&p.path, path)),
+        None => activity
+            .implicit
+            .as_deref()
+            .and_then(by_id)
+            .or_else(default),
+    };
+
+    let found = match selector {
+        ProfileSelector::Default => default(),
+        ProfileSelector::LastUsed => last_used(),
+        ProfileSelector::LastActive => {
+            let mut instances: Vec<&RunningInstance> = activity.instances.iter().collect();
+            instances.sort_by_key(|instance| std::cmp::Reverse(instance.start_time));
+            instances
+                .into_iter()
+                .find_map(of_instance)
+                .or_else(last_used)
+        }
+    };
+    found.cloned().ok_or_else(|| Error::NotFound {
+        browser: browser.to_string(),
+        profile: selector.to_string(),
+    })
+}
+
+/// Compare profile paths, resolving symlinks when both exist.
+fn same_path(a: &Path, b: &Path) -> bool {
+    let canonical = |p: &Path| std::fs::canonicalize(p).unwrap_or_else(|_| PathBuf::from(p));
+    a == b || canonical(a) == canonical(b)
+}
+
+/// Pick the profile matching `query` from a browser's profiles.
+///
+/// Matching tries, in order, and stops at the first rule with any match:
+///
+/// 1. the exact profile ID (the directory, e.g. `Profile 2`),
+/// 2. the display name, ignoring case,
+/// 3. the account email, ignoring case,
+/// 4. a prefix of the ID or display name, ignoring case.
+///
+/// Display names change when a profile is renamed while IDs do not, so
+/// callers that store a profile reference should store the returned
+/// profile's [`id`](Profile::id).
+///
+/// # Errors
+///
+/// - [`Error::NotFound`] if no profile matches
+/// - [`Error::Ambiguous`] if the first matching rule matches several profiles
+pub fn select_profile(browser: &str, profiles: &[Profile], query: &str) -> Result<Profile> {
+    let query_lower = query.to_lowercase();
+    let rules: [&dyn Fn(&Profile) -> bool; 4] = [
+        &|p| p.id.0 == query,
+        &|p| p.name.to_lowercase() == query_lower,
+        &|p| {
+            p.email
+                .as_ref()
+                .is_some_and(|email| email.to_lowercase() == query_lower)
+        },
+        &|p| {
+            p.id.0.to_lowercase().starts_with(&query_lower)
+                || p.name.to_lowercase().starts_with(&query_lower)
+        },
+    ];
+
+    if !query.is_empty() {
+        for rule in rules {
+            let matches: Vec<&Profile> = profiles.iter().filter(|p| rule(p)).collect();
+            match matches.len() {
+                0 => {}
+                1 => return Ok(matches[0].clone()),
+                _ => {
+                    return Err(Error::Ambiguous {
+                        browser: browser.to_string(),
+                        query: query.to_string(),
+                        candidates: matches.iter().map(|p| p.id.0.clone()).collect(),
+                    });
+                }
+            }
+        }
+    }
+    Err(Error::NotFound {
+        browser: browser.to_string(),
+        profile: query.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profiles() -> Vec<Profile> {
+        let profile = |id: &str, name: &str| {
+            Profile::new(
+                id,
+                name,
+                format!("/data/{id}").into(),
+                BrowserFamily::Chromium,
+            )
+        };
+        vec![
+            profile("Default", "Personal").with_email("me@example.com"),
+            profile("Profile 2", "Work").with_email("Me@Corp.example"),
+            profile("Profile 3", "Workshop"),
+            profile("Profile 4", "profile 2"),
+        ]
+    }
+
+    fn find(query: &str) -> Result<Profile> {
+        select_profile("chrome", &profiles(), query)
+    }
+
+    #[test]
+    fn matches_in_order_of_precedence() {
+        // The directory wins over another profile's display name
+        assert_eq!(find("Profile 2").unwrap().name, "Work");
+        // Names ignore case, and an exact name beats a longer one
+        assert_eq!(find("work").unwrap().id.0, "Profile 2");
+        assert_eq!(find("me@corp.example").unwrap().id.0, "Profile 2");
+        assert_eq!(find("pers").unwrap().id.0, "Default");
+        assert_eq!(find("works").unwrap().id.0, "Profile 3");
+    }
+
+    #[test]
+    fn ambiguous_prefix_lists_candidates() {
+        let err = find("profile").unwrap_err();
+        let Error::Ambiguous { candidates, .. } = err else {
+            panic!("expected ambiguity, got {err}");
+        };
+        assert_eq!(candidates, ["Profile 2", "Profile 3", "Profile 4"]);
+    }
+
+    #[test]
+    fn unknown_or_empty_query_is_not_found() {
+        assert!(matches!(find("Travel"), Err(Error::NotFound { .. })));
+        assert!(matches!(find(""), Err(Error::NotFound { .. })));
+    }
+
+    fn instance(profile: Option<ProfileArg>, start_time: u64) -> RunningInstance {
+        RunningInstance {
+            pid: 1,
+            browser: Browser::new("chrome", "Google Chrome", "/usr/bin/google-chrome".into()),
+            profile,
+            user_data_dir: None,
+            start_time: Some(start_time),
+        }
+    }
+
+    fn resolve(selector: ProfileSelector, activity: &ProfileActivity) -> Result<Profile> {
+        let mut profiles = profiles();
+        profiles[0].is_default = true;
+        profiles[2].last_used = Some(1_700_000_000);
+        resolve_selector("chrome", &profiles, selector, activity)
+    }
+
+    #[test]
+    fn default_and_last_used_selectors() {
+        let none = ProfileActivity::default();
+        assert_eq!(
+            resolve(ProfileSelector::Default, &none).unwrap().id.0,
+            "Default"
+        );
+        // Latest recorded time when the browser does not name one
+        assert_eq!(
+            resolve(ProfileSelector::LastUsed, &none).unwrap().id.0,
+            "Profile 3"
+        );
+
+        let recorded = ProfileActivity {
+            last_used: Some("Profile 4".into()),
+            ..ProfileActivity::default()
+        };
+        assert_eq!(
+            resolve(ProfileSelector::LastUsed, &recorded).unwrap().id.0,
+            "Profile 4"
+        );
+
+        let err = resolve_selector("chrome", &[], ProfileSelector::Default, &none).unwrap_err();
+        assert_eq!(err.to_string(), "profile '@default' not found in chrome");
+    }
+
+    #[test]
+    fn last_active_uses_newest_instance() {
+        let activity = ProfileActivity {
+            last_used: Some("Profile 4".into()),
+            implicit: Some("Profile 4".into()),
+            instances: vec![
+                instance(Some(ProfileArg::Directory("Profile 2".into())), 100),
+                instance(Some(ProfileArg::Directory("Profile 3".into())), 300),
+                instance(Some(ProfileArg::Directory("Deleted".into())), 500),
+            ],
+        };
+        assert_eq!(
+            resolve(ProfileSelector::LastActive, &activity)
+                .unwrap()
+                .id
+                .0,
+            "Profile 3"
+        );
+
+        // Without a profile argument the browser's implicit profile is used
+        let implicit = ProfileActivity {
+            instances: vec![instance(None, 100)],
+            ..activity.clone()
+        };
+        assert_eq!(
+            resolve(ProfileSelector::LastActive, &implicit)
+                .unwrap()
+                .id
+                .0,
+            "Profile 4"
+        );
+
+        // Nothing running falls back to the last used profile
+        let idle = ProfileActivity {
+            instances: Vec::new(),
+            ..activity
+        };
+        assert_eq!(
+            resolve(ProfileSelector::LastActive, &idle).unwrap().id.0,
+            "Profile 4"
+        );
+    }
+}
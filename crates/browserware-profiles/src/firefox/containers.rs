@@ -0,0 +1,237 @@
+//! Firefox Multi-Account Containers.
+//!
+//! Containers (contextual identities) split a profile's cookies and storage
+//! by tab. Their list is kept per profile in `containers.json`:
+//!
+//! ```json
+//! {"version": 5, "lastUserContextId": 5, "identities": [
+//!   {"userContextId": 1, "public": true, "icon": "fingerprint", "color": "blue",
+//!    "l10nID": "userContextPersonal.label", "accessKey": "userContextPersonal.accesskey"},
+//!   {"userContextId": 5, "public": true, "icon": "briefcase", "color": "red", "name": "Acme"}
+//! ]}
+//! ```
+//!
+//! Firefox has no command-line option to open a tab in a container. The
+//! [Open external links in a container] add-on registers the
+//! `ext+container:` protocol for this, so [`container_url`] URLs only work
+//! in profiles where [`has_container_addon`] is true.
+//!
+//! [Open external links in a container]: https://addons.mozilla.org/firefox/addon/open-url-in-container/
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::json::read_json;
+use crate::{Error, Result};
+
+/// Name of the file listing a profile's containers.
+pub const CONTAINERS_JSON: &str = "containers.json";
+
+/// Add-on ID of "Open external links in a container".
+pub const CONTAINER_ADDON_ID: &str = "{f069aec0-43c5-4bbf-b6b4-df95c4326b98}";
+
+/// Display name of the container add-on, for messages.
+pub const CONTAINER_ADDON_NAME: &str = "Open external links in a container";
+
+/// A container of a Firefox profile.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Container {
+    /// User context ID, stable for the life of the container
+    pub id: u32,
+    /// Display name
+    pub name: String,
+    /// Icon name (e.g. `fingerprint`, `briefcase`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    /// Color name (e.g. `blue`, `orange`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ContainersFile {
+    #[serde(default)]
+    identities: Vec<Identity>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Identity {
+    user_context_id: u32,
+    #[serde(default = "default_public")]
+    public: bool,
+    name: Option<String>,
+    #[serde(rename = "l10nID")]
+    l10n_id: Option<String>,
+    icon: Option<String>,
+    color: Option<String>,
+}
+
+const fn default_public() -> bool {
+    true
+}
+
+/// Read the containers of a Firefox profile, in file order.
+///
+/// Built-in containers, which only carry a localization ID, get their
+/// English names. Internal (non-public) containers are skipped. A profile
+/// without `containers.json` has no containers.
+///
+/// # Errors
+///
+/// Returns [`Error::Io`] if the file cannot be read and [`Error::Parse`] if
+/// it is malformed.
+pub fn read_containers(profile_dir: &Path) -> Result<Vec<Container>> {
+    let path = profile_dir.join(CONTAINERS_JSON);
+    let Some(file) = read_json::<ContainersFile>(&path)? else {
+        return Ok(Vec::new());
+    };
+    Ok(file
+        .identities
+        .into_iter()
+        .filter(|identity| identity.public)
+        .filter_map(|identity| {
+            let name = identity
+                .name
+                .filter(|name| !name.is_empty())
+                .or_else(|| identity.l10n_id.as_deref().and_then(builtin_name))?;
+            Some(Container {
+                id: identity.user_context_id,
+                name,
+                icon: identity.icon,
+                color: identity.color,
+            })
+        })
+        .collect())
+}
+
+/// Pick the container matching `query`, by name (ignoring case) or ID.
+///
+/// # Errors
+///
+/// Returns [`Error::ContainerNotFound`] if no container matches.
+pub fn find_container(profile: &str, containers: &[Container], query: &str) -> Result<Container> {
+    let query_lower = query.to_lowercase();
+    containers
+        .iter()
+        .find(|c| c.name.to_lowercase() == query_lower)
+        .or_else(|| {
+            let id: u32 = query.parse().ok()?;
+            containers.iter().find(|c| c.id == id)
+        })
+        .cloned()
+        .ok_or_else(|| Error::ContainerNotFound {
+            profile: profile.to_string(),
+            container: query.to_string(),
+        })
+}
+
+/// Whether the container add-on is installed and enabled in a profile.
+///
+/// # Errors
+///
+/// Returns [`Error::Io`] or [`Error::Parse`] if `extensions.json` cannot be read.
+pub fn has_container_addon(profile_dir: &Path) -> Result<bool> {
+    Ok(crate::extensions::read_firefox(profile_dir)?
+        .iter()
+        .any(|extension| extension.id == CONTAINER_ADDON_ID && extension.enabled))
+}
+
+/// Address that opens `url` in `container` through the container add-on.
+#[must_use]
+pub fn container_url(container: &Container, url: &str) -> String {
+    let query = url::form_urlencoded::Serializer::new(String::new())
+        .append_pair("name", &container.name)
+        .append_pair("url", url)
+        .finish();
+    format!("ext+container:{query}")
+}
+
+/// English names of the containers Firefox creates by default.
+fn builtin_name(l10n_id: &str) -> Option<String> {
+    let name = match l10n_id {
+        "userContextPersonal.label" => "Personal",
+        "userContextWork.label" => "Work",
+        "userContextBanking.label" => "Banking",
+        "userContextShopping.label" => "Shopping",
+        _ => return None,
+    };
+    Some(name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_builtin_and_custom_containers() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join(CONTAINERS_JSON),
+            r#"{"version": 5, "lastUserContextId": 6, "identities": [
+                {"userContextId": 1, "public": true, "icon": "fingerprint", "color": "blue",
+                 "l10nID": "userContextPersonal.label"},
+                {"userContextId": 3, "public": true, "icon": "dollar", "color": "green",
+                 "l10nID": "userContextBanking.label"},
+                {"userContextId": 5, "public": false, "icon": "", "color": "",
+                 "name": "userContextIdInternal.thumbnail"},
+                {"userContextId": 6, "public": true, "icon": "briefcase", "color": "red",
+                 "name": "Acme Corp"}
+            ]}"#,
+        )
+        .unwrap();
+
+        let containers = read_containers(dir.path()).unwrap();
+        let names: Vec<_> = containers.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["Personal", "Banking", "Acme Corp"]);
+        assert_eq!(containers[2].color.as_deref(), Some("red"));
+
+        assert_eq!(find_container("p", &containers, "acme corp").unwrap().id, 6);
+        assert_eq!(
+            find_container("p", &containers, "3").unwrap().name,
+            "Banking"
+        );
+        assert!(matches!(
+            find_container("p", &containers, "Travel"),
+            Err(Error::ContainerNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn missing_files_mean_no_containers_or_addon() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(read_containers(dir.path()).unwrap().is_empty());
+        assert!(!has_container_addon(dir.path()).unwrap());
+    }
+
+    #[test]
+    fn detects_active_container_addon() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |active: bool| {
+            std::fs::write(
+                dir.path().join(crate::extensions::FIREFOX_EXTENSIONS_JSON),
+                format!(r#"{{"schemaVersion": 36, "addons": [{{"id": "{CONTAINER_ADDON_ID}", "active": {active}}}]}}"#),
+            )
+            .unwrap();
+        };
+        write(true);
+        assert!(has_container_addon(dir.path()).unwrap());
+        write(false);
+        assert!(!has_container_addon(dir.path()).unwrap());
+    }
+
+    #[test]
+    fn builds_container_url() {
+        let container = Container {
+            id: 2,
+            name: "Work & Co".into(),
+            icon: None,
+            color: None,
+        };
+        assert_eq!(
+            container_url(&container, "https://example.com/?a=1&b=2"),
+            "ext+container:name=Work+%26+Co&url=https%3A%2F%2Fexample.com%2F%3Fa%3D1%26b%3D2"
+        );
+    }
+}
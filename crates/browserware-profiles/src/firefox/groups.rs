@@ -0,0 +1,204 @@
+//! Profile groups of Firefox's new profile manager.
+//!
+//! Profiles created through the profile selector (Firefox 138 and later)
+//! belong to a group stored in a SQLite database, `Profile Groups/<StoreID>.sqlite`
+//! under the profile root. `profiles.ini` only mirrors the paths of the group's
+//! profiles (tagged with `StoreID=`), so names, avatars and themes must be read
+//! from the database.
+
+use std::path::{Path, PathBuf};
+
+use rusqlite::{Connection, OpenFlags};
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, Result};
+
+/// Directory containing the profile group databases.
+pub const PROFILE_GROUPS_DIR: &str = "Profile Groups";
+
+/// A profile as recorded in a profile group database.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupProfile {
+    /// Row ID within the group
+    pub id: i64,
+    /// Profile directory, relative to the profile root unless absolute
+    pub path: String,
+    /// Display name
+    pub name: String,
+    /// Avatar identifier (e.g. `book`, `briefcase`)
+    pub avatar: Option<String>,
+    /// Theme chosen for the profile
+    pub theme: Option<ProfileTheme>,
+}
+
+/// A profile's theme as shown in the profile selector.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProfileTheme {
+    /// Theme add-on ID
+    pub id: String,
+    /// Foreground color (CSS color)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub foreground: Option<String>,
+    /// Background color (CSS color)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background: Option<String>,
+}
+
+/// Path of the database of the group with `store_id`.
+#[must_use]
+pub fn database_path(root: &Path, store_id: &str) -> PathBuf {
+    root.join(PROFILE_GROUPS_DIR)
+        .join(format!("{store_id}.sqlite"))
+}
+
+/// Read the profiles of a profile group database, ordered by row ID.
+///
+/// The database is opened read-only, so it can be read while Firefox runs.
+///
+/// # Errors
+///
+/// Returns [`Error::Database`] if the database cannot be opened or does not
+/// have the expected `Profiles` table.
+pub fn read_group(path: &Path) -> Result<Vec<GroupProfile>> {
+    let database_error = |source| Error::Database {
+        path: path.to_path_buf(),
+        source,
+    };
+    let connection = Connection::open_with_flags(
+        path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .map_err(database_error)?;
+
+    let mut statement = connection
+        .prepare(
+            "SELECT id, path, name, avatar, themeId, themeFg, themeBg \
+             FROM Profiles ORDER BY id",
+        )
+        .map_err(database_error)?;
+    let rows = statement
+        .query_map([], |row| {
+            let theme = non_empty(row.get(4)?).map(|id| -> rusqlite::Result<_> {
+                Ok(ProfileTheme {
+                    id,
+                    foreground: non_empty(row.get(5)?),
+                    background: non_empty(row.get(6)?),
+                })
+            });
+            Ok(GroupProfile {
+                id: row.get(0)?,
+                path: row.get(1)?,
+                name: row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+                avatar: non_empty(row.get(3)?),
+                theme: theme.transpose()?,
+            })
+        })
+        .map_err(database_error)?;
+    rows.collect::<rusqlite::Result<_>>()
+        .map_err(database_error)
+}
+
+/// Rename (`Some`) or remove (`None`) a profile's row in a group database.
+///
+/// The database is backed up first. A profile without a row is left alone.
+pub(crate) fn update_profile(
+    path: &Path,
+    root: &Path,
+    profile_dir: &Path,
+    name: Option<&str>,
+) -> Result<()> {
+    let Some(row) = read_group(path)?
+        .into_iter()
+        .find(|row| super::resolve_group_path(root, &row.path) == profile_dir)
+    else {
+        return Ok(());
+    };
+    std::fs::copy(path, crate::write::backup_path(path)).map_err(|err| Error::io(path, err))?;
+
+    let database_error = |source| Error::Database {
+        path: path.to_path_buf(),
+        source,
+    };
+    let connection = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_WRITE)
+        .map_err(database_error)?;
+    match name {
+        Some(name) => connection.execute(
+            "UPDATE Profiles SET name = ?1 WHERE id = ?2",
+            rusqlite::params![name, row.id],
+        ),
+        None => connection.execute("DELETE FROM Profiles WHERE id = ?1", [row.id]),
+    }
+    .map_err(database_error)?;
+    Ok(())
+}
+
+/// Treat `NULL` and empty strings alike; Firefox writes both for unset values.
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.filter(|v| !v.is_empty())
+}
+
+/// Create a group database with the schema Firefox uses.
+#[cfg(test)]
+pub(crate) fn create_group(path: &Path, rows: &[(&str, &str, &str, &str)]) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    let connection = Connection::open(path).unwrap();
+    connection
+        .execute_batch(
+            "CREATE TABLE Profiles (id INTEGER NOT NULL, path TEXT NOT NULL UNIQUE, \
+             name TEXT NOT NULL, avatar TEXT NOT NULL, themeId TEXT NOT NULL, \
+             themeFg TEXT NOT NULL, themeBg TEXT NOT NULL, PRIMARY KEY(id));",
+        )
+        .unwrap();
+    for (path, name, avatar, theme) in rows {
+        connection
+            .execute(
+                "INSERT INTO Profiles (path, name, avatar, themeId, themeFg, themeBg) \
+                 VALUES (?1, ?2, ?3, ?4, '#000000', '#ffffff')",
+                [path, name, avatar, theme],
+            )
+            .unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_group_profiles() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = database_path(dir.path(), "a1b2c3");
+        create_group(
+            &path,
+            &[
+                (
+                    "Profiles/x8k2m1qa.Work",
+                    "Work",
+                    "briefcase",
+                    "default-theme@mozilla.org",
+                ),
+                ("Profiles/p3n7c0vd.Home", "Home", "", ""),
+            ],
+        );
+
+        let profiles = read_group(&path).unwrap();
+        assert_eq!(profiles.len(), 2);
+        assert_eq!(profiles[0].name, "Work");
+        assert_eq!(profiles[0].avatar.as_deref(), Some("briefcase"));
+        let theme = profiles[0].theme.as_ref().unwrap();
+        assert_eq!(theme.id, "default-theme@mozilla.org");
+        assert_eq!(theme.background.as_deref(), Some("#ffffff"));
+        assert_eq!(profiles[1].avatar, None);
+        assert_eq!(profiles[1].theme, None);
+    }
+
+    #[test]
+    fn unreadable_database_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("missing.sqlite");
+        assert!(matches!(read_group(&path), Err(Error::Database { .. })));
+
+        std::fs::write(&path, "not a database").unwrap();
+        assert!(matches!(read_group(&path), Err(Error::Database { .. })));
+    }
+}
@@ -0,0 +1,991 @@
+//! Firefox-family profiles.
+//!
+//! Firefox and its forks (`LibreWolf`, Waterfox, Floorp) list profiles in
+//! `profiles.ini` at the root of their profile directory:
+//!
+//! ```ini
+//! [Install4F96D1932A9F858E]
+//! Default=x8k2m1qa.default-release
+//! Locked=1
+//!
+//! [Profile0]
+//! Name=default-release
+//! IsRelative=1
+//! Path=x8k2m1qa.default-release
+//!
+//! [Profile1]
+//! Name=default
+//! IsRelative=1
+//! Path=p3n7c0vd.default
+//! Default=1
+//! ```
+//!
+//! Since Firefox 67 every installation has its own default profile, recorded
+//! in an `[Install<hash>]` section (and mirrored in `installs.ini`). The hash
+//! identifies the install directory; see [`install_hash`]. A lone install
+//! section is used even when the hash does not match, as happens when the
+//! executable is a wrapper script. `Default=1` on a profile is only used by
+//! installations without an install section.
+//!
+//! Profiles created by the new profile manager are also listed in a profile
+//! group database, which holds their display names, avatars and themes; see
+//! [`groups`]. Each profile's containers are listed in `containers.json`;
+//! see [`containers`].
+
+pub mod containers;
+pub mod groups;
+
+use std::path::{Path, PathBuf};
+
+use browserware_types::{Browser, BrowserFamily, Packaging, Profile, ProfileId};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use self::groups::{GroupProfile, ProfileTheme};
+use crate::cityhash::city_hash64;
+use crate::ini::{Ini, Section, SyntaxError};
+use crate::write::{remove_profile_dir, write_atomic};
+use crate::{Error, Result};
+
+/// Name of the file listing a Firefox-family browser's profiles.
+pub const PROFILES_INI: &str = "profiles.ini";
+
+/// Name of the file mapping installations to their default profiles.
+pub const INSTALLS_INI: &str = "installs.ini";
+
+/// Prefix of install sections in `profiles.ini`.
+const INSTALL_SECTION_PREFIX: &str = "Install";
+
+/// A Firefox profile with the browser-specific details from `profiles.ini`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FirefoxProfile {
+    /// Browser-independent profile information
+    #[serde(flatten)]
+    pub profile: Profile,
+    /// Whether the path is relative to the profile root
+    pub is_relative: bool,
+    /// Whether the profile is marked `Default=1` (pre-install-default behavior)
+    pub is_legacy_default: bool,
+    /// Hashes of the installations using this profile as their default
+    pub installs: Vec<String>,
+    /// ID of the profile group the profile belongs to (if any)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub store_id: Option<String>,
+    /// Theme chosen in the profile manager (if any)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub theme: Option<ProfileTheme>,
+}
+
+/// An installation's default profile.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Install {
+    /// Install hash, see [`install_hash`]
+    pub hash: String,
+    /// Directory of the default profile
+    pub default: PathBuf,
+    /// Whether the installation is locked to this profile
+    ///
+    /// Firefox never reassigns a locked install's default, even when another
+    /// installation starts using the same profile.
+    pub locked: bool,
+}
+
+/// The profile list of a Firefox profile root.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProfilesIni {
+    /// Profiles, in file order
+    pub profiles: Vec<FirefoxProfile>,
+    /// Per-installation defaults from `profiles.ini` and `installs.ini`
+    pub installs: Vec<Install>,
+    /// Whether Firefox starts the default profile without asking
+    pub start_with_last_profile: bool,
+}
+
+impl ProfilesIni {
+    /// Read the profile list of a Firefox profile root.
+    ///
+    /// `install_hash` selects whose default profile is marked
+    /// [`is_default`](Profile::is_default); see [`ProfilesIni::parse`]. A
+    /// missing profile root (the browser has never run) yields an empty list.
+    ///
+    /// Names, avatars and themes of profiles in a profile group are taken from
+    /// the group database, and group profiles missing from `profiles.ini` are
+    /// added. A missing or unreadable database leaves `profiles.ini` as is.
+    ///
+    /// [`last_used`](Profile::last_used) is the time the profile was last
+    /// locked by a running browser, and [`email`](Profile::email) the
+    /// Firefox Account the profile syncs with.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] if a file cannot be read and [`Error::Parse`] if
+    /// it is malformed.
+    pub fn read(root: &Path, install_hash: Option<&str>) -> Result<Self> {
+        let Some(profiles) = read_optional(&root.join(PROFILES_INI))? else {
+            return Ok(Self::default());
+        };
+        let installs = read_optional(&root.join(INSTALLS_INI))?;
+        let mut this = Self::parse_files(&profiles, installs.as_deref(), root)?;
+
+        let mut store_ids: Vec<String> = Vec::new();
+        for store_id in this.profiles.iter().filter_map(|p| p.store_id.as_ref()) {
+            if !store_ids.contains(store_id) {
+                store_ids.push(store_id.clone());
+            }
+        }
+        for store_id in store_ids {
+            let path = groups::database_path(root, &store_id);
+            if !path.exists() {
+                debug!(path = %path.display(), "profile group database not found");
+                continue;
+            }
+            match groups::read_group(&path) {
+                Ok(group) => this.apply_group(root, &store_id, group),
+                Err(err) => warn!("ignoring profile group: {err}"),
+            }
+        }
+
+        for profile in &mut this.profiles {
+            profile.profile.last_used = lock_time(&profile.profile.path);
+            profile.profile.email = sync_email(&profile.profile.path);
+        }
+        this.resolve_defaults(install_hash);
+        Ok(this)
+    }
+
+    /// Parse the contents of `profiles.ini` and, if present, `installs.ini`.
+    ///
+    /// The default profile is, in order of preference:
+    ///
+    /// 1. the default of the installation with `install_hash`,
+    /// 2. the default of the only installation, when `install_hash` is `None`
+    ///    (sandboxed packages only ever see their own installation),
+    /// 3. the profile marked `Default=1`,
+    /// 4. the only profile.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Parse`] if either file is malformed.
+    pub fn parse(
+        profiles_ini: &str,
+        installs_ini: Option<&str>,
+        root: &Path,
+        install_hash: Option<&str>,
+    ) -> Result<Self> {
+        let mut this = Self::parse_files(profiles_ini, installs_ini, root)?;
+        this.resolve_defaults(install_hash);
+        Ok(this)
+    }
+
+    /// Merge the profiles of a profile group into the list.
+    ///
+    /// Group profiles are matched to `profiles.ini` entries by path; their
+    /// names (unless empty), avatars and themes replace the ones from
+    /// `profiles.ini`. Unmatched group profiles are appended. Call before
+    /// [`is_default`](Profile::is_default) is resolved, i.e. on a list from
+    /// [`ProfilesIni::parse_files`].
+    fn apply_group(&mut self, root: &Path, store_id: &str, group: Vec<GroupProfile>) {
+        for entry in group {
+            let path = resolve_group_path(root, &entry.path);
+            let position = self.profiles.iter().position(|p| p.profile.path == path);
+            let index = position.unwrap_or_else(|| {
+                let id = ProfileId::from_dir(&path).map_or_else(|| entry.id.to_string(), |id| id.0);
+                let name = id.clone();
+                self.profiles.push(FirefoxProfile {
+                    profile: Profile::new(id, name, path, BrowserFamily::Firefox),
+                    is_relative: !Path::new(&entry.path).is_absolute(),
+                    is_legacy_default: false,
+                    installs: Vec::new(),
+                    store_id: None,
+                    theme: None,
+                });
+                self.profiles.len() - 1
+            });
+            let profile = &mut self.profiles[index];
+            if !entry.name.is_empty() {
+                profile.profile.name = entry.name;
+            }
+            if entry.avatar.is_some() {
+                profile.profile.avatar = entry.avatar;
+            }
+            profile.theme = entry.theme;
+            profile.store_id = Some(store_id.to_string());
+        }
+    }
+
+    /// Parse the files without resolving defaults.
+    fn parse_files(profiles_ini: &str, installs_ini: Option<&str>, root: &Path) -> Result<Self> {
+        let parse_error = |file: &str, err: SyntaxError| Error::Parse {
+            path: root.join(file),
+            line: Some(err.line),
+            message: err.message,
+        };
+        let ini = Ini::parse(profiles_ini).map_err(|err| parse_error(PROFILES_INI, err))?;
+
+        let mut profiles = Vec::new();
+        let mut installs = Vec::new();
+        let mut start_with_last_profile = true;
+
+        for section in &ini.sections {
+            if section.name == "General" {
+                start_with_last_profile = section.get("StartWithLastProfile") != Some("0");
+            } else if let Some(hash) = section.name.strip_prefix(INSTALL_SECTION_PREFIX) {
+                if let Some(default) = section.get("Default") {
+                    installs.push(Install {
+                        hash: hash.to_string(),
+                        default: resolve_path(root, default, true),
+                        locked: section.flag("Locked"),
+                    });
+                }
+            } else if section.name.starts_with("Profile") {
+                let Some(path) = section.get("Path") else {
+                    return Err(Error::Parse {
+                        path: root.join(PROFILES_INI),
+                        line: None,
+                        message: format!("[{}] has no Path", section.name),
+                    });
+                };
+                let is_relative = section.flag("IsRelative");
+                let path = resolve_path(root, path, is_relative);
+                let id = ProfileId::from_dir(&path).map_or_else(|| section.name.clone(), |id| id.0);
+                let name = section.get("Name").unwrap_or(&id).to_string();
+                profiles.push(FirefoxProfile {
+                    profile: Profile::new(id, name, path, BrowserFamily::Firefox),
+                    is_relative,
+                    is_legacy_default: section.flag("Default"),
+                    installs: Vec::new(),
+                    store_id: section.get("StoreID").map(str::to_string),
+                    theme: None,
+                });
+            }
+        }
+
+        // installs.ini is the older copy; profiles.ini wins for shared hashes
+        if let Some(text) = installs_ini {
+            let extra = Ini::parse(text).map_err(|err| parse_error(INSTALLS_INI, err))?;
+            for section in &extra.sections {
+                if installs.iter().any(|i| i.hash == section.name) {
+                    continue;
+                }
+                if let Some(default) = section.get("Default") {
+                    installs.push(Install {
+                        hash: section.name.clone(),
+                        default: resolve_path(root, default, true),
+                        locked: section.flag("Locked"),
+                    });
+                }
+            }
+        }
+
+        Ok(Self {
+            profiles,
+            installs,
+            start_with_last_profile,
+        })
+    }
+
+    /// Link installs to their default profiles and mark the default profile.
+    fn resolve_defaults(&mut self, install_hash: Option<&str>) {
+        for install in &self.installs {
+            if let Some(profile) = self
+                .profiles
+                .iter_mut()
+                .find(|p| p.profile.path == install.default)
+            {
+                profile.installs.push(install.hash.clone());
+            }
+        }
+        if let Some(path) = self.default_path(install_hash).map(Path::to_path_buf)
+            && let Some(profile) = self.profiles.iter_mut().find(|p| p.profile.path == path)
+        {
+            profile.profile.is_default = true;
+        }
+    }
+
+    fn default_path(&self, install_hash: Option<&str>) -> Option<&Path> {
+        let matching = install_hash.and_then(|hash| {
+            self.installs
+                .iter()
+                .find(|i| i.hash.eq_ignore_ascii_case(hash))
+        });
+        // A single install is ours even when the hash does not match, e.g.
+        // when the executable is a wrapper script outside the install directory
+        let install = match self.installs.as_slice() {
+            [only] => matching.or(Some(only)),
+            _ => matching,
+        };
+        if let Some(install) = install {
+            return Some(&install.default);
+        }
+        if let Some(legacy) = self.profiles.iter().find(|p| p.is_legacy_default) {
+            return Some(&legacy.profile.path);
+        }
+        match self.profiles.as_slice() {
+            [only] => Some(&only.profile.path),
+            _ => None,
+        }
+    }
+}
+
+/// List the profiles of a Firefox profile root.
+///
+/// # Errors
+///
+/// See [`ProfilesIni::read`].
+pub fn list_profiles(root: &Path, install_hash: Option<&str>) -> Result<Vec<Profile>> {
+    let ini = ProfilesIni::read(root, install_hash)?;
+    Ok(ini.profiles.into_iter().map(|p| p.profile).collect())
+}
+
+/// Add a profile to a Firefox profile root.
+///
+/// Creates a `<salt>.<name>` directory and a `[Profile<n>]` section for it in
+/// `profiles.ini`, which is created if missing. Profile sections are
+/// renumbered from zero, as Firefox stops reading at the first gap.
+///
+/// # Errors
+///
+/// Returns [`Error::Io`] or [`Error::Parse`] if `profiles.ini` cannot be read
+/// or written.
+pub fn create_profile(root: &Path, name: &str) -> Result<Profile> {
+    let mut ini = load_profiles_ini(root)?;
+    let dir_name = format!("{}.{}", random_salt(), dir_safe(name));
+    let count = ini
+        .sections
+        .iter()
+        .filter(|s| is_profile_section(&s.name))
+        .count();
+    let section = Section {
+        name: format!("Profile{count}"),
+        entries: vec![
+            ("Name".to_string(), name.to_string()),
+            ("IsRelative".to_string(), "1".to_string()),
+            ("Path".to_string(), dir_name.clone()),
+        ],
+    };
+    // Keep profile sections together, after the existing ones
+    let index = ini
+        .sections
+        .iter()
+        .rposition(|s| is_profile_section(&s.name))
+        .map_or(ini.sections.len(), |i| i + 1);
+    ini.sections.insert(index, section);
+    // Close gaps, which would also leave `Profile{count}` taken
+    renumber_profile_sections(&mut ini);
+
+    let path = root.join(&dir_name);
+    std::fs::create_dir_all(&path).map_err(|err| Error::io(&path, err))?;
+    write_atomic(&root.join(PROFILES_INI), ini.to_string().as_bytes())?;
+    Ok(Profile::new(dir_name, name, path, BrowserFamily::Firefox))
+}
+
+/// Rename a profile of a Firefox profile root.
+///
+/// Updates `profiles.ini` and, for profiles in a profile group, the group
+/// database.
+///
+/// # Errors
+///
+/// Returns [`Error::NotRegistered`] if no profile has directory `id`, and
+/// [`Error::Io`], [`Error::Parse`] or [`Error::Database`] if a file cannot be
+/// read or written.
+pub fn rename_profile(root: &Path, id: &str, name: &str) -> Result<()> {
+    let mut ini = load_profiles_ini(root)?;
+    let index = profile_section(&ini, root, id)?;
+    let section = &mut ini.sections[index];
+    section.set("Name", name);
+    let path = section_path(section, root);
+    let store_id = section.get("StoreID").map(str::to_string);
+    write_atomic(&root.join(PROFILES_INI), ini.to_string().as_bytes())?;
+
+    if let (Some(store_id), Some(path)) = (store_id, path) {
+        let database = groups::database_path(root, &store_id);
+        if database.exists() {
+            groups::update_profile(&database, root, &path, Some(name))?;
+        }
+    }
+    Ok(())
+}
+
+/// Remove a profile from a Firefox profile root.
+///
+/// Removes its `profiles.ini` section (renumbering the others, as Firefox
+/// stops reading at the first gap), install defaults pointing to it and its
+/// profile group entry. Unless `keep_files` is set, the directory is deleted
+/// when it lies inside the profile root.
+///
+/// # Errors
+///
+/// See [`rename_profile`].
+pub fn delete_profile(root: &Path, id: &str, keep_files: bool) -> Result<()> {
+    let mut ini = load_profiles_ini(root)?;
+    let index = profile_section(&ini, root, id)?;
+    let section = ini.sections.remove(index);
+    let path = section_path(&section, root);
+    renumber_profile_sections(&mut ini);
+    let is_deleted_default = |section: &Section| {
+        section
+            .get("Default")
+            .is_some_and(|default| Some(resolve_path(root, default, true)) == path)
+    };
+    ini.sections
+        .retain(|s| !(s.name.starts_with(INSTALL_SECTION_PREFIX) && is_deleted_default(s)));
+    write_atomic(&root.join(PROFILES_INI), ini.to_string().as_bytes())?;
+
+    let installs_path = root.join(INSTALLS_INI);
+    if let Some(text) = read_optional(&installs_path)? {
+        let mut installs = Ini::parse(&text).map_err(|err| Error::Parse {
+            path: installs_path.clone(),
+            line: Some(err.line),
+            message: err.message,
+        })?;
+        let before = installs.sections.len();
+        installs.sections.retain(|s| !is_deleted_default(s));
+        if installs.sections.len() != before {
+            write_atomic(&installs_path, installs.to_string().as_bytes())?;
+        }
+    }
+
+    if let Some(path) = path {
+        if let Some(store_id) = section.get("StoreID") {
+            let database = groups::database_path(root, store_id);
+            if database.exists() {
+                groups::update_profile(&database, root, &path, None)?;
+            }
+        }
+        if !keep_files {
+            remove_profile_dir(root, &path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Compute the install hash Firefox uses for an install directory.
+///
+/// This is the uppercase hexadecimal `CityHash64` of the directory path
+/// encoded as UTF-16LE, e.g. `4F96D1932A9F858E` for `/usr/lib/firefox`.
+#[must_use]
+pub fn install_hash(install_dir: &Path) -> String {
+    let bytes: Vec<u8> = install_dir
+        .to_string_lossy()
+        .encode_utf16()
+        .flat_map(u16::to_le_bytes)
+        .collect();
+    format!("{:016X}", city_hash64(&bytes))
+}
+
+/// Compute the install hash of an installed browser.
+///
+/// The install directory is the directory of the resolved executable, so
+/// launcher symlinks like `/usr/bin/firefox` map to `/usr/lib/firefox`.
+/// Returns `None` for Flatpak and Snap installs, whose install directory is
+/// only visible inside the sandbox, and when the executable cannot be resolved.
+#[must_use]
+pub fn browser_install_hash(browser: &Browser) -> Option<String> {
+    if browser.packaging != Packaging::Native {
+        return None;
+    }
+    let executable = std::fs::canonicalize(&browser.executable).ok()?;
+    executable.parent().map(install_hash)
+}
+
+/// Read `profiles.ini` for editing; a missing file yields a new one.
+fn load_profiles_ini(root: &Path) -> Result<Ini> {
+    let path = root.join(PROFILES_INI);
+    let Some(text) = read_optional(&path)? else {
+        return Ok(Ini {
+            sections: vec![Section {
+                name: "General".to_string(),
+                entries: vec![
+                    ("StartWithLastProfile".to_string(), "1".to_string()),
+                    ("Version".to_string(), "2".to_string()),
+                ],
+            }],
+        });
+    };
+    Ini::parse(&text).map_err(|err| Error::Parse {
+        path,
+        line: Some(err.line),
+        message: err.message,
+    })
+}
+
+/// Index of the section of the profile with directory `id`.
+fn profile_section(ini: &Ini, root: &Path, id: &str) -> Result<usize> {
+    ini.sections
+        .iter()
+        .position(|s| {
+            is_profile_section(&s.name)
+                && section_path(s, root)
+                    .and_then(|path| ProfileId::from_dir(&path))
+                    .is_some_and(|dir| dir.0 == id)
+        })
+        .ok_or_else(|| Error::NotRegistered {
+            path: root.join(PROFILES_INI),
+            profile: id.to_string(),
+        })
+}
+
+fn section_path(section: &Section, root: &Path) -> Option<PathBuf> {
+    section
+        .get("Path")
+        .map(|path| resolve_path(root, path, section.flag("IsRelative")))
+}
+
+/// Name the profile sections `[Profile0]`, `[Profile1]`, ... in order.
+fn renumber_profile_sections(ini: &mut Ini) {
+    let mut number = 0;
+    for section in &mut ini.sections {
+        if is_profile_section(&section.name) {
+            section.name = format!("Profile{number}");
+            number += 1;
+        }
+    }
+}
+
+/// Whether a section is `[Profile<n>]`.
+fn is_profile_section(name: &str) -> bool {
+    name.strip_prefix("Profile")
+        .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Random 8-character prefix Firefox puts in front of profile directory names.
+pub(crate) fn random_salt() -> String {
+    use std::hash::{BuildHasher, Hasher};
+
+    const CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+    let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+    hasher.write_u32(std::process::id());
+    if let Ok(since) = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH) {
+        hasher.write_u128(since.as_nanos());
+    }
+    let mut bits = hasher.finish();
+    (0..8)
+        .map(|_| {
+            let index = usize::try_from(bits % 36).unwrap_or_default();
+            bits /= 36;
+            char::from(CHARS[index])
+        })
+        .collect()
+}
+
+/// Make a profile name usable as part of a directory name.
+fn dir_safe(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, ' ' | '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Read a file, treating a missing file as `None`.
+fn read_optional(path: &Path) -> Result<Option<String>> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => Ok(Some(text)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(Error::io(path, err)),
+    }
+}
+
+/// Resolve a `Path=` value; relative paths always use `/` separators.
+fn resolve_path(root: &Path, path: &str, is_relative: bool) -> PathBuf {
+    if is_relative {
+        let mut resolved = root.to_path_buf();
+        resolved.extend(path.split('/').filter(|c| !c.is_empty()));
+        resolved
+    } else {
+        PathBuf::from(path)
+    }
+}
+
+/// Last time a browser locked a profile, in seconds since the Unix epoch.
+///
+/// Firefox creates `lock` (a symlink to `<ip>:+<pid>`) and `.parentlock` on
+/// Unix, and `parent.lock` on Windows, whenever it opens a profile.
+fn lock_time(profile_dir: &Path) -> Option<u64> {
+    ["lock", ".parentlock", "parent.lock"]
+        .iter()
+        .filter_map(|name| std::fs::symlink_metadata(profile_dir.join(name)).ok())
+        .filter_map(|meta| meta.modified().ok())
+        .filter_map(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|since| since.as_secs())
+        .max()
+}
+
+/// Email of the Firefox Account a profile is signed in to.
+///
+/// Read from `signedInUser.json`, or the `services.sync.username` preference
+/// in `prefs.js` that older releases and Sync setups record.
+fn sync_email(profile_dir: &Path) -> Option<String> {
+    let signed_in =
+        crate::json::read_json::<serde_json::Value>(&profile_dir.join("signedInUser.json"))
+            .ok()
+            .flatten()
+            .and_then(|user| {
+                user.pointer("/accountData/email")?
+                    .as_str()
+                    .map(str::to_string)
+            });
+    signed_in.or_else(|| {
+        let prefs = std::fs::read_to_string(profile_dir.join("prefs.js")).ok()?;
+        prefs.lines().find_map(|line| {
+            let value = line
+                .trim()
+                .strip_prefix(r#"user_pref("services.sync.username", ""#)?
+                .strip_suffix(r#"");"#)?;
+            (!value.is_empty()).then(|| value.to_string())
+        })
+    })
+}
+
+/// Resolve a group database path, which uses the platform's separators.
+fn resolve_group_path(root: &Path, path: &str) -> PathBuf {
+    if Path::new(path).is_absolute() {
+        PathBuf::from(path)
+    } else {
+        let mut resolved = root.to_path_buf();
+        resolved.extend(path.split(['/', '\\']).filter(|c| !c.is_empty()));
+        resolved
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROFILES: &str = "\
+[Install4F96D1932A9F858E]
+Default=x8k2m1qa.default-release
+Locked=1
+
+[Profile2]
+Name=work
+IsRelative=0
+Path=/data/firefox/work
+
+[Profile1]
+Name=default
+IsRelative=1
+Path=p3n7c0vd.default
+Default=1
+
+[Profile0]
+Name=default-release
+IsRelative=1
+Path=x8k2m1qa.default-release
+
+[General]
+StartWithLastProfile=1
+Version=2
+";
+
+    fn root() -> PathBuf {
+        PathBuf::from("/home/user/.mozilla/firefox")
+    }
+
+    fn default_ids(ini: &ProfilesIni) -> Vec<&str> {
+        ini.profiles
+            .iter()
+            .filter(|p| p.profile.is_default)
+            .map(|p| p.profile.id.0.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn parses_profiles() {
+        let ini = ProfilesIni::parse(PROFILES, None, &root(), None).unwrap();
+        assert_eq!(ini.profiles.len(), 3);
+        assert!(ini.start_with_last_profile);
+
+        let work = &ini.profiles[0];
+        assert_eq!(work.profile.id.0, "work");
+        assert_eq!(work.profile.name, "work");
+        assert_eq!(work.profile.path, PathBuf::from("/data/firefox/work"));
+        assert!(!work.is_relative);
+
+        let release = &ini.profiles[2];
+        assert_eq!(release.profile.id.0, "x8k2m1qa.default-release");
+        assert_eq!(release.profile.name, "default-release");
+        assert_eq!(
+            release.profile.path,
+            root().join("x8k2m1qa.default-release")
+        );
+        assert_eq!(release.installs, ["4F96D1932A9F858E"]);
+        assert!(ini.installs[0].locked);
+    }
+
+    #[test]
+    fn install_default_wins_over_legacy_default() {
+        let ini = ProfilesIni::parse(PROFILES, None, &root(), Some("4F96D1932A9F858E")).unwrap();
+        assert_eq!(default_ids(&ini), ["x8k2m1qa.default-release"]);
+    }
+
+    #[test]
+    fn only_install_is_used_without_hash() {
+        let ini = ProfilesIni::parse(PROFILES, None, &root(), None).unwrap();
+        assert_eq!(default_ids(&ini), ["x8k2m1qa.default-release"]);
+    }
+
+    #[test]
+    fn unknown_install_uses_only_install_or_legacy_default() {
+        // A wrapper script outside the install directory hashes differently
+        let ini = ProfilesIni::parse(PROFILES, None, &root(), Some("0000000000000000")).unwrap();
+        assert_eq!(default_ids(&ini), ["x8k2m1qa.default-release"]);
+
+        let installs = "[3B6073811A6ABF12]\nDefault=work\n";
+        let ini = ProfilesIni::parse(PROFILES, Some(installs), &root(), Some("0000000000000000"))
+            .unwrap();
+        assert_eq!(default_ids(&ini), ["p3n7c0vd.default"]);
+    }
+
+    #[test]
+    fn installs_ini_adds_missing_installs() {
+        let installs = "[3B6073811A6ABF12]\nDefault=p3n7c0vd.default\nLocked=1\n\n\
+                        [4F96D1932A9F858E]\nDefault=p3n7c0vd.default\n";
+        let ini = ProfilesIni::parse(PROFILES, Some(installs), &root(), Some("3B6073811A6ABF12"))
+            .unwrap();
+        assert_eq!(ini.installs.len(), 2);
+        assert_eq!(default_ids(&ini), ["p3n7c0vd.default"]);
+
+        // profiles.ini takes precedence for installs listed in both
+        let release = ini
+            .installs
+            .iter()
+            .find(|i| i.hash == "4F96D1932A9F858E")
+            .unwrap();
+        assert_eq!(release.default, root().join("x8k2m1qa.default-release"));
+    }
+
+    #[test]
+    fn single_profile_is_default() {
+        let ini = ProfilesIni::parse(
+            "[Profile0]\nName=default\nIsRelative=1\nPath=abc.default\n",
+            None,
+            &root(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(default_ids(&ini), ["abc.default"]);
+    }
+
+    #[test]
+    fn malformed_files_are_parse_errors() {
+        let err = ProfilesIni::parse("[Profile0]\nName=x\n", None, &root(), None).unwrap_err();
+        assert!(matches!(err, Error::Parse { .. }), "{err}");
+
+        let err = ProfilesIni::parse("[General]\ngarbage\n", None, &root(), None).unwrap_err();
+        assert!(matches!(err, Error::Parse { line: Some(2), .. }), "{err}");
+    }
+
+    #[test]
+    fn install_hash_of_directory() {
+        assert_eq!(
+            install_hash(Path::new("/usr/lib/firefox")),
+            "4F96D1932A9F858E"
+        );
+    }
+
+    #[test]
+    fn reads_profile_root() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(PROFILES_INI), PROFILES).unwrap();
+
+        let profiles = list_profiles(dir.path(), None).unwrap();
+        assert_eq!(profiles.len(), 3);
+        assert!(
+            list_profiles(&dir.path().join("missing"), None)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn reads_sync_account_email() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join(PROFILES_INI),
+            "[Profile0]\nName=work\nIsRelative=1\nPath=a.work\n\n\
+             [Profile1]\nName=home\nIsRelative=1\nPath=b.home\n",
+        )
+        .unwrap();
+        for profile in ["a.work", "b.home"] {
+            std::fs::create_dir(dir.path().join(profile)).unwrap();
+        }
+        std::fs::write(
+            dir.path().join("a.work/signedInUser.json"),
+            r#"{"version": 1, "accountData": {"email": "me@corp.example", "verified": true}}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("b.home/prefs.js"),
+            "user_pref(\"browser.startup.page\", 3);\n\
+             user_pref(\"services.sync.username\", \"me@example.com\");\n",
+        )
+        .unwrap();
+
+        let profiles = list_profiles(dir.path(), None).unwrap();
+        assert_eq!(profiles[0].email.as_deref(), Some("me@corp.example"));
+        assert_eq!(profiles[1].email.as_deref(), Some("me@example.com"));
+    }
+
+    #[test]
+    fn profile_group_overrides_names_and_adds_profiles() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join(PROFILES_INI),
+            "[Profile0]\nName=Profile 1\nIsRelative=1\nPath=Profiles/x8k2m1qa.Profile 1\n\
+             StoreID=a1b2c3\nDefault=1\n\n[Profile1]\nName=old\nIsRelative=1\nPath=p3n7c0vd.old\n",
+        )
+        .unwrap();
+        groups::create_group(
+            &groups::database_path(dir.path(), "a1b2c3"),
+            &[
+                (
+                    "Profiles/x8k2m1qa.Profile 1",
+                    "Work",
+                    "briefcase",
+                    "firefox-compact-dark@mozilla.org",
+                ),
+                ("Profiles/q9w8e7r6.Profile 2", "Personal", "book", ""),
+            ],
+        );
+
+        let ini = ProfilesIni::read(dir.path(), None).unwrap();
+        let names: Vec<_> = ini
+            .profiles
+            .iter()
+            .map(|p| p.profile.name.as_str())
+            .collect();
+        assert_eq!(names, ["Work", "old", "Personal"]);
+
+        let work = &ini.profiles[0];
+        assert!(work.profile.is_default);
+        assert_eq!(work.profile.avatar.as_deref(), Some("briefcase"));
+        assert_eq!(
+            work.theme.as_ref().map(|t| t.id.as_str()),
+            Some("firefox-compact-dark@mozilla.org")
+        );
+        let personal = &ini.profiles[2];
+        assert_eq!(personal.profile.id.0, "q9w8e7r6.Profile 2");
+        assert_eq!(personal.store_id.as_deref(), Some("a1b2c3"));
+        assert_eq!(ini.profiles[1].store_id, None);
+    }
+
+    #[test]
+    fn unreadable_profile_group_falls_back_to_profiles_ini() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join(PROFILES_INI),
+            "[Profile0]\nName=Profile 1\nIsRelative=1\nPath=x8k2m1qa.Profile 1\nStoreID=a1b2c3\n",
+        )
+        .unwrap();
+        let profiles = list_profiles(dir.path(), None).unwrap();
+        assert_eq!(profiles[0].name, "Profile 1");
+
+        let database = groups::database_path(dir.path(), "a1b2c3");
+        std::fs::create_dir_all(database.parent().unwrap()).unwrap();
+        std::fs::write(&database, "not a database").unwrap();
+        let profiles = list_profiles(dir.path(), None).unwrap();
+        assert_eq!(profiles[0].name, "Profile 1");
+    }
+
+    #[test]
+    fn last_used_is_lock_time() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(PROFILES_INI), PROFILES).unwrap();
+        let release = dir.path().join("x8k2m1qa.default-release");
+        std::fs::create_dir(&release).unwrap();
+        std::fs::write(release.join(".parentlock"), "").unwrap();
+
+        let ini = ProfilesIni::read(dir.path(), None).unwrap();
+        assert!(ini.profiles[2].profile.last_used.is_some());
+        assert_eq!(ini.profiles[1].profile.last_used, None);
+    }
+
+    #[test]
+    fn creates_renames_and_deletes_profiles() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(root.join(PROFILES_INI), PROFILES).unwrap();
+        std::fs::write(
+            root.join(INSTALLS_INI),
+            "[4F96D1932A9F858E]\nDefault=x8k2m1qa.default-release\n",
+        )
+        .unwrap();
+
+        let created = create_profile(root, "Work/Client").unwrap();
+        assert!(created.id.0.ends_with(".Work_Client"), "{}", created.id);
+        assert!(created.path.is_dir());
+        let ini = ProfilesIni::read(root, None).unwrap();
+        assert_eq!(ini.profiles.len(), 4);
+        assert_eq!(ini.profiles[3].profile.name, "Work/Client");
+
+        rename_profile(root, &created.id.0, "Client").unwrap();
+        let ini = ProfilesIni::read(root, None).unwrap();
+        assert_eq!(ini.profiles[3].profile.name, "Client");
+
+        std::fs::create_dir(root.join("x8k2m1qa.default-release")).unwrap();
+        delete_profile(root, "x8k2m1qa.default-release", false).unwrap();
+        assert!(!root.join("x8k2m1qa.default-release").exists());
+        let text = std::fs::read_to_string(root.join(PROFILES_INI)).unwrap();
+        assert!(text.contains("[Profile2]"));
+        assert!(!text.contains("[Profile3]"));
+        assert!(!text.contains("Install"));
+        let installs = std::fs::read_to_string(root.join(INSTALLS_INI)).unwrap();
+        assert!(installs.is_empty(), "{installs}");
+        assert_eq!(list_profiles(root, None).unwrap().len(), 3);
+
+        let err = delete_profile(root, "missing", false).unwrap_err();
+        assert!(matches!(err, Error::NotRegistered { .. }), "{err}");
+    }
+
+    #[test]
+    fn renames_and_deletes_profile_group_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join(PROFILES_INI),
+            "[Profile0]\nName=Profile 1\nIsRelative=1\nPath=Profiles/abc.Profile 1\nStoreID=s1\n",
+        )
+        .unwrap();
+        let database = groups::database_path(dir.path(), "s1");
+        groups::create_group(&database, &[("Profiles/abc.Profile 1", "Work", "", "")]);
+
+        rename_profile(dir.path(), "abc.Profile 1", "Office").unwrap();
+        assert_eq!(groups::read_group(&database).unwrap()[0].name, "Office");
+        assert_eq!(list_profiles(dir.path(), None).unwrap()[0].name, "Office");
+
+        delete_profile(dir.path(), "abc.Profile 1", true).unwrap();
+        assert!(groups::read_group(&database).unwrap().is_empty());
+    }
+
+    #[test]
+    fn creates_profiles_ini() {
+        let dir = tempfile::tempdir().unwrap();
+        let created = create_profile(dir.path(), "Personal").unwrap();
+        let profiles = list_profiles(dir.path(), None).unwrap();
+        assert_eq!(profiles.len(), 1);
+        assert_eq!(profiles[0].id, created.id);
+        assert!(profiles[0].is_default);
+    }
+
+    #[test]
+    fn create_profile_closes_section_gaps() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join(PROFILES_INI),
+            "[Profile0]\nName=a\nIsRelative=1\nPath=a\n\n\
+             [Profile2]\nName=b\nIsRelative=1\nPath=b\n",
+        )
+        .unwrap();
+
+        create_profile(dir.path(), "c").unwrap();
+        let ini = load_profiles_ini(dir.path()).unwrap();
+        let names: Vec<_> = ini.sections.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["Profile0", "Profile1", "Profile2"]);
+        assert_eq!(ini.sections[2].get("Name"), Some("c"));
+    }
+}
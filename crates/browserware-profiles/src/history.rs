@@ -0,0 +1,259 @@
+//! Browsing history of a profile, aggregated by host.
+//!
+//! - Chromium keeps visited URLs and their visit counts in the profile's
+//!   `History` database (`urls` table).
+//! - Firefox keeps them in `places.sqlite` (`moz_places` table).
+//! - GNOME Web keeps them in `ephy-history.db` (`urls` table).
+//!
+//! Browsers lock these databases while they run, so each one is copied
+//! (with its write-ahead log or rollback journal) to a private temporary
+//! directory and read from there. Nothing is written to the profile.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use browserware_types::{BrowserFamily, Profile};
+use rusqlite::{Connection, OpenFlags};
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, Result};
+
+/// Name of a Chromium profile's history database.
+pub const CHROMIUM_HISTORY: &str = "History";
+
+/// Name of a Firefox profile's history and bookmarks database.
+pub const FIREFOX_PLACES: &str = "places.sqlite";
+
+/// Name of a GNOME Web profile's history database.
+pub const WEBKIT_HISTORY: &str = "ephy-history.db";
+
+/// Files SQLite keeps next to a database that hold uncommitted changes.
+const SIDE_FILES: [&str; 2] = ["-wal", "-journal"];
+
+/// Number of visits to one host.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct HostVisits {
+    /// Host name, lowercase (e.g. `www.example.com`)
+    pub host: String,
+    /// Total visits to URLs on the host
+    pub visits: u64,
+}
+
+/// Count the visits to each host in a profile's history.
+///
+/// Only `http` and `https` URLs are counted. The result is ordered by visits,
+/// most visited first. A profile without a history database has no visits.
+///
+/// # Errors
+///
+/// - [`Error::UnsupportedBrowser`] for profiles of other browser families
+/// - [`Error::Io`] if the database cannot be copied
+/// - [`Error::Database`] if the copy cannot be read
+pub fn host_visits(profile: &Profile) -> Result<Vec<HostVisits>> {
+    let (file, table) = match profile.family {
+        BrowserFamily::Chromium => (CHROMIUM_HISTORY, "urls"),
+        BrowserFamily::Firefox => (FIREFOX_PLACES, "moz_places"),
+        BrowserFamily::WebKit => (WEBKIT_HISTORY, "urls"),
+        BrowserFamily::Other => {
+            return Err(Error::UnsupportedBrowser {
+                browser: profile.family.to_string(),
+            });
+        }
+    };
+    let path = profile.path.join(file);
+    if !path.is_file() {
+        tracing::debug!(path = %path.display(), "No history database");
+        return Ok(Vec::new());
+    }
+
+    let copy = Snapshot::new(&path)?;
+    read_visits(&copy, table).map_err(|source| Error::Database { path, source })
+}
+
+/// Sum the visit counts of `table`'s URLs per host.
+fn read_visits(copy: &Snapshot, table: &str) -> rusqlite::Result<Vec<HostVisits>> {
+    let connection = copy.open()?;
+    let mut statement = connection.prepare(&format!(
+        "SELECT url, visit_count FROM {table} WHERE visit_count > 0"
+    ))?;
+    let rows = statement.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+    })?;
+
+    let mut counts: HashMap<String, u64> = HashMap::new();
+    for row in rows {
+        let (url, visits) = row?;
+        let Ok(url) = url::Url::parse(&url) else {
+            continue;
+        };
+        if let ("http" | "https", Some(host)) = (url.scheme(), url.host_str()) {
+            *counts.entry(host.to_lowercase()).or_default() += visits.unsigned_abs();
+        }
+    }
+
+    let mut visits: Vec<HostVisits> = counts
+        .into_iter()
+        .map(|(host, visits)| HostVisits { host, visits })
+        .collect();
+    visits.sort_by(|a, b| b.visits.cmp(&a.visits).then_with(|| a.host.cmp(&b.host)));
+    Ok(visits)
+}
+
+/// A copy of a database in a temporary directory, removed on drop.
+pub(crate) struct Snapshot {
+    dir: PathBuf,
+    /// Path of the copy
+    database: PathBuf,
+}
+
+impl Snapshot {
+    /// Copy a database and its uncommitted changes.
+    pub(crate) fn new(path: &Path) -> Result<Self> {
+        let dir = std::env::temp_dir().join(format!(
+            "browserware-snapshot-{}",
+            crate::firefox::random_salt()
+        ));
+        std::fs::create_dir(&dir).map_err(|err| Error::io(&dir, err))?;
+        let snapshot = Self {
+            database: dir.join("snapshot.sqlite"),
+            dir,
+        };
+
+        std::fs::copy(path, &snapshot.database).map_err(|err| Error::io(path, err))?;
+        for suffix in SIDE_FILES {
+            let mut side = path.as_os_str().to_owned();
+            side.push(suffix);
+            let side = PathBuf::from(side);
+            if side.is_file() {
+                let mut copy = snapshot.database.as_os_str().to_owned();
+                copy.push(suffix);
+                std::fs::copy(&side, copy).map_err(|err| Error::io(&side, err))?;
+            }
+        }
+        Ok(snapshot)
+    }
+
+    /// Open the copy.
+    ///
+    /// The copy is private, so it is opened read-write: a hot journal left
+    /// by a transaction in progress must be rolled back before reading.
+    pub(crate) fn open(&self) -> rusqlite::Result<Connection> {
+        Connection::open_with_flags(
+            &self.database,
+            OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )
+    }
+}
+
+impl Drop for Snapshot {
+    fn drop(&mut self) {
+        if let Err(err) = std::fs::remove_dir_all(&self.dir) {
+            tracing::debug!(dir = %self.dir.display(), "Failed to remove database copy: {err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(dir: &Path, file: &str, table: &str, urls: &[(&str, i64)]) {
+        let connection = Connection::open(dir.join(file)).unwrap();
+        connection
+            .execute_batch(&format!(
+                "CREATE TABLE {table} (id INTEGER PRIMARY KEY, url TEXT, visit_count INTEGER)"
+            ))
+            .unwrap();
+        for (url, count) in urls {
+            connection
+                .execute(
+                    &format!("INSERT INTO {table} (url, visit_count) VALUES (?1, ?2)"),
+                    rusqlite::params![url, count],
+                )
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn counts_visits_per_host() {
+        let dir = tempfile::tempdir().unwrap();
+        history(
+            dir.path(),
+            FIREFOX_PLACES,
+            "moz_places",
+            &[
+                ("https://GitHub.com/a", 3),
+                ("https://github.com/b", 2),
+                ("http://docs.rs/serde", 4),
+                ("about:config", 9),
+                ("file:///tmp/x.html", 9),
+                ("https://never.example/", 0),
+            ],
+        );
+        let profile = Profile::new(
+            "abc.work",
+            "work",
+            dir.path().into(),
+            BrowserFamily::Firefox,
+        );
+
+        let visits = host_visits(&profile).unwrap();
+        let visits: Vec<_> = visits.iter().map(|v| (v.host.as_str(), v.visits)).collect();
+        assert_eq!(visits, [("github.com", 5), ("docs.rs", 4)]);
+    }
+
+    #[test]
+    fn missing_history_is_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let profile = Profile::new(
+            "Default",
+            "Person 1",
+            dir.path().into(),
+            BrowserFamily::Chromium,
+        );
+        assert!(host_visits(&profile).unwrap().is_empty());
+
+        history(
+            dir.path(),
+            CHROMIUM_HISTORY,
+            "urls",
+            &[("https://mail.example", 7)],
+        );
+        assert_eq!(host_visits(&profile).unwrap()[0].visits, 7);
+    }
+
+    #[test]
+    fn reads_copy_with_hot_journal() {
+        let dir = tempfile::tempdir().unwrap();
+        history(
+            dir.path(),
+            CHROMIUM_HISTORY,
+            "urls",
+            &[("https://mail.example", 7)],
+        );
+        // A running browser in the middle of a transaction, with the
+        // original pages in History-journal and changes written to History
+        let browser = Connection::open(dir.path().join(CHROMIUM_HISTORY)).unwrap();
+        browser
+            .execute_batch(
+                "PRAGMA cache_size = 1; BEGIN; \
+                 UPDATE urls SET visit_count = 1000; \
+                 WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 2000) \
+                 INSERT INTO urls (url, visit_count) SELECT 'https://x.example/' || i, 1 FROM n;",
+            )
+            .unwrap();
+        assert!(dir.path().join("History-journal").is_file());
+
+        let profile = Profile::new(
+            "Default",
+            "Person 1",
+            dir.path().into(),
+            BrowserFamily::Chromium,
+        );
+        // The copy is rolled back to the last committed state
+        let visits = host_visits(&profile).unwrap();
+        assert_eq!(visits.len(), 1);
+        assert_eq!(visits[0].visits, 7);
+    }
+}
@@ -0,0 +1,269 @@
+//! Resolving identities to browser profiles on the current machine.
+//!
+//! An [`Identity`] lists candidate `browser:profile` targets in order of
+//! preference. The first candidate whose browser is installed and whose
+//! profile exists is used; when none is, a profile signed in with one of the
+//! identity's emails (Chromium account or Firefox Sync) is used instead.
+
+use browserware_types::{Browser, BrowserTarget, Identity, Profile};
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, Result, find_profile, list_profiles};
+
+/// An identity resolved to a browser profile.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResolvedIdentity {
+    /// Browser to open
+    pub browser: Browser,
+    /// Profile representing the identity
+    pub profile: Profile,
+    /// The candidate that matched, or a target built from an email match
+    pub target: BrowserTarget,
+}
+
+/// Resolve an identity to the first available candidate among `browsers`.
+///
+/// Candidates without a profile use the browser's default profile.
+///
+/// # Example
+///
+/// ```no_run
+/// use browserware_types::Identity;
+///
+/// let work = Identity::new("work")
+///     .with_candidate("chrome:Profile 1".parse()?)
+///     .with_candidate("firefox:work".parse()?);
+/// let browsers = browserware_detect::detect_browsers();
+/// let resolved = browserware_profiles::resolve_identity(&work, &browsers)?;
+/// println!("{} {}", resolved.browser.name, resolved.profile.name);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns [`Error::IdentityUnavailable`] if no candidate or email matches.
+pub fn resolve_identity(identity: &Identity, browsers: &[Browser]) -> Result<ResolvedIdentity> {
+    resolve_with(
+        identity,
+        browsers,
+        |browser, query| find_profile(browser, query.unwrap_or("@default")),
+        list_profiles,
+    )
+}
+
+/// Add a candidate for every profile signed in with one of the identity's
+/// emails that is not a candidate yet.
+///
+/// Returns the number of candidates added.
+pub fn populate_identity(identity: &mut Identity, browsers: &[Browser]) -> usize {
+    populate_with(identity, browsers, list_profiles)
+}
+
+/// Build one identity per account email found in the profiles of `browsers`.
+///
+/// Each identity is named after its email and lists the signed-in profiles
+/// in browser order.
+#[must_use]
+pub fn discover_identities(browsers: &[Browser]) -> Vec<Identity> {
+    let mut identities: Vec<Identity> = Vec::new();
+    for (target, email) in signed_in_profiles(browsers, list_profiles) {
+        let index = identities
+            .iter()
+            .position(|identity| identity.has_email(&email))
+            .unwrap_or_else(|| {
+                identities.push(Identity::new(email.to_lowercase()).with_email(&email));
+                identities.len() - 1
+            });
+        identities[index].candidates.push(target);
+    }
+    identities
+}
+
+fn resolve_with(
+    identity: &Identity,
+    browsers: &[Browser],
+    find: impl Fn(&Browser, Option<&str>) -> Result<Profile>,
+    list: impl Fn(&Browser) -> Result<Vec<Profile>>,
+) -> Result<ResolvedIdentity> {
+    for candidate in &identity.candidates {
+        let Some(browser) = browsers.iter().find(|b| matches_browser(candidate, b)) else {
+            tracing::debug!(identity = %identity.name, %candidate, "Candidate browser not installed");
+            continue;
+        };
+        match find(browser, candidate.profile.as_deref()) {
+            Ok(profile) => {
+                return Ok(ResolvedIdentity {
+                    browser: browser.clone(),
+                    profile,
+                    target: candidate.clone(),
+                });
+            }
+            Err(err) => {
+                tracing::debug!(identity = %identity.name, %candidate, "Candidate unavailable: {err}");
+            }
+        }
+    }
+
+    if !identity.emails.is_empty() {
+        for browser in browsers {
+            let profiles = list(browser).unwrap_or_default();
+            let signed_in = profiles
+                .into_iter()
+                .find(|p| p.email.as_deref().is_some_and(|e| identity.has_email(e)));
+            if let Some(profile) = signed_in {
+                return Ok(ResolvedIdentity {
+                    target: BrowserTarget::for_profile(browser, &profile),
+                    browser: browser.clone(),
+                    profile,
+                });
+            }
+        }
+    }
+
+    Err(Error::IdentityUnavailable {
+        identity: identity.name.clone(),
+        tried: identity
+            .candidates
+            .iter()
+            .map(ToString::to_string)
+            .collect(),
+    })
+}
+
+fn populate_with(
+    identity: &mut Identity,
+    browsers: &[Browser],
+    list: impl Fn(&Browser) -> Result<Vec<Profile>>,
+) -> usize {
+    let mut added = 0;
+    for (target, email) in signed_in_profiles(browsers, list) {
+        if identity.has_email(&email) && !identity.candidates.contains(&target) {
+            identity.candidates.push(target);
+            added += 1;
+        }
+    }
+    added
+}
+
+/// Targets of all profiles with an account email, and the email.
+fn signed_in_profiles(
+    browsers: &[Browser],
+    list: impl Fn(&Browser) -> Result<Vec<Profile>>,
+) -> Vec<(BrowserTarget, String)> {
+    browsers
+        .iter()
+        .flat_map(|browser| {
+            list(browser)
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|profile| {
+                    let email = profile.email.clone()?;
+                    Some((BrowserTarget::for_profile(browser, &profile), email))
+                })
+        })
+        .collect()
+}
+
+fn matches_browser(candidate: &BrowserTarget, browser: &Browser) -> bool {
+    let wanted = &candidate.browser.0;
+    (browser.id.0 == *wanted || browser.name.eq_ignore_ascii_case(wanted))
+        && candidate.packaging.is_none_or(|p| browser.packaging == p)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use browserware_types::{BrowserFamily, Packaging};
+
+    fn browsers() -> Vec<Browser> {
+        vec![
+            Browser::new("firefox", "Firefox", "/usr/bin/firefox".into()),
+            Browser::new("chromium", "Chromium", "/usr/bin/chromium".into())
+                .with_packaging(Packaging::Flatpak),
+        ]
+    }
+
+    fn profiles(browser: &Browser) -> Vec<Profile> {
+        let profile = |id: &str| {
+            Profile::new(
+                id,
+                id,
+                format!("/data/{id}").into(),
+                BrowserFamily::Chromium,
+            )
+        };
+        match browser.id.0.as_str() {
+            "firefox" => vec![profile("abc.home").with_default(true)],
+            _ => vec![
+                profile("Default").with_default(true),
+                profile("Profile 1").with_email("Me@Corp.example"),
+            ],
+        }
+    }
+
+    fn find(browser: &Browser, query: Option<&str>) -> Result<Profile> {
+        let profiles = profiles(browser);
+        match query {
+            Some(query) => crate::select_profile(&browser.id.0, &profiles, query),
+            None => profiles
+                .into_iter()
+                .find(|p| p.is_default)
+                .ok_or_else(|| Error::NotFound {
+                    browser: browser.id.0.clone(),
+                    profile: "@default".into(),
+                }),
+        }
+    }
+
+    fn resolve(identity: &Identity) -> Result<ResolvedIdentity> {
+        resolve_with(identity, &browsers(), find, |b| Ok(profiles(b)))
+    }
+
+    #[test]
+    fn first_available_candidate_wins() {
+        let work = Identity::new("work")
+            .with_candidate("chrome:Profile 1".parse().unwrap())
+            .with_candidate("firefox:missing".parse().unwrap())
+            .with_candidate("chromium@flatpak:Profile 1".parse().unwrap())
+            .with_candidate("firefox".parse().unwrap());
+        let resolved = resolve(&work).unwrap();
+        assert_eq!(resolved.browser.id.0, "chromium");
+        assert_eq!(resolved.profile.id.0, "Profile 1");
+
+        let home = Identity::new("home").with_candidate("firefox".parse().unwrap());
+        assert_eq!(resolve(&home).unwrap().profile.id.0, "abc.home");
+    }
+
+    #[test]
+    fn falls_back_to_account_email() {
+        let work = Identity::new("work")
+            .with_candidate("chrome:Work".parse().unwrap())
+            .with_email("me@corp.example");
+        let resolved = resolve(&work).unwrap();
+        assert_eq!(resolved.target.to_string(), "chromium@flatpak:Profile 1");
+
+        let nobody = Identity::new("nobody").with_candidate("chrome".parse().unwrap());
+        let err = resolve(&nobody).unwrap_err();
+        assert!(matches!(err, Error::IdentityUnavailable { .. }), "{err}");
+        assert!(err.to_string().contains("tried chrome"), "{err}");
+    }
+
+    #[test]
+    fn populates_candidates_from_emails() {
+        let mut work = Identity::new("work").with_email("me@corp.example");
+        assert_eq!(
+            populate_with(&mut work, &browsers(), |b| Ok(profiles(b))),
+            1
+        );
+        assert_eq!(
+            populate_with(&mut work, &browsers(), |b| Ok(profiles(b))),
+            0
+        );
+        assert_eq!(
+            work.candidates,
+            ["chromium@flatpak:Profile 1"
+                .parse::<BrowserTarget>()
+                .unwrap()]
+        );
+    }
+}
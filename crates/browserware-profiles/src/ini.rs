@@ -0,0 +1,148 @@
+//! Minimal INI reader for Mozilla profile files.
+//!
+//! Mozilla's INI dialect is simple: `[Section]` headers, `Key=Value` lines,
+//! and `;` or `#` comments. Keys are case-sensitive and values are not quoted.
+//! Section and entry order is preserved, and documents can be written back
+//! (without comments).
+
+/// A parsed INI document.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Ini {
+    pub(crate) sections: Vec<Section>,
+}
+
+/// A `[Section]` and its entries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Section {
+    pub(crate) name: String,
+    pub(crate) entries: Vec<(String, String)>,
+}
+
+/// A line that is neither a section header, an entry, nor a comment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SyntaxError {
+    /// 1-based line number
+    pub(crate) line: usize,
+    pub(crate) message: String,
+}
+
+impl Ini {
+    /// Parse an INI document.
+    pub(crate) fn parse(text: &str) -> Result<Self, SyntaxError> {
+        let mut ini = Self::default();
+        // Strip a UTF-8 byte order mark written by some Windows editors
+        let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+
+        for (index, raw) in text.lines().enumerate() {
+            let line = raw.trim();
+            if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[') {
+                let Some(name) = name.strip_suffix(']') else {
+                    return Err(SyntaxError {
+                        line: index + 1,
+                        message: "unterminated section header".to_string(),
+                    });
+                };
+                ini.sections.push(Section {
+                    name: name.trim().to_string(),
+                    entries: Vec::new(),
+                });
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(SyntaxError {
+                    line: index + 1,
+                    message: format!("expected 'key=value', found '{line}'"),
+                });
+            };
+            let Some(section) = ini.sections.last_mut() else {
+                return Err(SyntaxError {
+                    line: index + 1,
+                    message: "entry outside of a section".to_string(),
+                });
+            };
+            section
+                .entries
+                .push((key.trim().to_string(), value.trim().to_string()));
+        }
+        Ok(ini)
+    }
+}
+
+impl Section {
+    /// Get the value of a key.
+    pub(crate) fn get(&self, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Get a `0`/`1` flag; missing keys are `false`.
+    pub(crate) fn flag(&self, key: &str) -> bool {
+        self.get(key) == Some("1")
+    }
+
+    /// Set the value of a key, appending it if missing.
+    pub(crate) fn set(&mut self, key: &str, value: impl Into<String>) {
+        let value = value.into();
+        match self.entries.iter_mut().find(|(k, _)| k == key) {
+            Some(entry) => entry.1 = value,
+            None => self.entries.push((key.to_string(), value)),
+        }
+    }
+}
+
+impl std::fmt::Display for Ini {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (index, section) in self.sections.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            writeln!(f, "[{}]", section.name)?;
+            for (key, value) in &section.entries {
+                writeln!(f, "{key}={value}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_sections_and_entries() {
+        let ini = Ini::parse(
+            "\u{feff}; comment\n[General]\nStartWithLastProfile=1\n\n[Profile0]\nName = default\nPath=a.default\n",
+        )
+        .unwrap();
+        assert_eq!(ini.sections.len(), 2);
+        let profile = &ini.sections[1];
+        assert_eq!(profile.get("Name"), Some("default"));
+        assert_eq!(profile.get("Missing"), None);
+        assert!(ini.sections[0].flag("StartWithLastProfile"));
+    }
+
+    #[test]
+    fn writes_back() {
+        let mut ini = Ini::parse("[General]\nVersion=2\n\n[Profile0]\nName=a\n").unwrap();
+        ini.sections[1].set("Name", "b");
+        ini.sections[1].set("Path", "x.b");
+        assert_eq!(
+            ini.to_string(),
+            "[General]\nVersion=2\n\n[Profile0]\nName=b\nPath=x.b\n"
+        );
+    }
+
+    #[test]
+    fn reports_syntax_errors_with_line() {
+        let err = Ini::parse("[General]\nnot an entry\n").unwrap_err();
+        assert_eq!(err.line, 2);
+        assert!(Ini::parse("Key=orphan\n").is_err());
+        assert!(Ini::parse("[Broken\n").is_err());
+    }
+}
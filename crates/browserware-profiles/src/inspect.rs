@@ -0,0 +1,572 @@
+//! Profile disk usage and health.
+//!
+//! [`inspect_profile`] measures a profile on disk and checks that the files
+//! a browser cannot start without are readable:
+//!
+//! | Browser   | Checked files |
+//! |-----------|---------------|
+//! | Chromium  | `Preferences`, `Secure Preferences` (JSON), `History` (SQLite) |
+//! | Firefox   | `prefs.js` (preference syntax), `extensions.json` (JSON), `places.sqlite` (SQLite) |
+//! | GNOME Web | `ephy-history.db` (SQLite) |
+//!
+//! Databases are checked with `PRAGMA integrity_check` on a copy, so a
+//! running browser is not disturbed.
+//!
+//! Caches count towards a profile's size: those inside the profile directory
+//! and, on Linux, the profile's directory under `$XDG_CACHE_HOME` where
+//! Chromium and Firefox keep their disk cache.
+
+use std::path::{Component, Path, PathBuf};
+
+use browserware_types::{BrowserFamily, Profile};
+use serde::{Deserialize, Serialize};
+
+use crate::ProfileExt;
+use crate::history::Snapshot;
+use crate::json::read_json;
+use crate::lock::LockState;
+
+/// Profile-relative directories holding caches the browser can rebuild.
+pub const CACHE_DIRS: [&str; 18] = [
+    // Chromium
+    "Cache",
+    "Code Cache",
+    "DawnCache",
+    "DawnGraphiteCache",
+    "DawnWebGPUCache",
+    "GPUCache",
+    "GrShaderCache",
+    "GraphiteDawnCache",
+    "Media Cache",
+    "ShaderCache",
+    "Service Worker/CacheStorage",
+    "Service Worker/ScriptCache",
+    // Firefox
+    "cache2",
+    "OfflineCache",
+    "safebrowsing",
+    "shader-cache",
+    "startupCache",
+    "thumbnails",
+];
+
+/// Disk usage and health of a profile.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ProfileReport {
+    /// The inspected profile
+    pub profile: Profile,
+    /// Space used on disk
+    pub size: DiskUsage,
+    /// Last time the profile was used, in seconds since the Unix epoch: the
+    /// time the browser recorded, or else when its settings were last written
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_used: Option<u64>,
+    /// Whether a browser holds the profile
+    pub lock: LockState,
+    /// Results of reading the profile's key files
+    pub checks: Vec<FileCheck>,
+    /// Overall health
+    pub verdict: Verdict,
+    /// Why the verdict is not healthy
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub problems: Vec<String>,
+}
+
+/// Space used by a profile, in bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct DiskUsage {
+    /// Everything, caches included
+    pub total: u64,
+    /// Caches the browser can rebuild
+    pub cache: u64,
+    /// Everything but caches
+    pub data: u64,
+}
+
+/// Result of reading one of a profile's key files.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct FileCheck {
+    /// File name, relative to the profile directory
+    pub file: String,
+    /// Outcome
+    pub status: CheckStatus,
+    /// Details of a failed check
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// Outcome of a file check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum CheckStatus {
+    /// The file was read successfully
+    Ok,
+    /// The file does not exist
+    Missing,
+    /// The file exists but is corrupt
+    Invalid,
+    /// The file could not be read (e.g. permissions)
+    Unreadable,
+}
+
+impl CheckStatus {
+    /// Returns the canonical string name for this status.
+    #[must_use]
+    pub const fn canonical_name(self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::Missing => "missing",
+            Self::Invalid => "invalid",
+            Self::Unreadable => "unreadable",
+        }
+    }
+}
+
+impl std::fmt::Display for CheckStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.canonical_name())
+    }
+}
+
+/// Overall health of a profile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum Verdict {
+    /// Every key file is readable
+    Healthy,
+    /// The profile works but needs attention (missing settings, stale lock)
+    Warning,
+    /// A key file is corrupt; the browser may lose data or refuse the profile
+    Corrupt,
+}
+
+impl Verdict {
+    /// Returns the canonical string name for this verdict.
+    #[must_use]
+    pub const fn canonical_name(self) -> &'static str {
+        match self {
+            Self::Healthy => "healthy",
+            Self::Warning => "warning",
+            Self::Corrupt => "corrupt",
+        }
+    }
+}
+
+impl std::fmt::Display for Verdict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.canonical_name())
+    }
+}
+
+/// How a key file is checked.
+#[derive(Clone, Copy)]
+enum FileKind {
+    Json,
+    FirefoxPrefs,
+    Sqlite,
+}
+
+/// A key file of a profile, and whether the browser writes it on first start.
+struct KeyFile {
+    name: &'static str,
+    kind: FileKind,
+    required: bool,
+}
+
+const fn key_file(name: &'static str, kind: FileKind, required: bool) -> KeyFile {
+    KeyFile {
+        name,
+        kind,
+        required,
+    }
+}
+
+/// Measure a profile and check its key files.
+///
+/// Inspection never fails: unreadable files are reported in the checks.
+///
+/// # Example
+///
+/// ```no_run
+/// use browserware_profiles::ProfileExt;
+///
+/// if let Some(chrome) = browserware_detect::detect_browser("chrome") {
+///     for profile in browserware_profiles::list_profiles(&chrome)? {
+///         let report = profile.inspect();
+///         println!("{}: {} bytes, {}", profile.name, report.size.total, report.verdict);
+///     }
+/// }
+/// # Ok::<(), browserware_profiles::Error>(())
+/// ```
+#[must_use]
+pub fn inspect_profile(profile: &Profile) -> ProfileReport {
+    let external_cache = browserware_detect::BaseDirs::from_env().and_then(|dirs| {
+        let cache_home = std::env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+            .unwrap_or_else(|| dirs.home.join(".cache"));
+        external_cache_dir(&profile.path, &dirs.home, &dirs.xdg_config, &cache_home)
+    });
+    inspect_with(profile, external_cache.as_deref())
+}
+
+fn inspect_with(profile: &Profile, external_cache: Option<&Path>) -> ProfileReport {
+    let mut size = DiskUsage::default();
+    measure(&profile.path, Path::new(""), false, &mut size);
+    if let Some(dir) = external_cache {
+        tracing::debug!(dir = %dir.display(), "Measuring profile cache");
+        measure(dir, Path::new(""), true, &mut size);
+    }
+    size.data = size.total - size.cache;
+
+    let files = key_files(profile.family);
+    let checks: Vec<FileCheck> = files
+        .iter()
+        .map(|file| check_file(&profile.path.join(file.name), file))
+        .collect();
+    let lock = profile.lock_state();
+
+    let mut problems = Vec::new();
+    let mut verdict = Verdict::Healthy;
+    for (file, check) in files.iter().zip(&checks) {
+        let problem = match check.status {
+            CheckStatus::Ok => continue,
+            CheckStatus::Missing if !file.required => continue,
+            CheckStatus::Missing => (Verdict::Warning, format!("{} is missing", check.file)),
+            CheckStatus::Unreadable => (Verdict::Warning, format!("{} is unreadable", check.file)),
+            CheckStatus::Invalid => (Verdict::Corrupt, format!("{} is corrupt", check.file)),
+        };
+        verdict = verdict.max(problem.0);
+        problems.push(problem.1);
+    }
+    if let LockState::Stale { pid } = lock {
+        verdict = verdict.max(Verdict::Warning);
+        problems.push(format!(
+            "stale lock left by process {pid}; the browser may not have exited cleanly"
+        ));
+    }
+
+    let last_used = profile.last_used.or_else(|| {
+        files
+            .iter()
+            .filter(|file| file.required)
+            .find_map(|file| modified(&profile.path.join(file.name)))
+    });
+    ProfileReport {
+        profile: profile.clone(),
+        size,
+        last_used,
+        lock,
+        checks,
+        verdict,
+        problems,
+    }
+}
+
+fn key_files(family: BrowserFamily) -> Vec<KeyFile> {
+    match family {
+        BrowserFamily::Chromium => vec![
+            key_file("Preferences", FileKind::Json, true),
+            key_file("Secure Preferences", FileKind::Json, false),
+            key_file(crate::history::CHROMIUM_HISTORY, FileKind::Sqlite, false),
+        ],
+        BrowserFamily::Firefox => vec![
+            key_file("prefs.js", FileKind::FirefoxPrefs, true),
+            key_file(
+                crate::extensions::FIREFOX_EXTENSIONS_JSON,
+                FileKind::Json,
+                false,
+            ),
+            key_file(crate::history::FIREFOX_PLACES, FileKind::Sqlite, false),
+        ],
+        BrowserFamily::WebKit => vec![key_file(
+            crate::history::WEBKIT_HISTORY,
+            FileKind::Sqlite,
+            false,
+        )],
+        BrowserFamily::Other => Vec::new(),
+    }
+}
+
+fn check_file(path: &Path, file: &KeyFile) -> FileCheck {
+    let (status, message) = if path.is_file() {
+        let result = match file.kind {
+            FileKind::Json => read_json::<serde_json::Value>(path)
+                .map_err(|err| match err {
+                    crate::Error::Io { source, .. } => {
+                        (CheckStatus::Unreadable, source.to_string())
+                    }
+                    err => (CheckStatus::Invalid, err.to_string()),
+                })
+                .map(drop),
+            FileKind::FirefoxPrefs => std::fs::read_to_string(path)
+                .map_err(|err| (CheckStatus::Unreadable, err.to_string()))
+                .and_then(|text| {
+                    check_prefs_js(&text).map_err(|message| (CheckStatus::Invalid, message))
+                }),
+            FileKind::Sqlite => check_sqlite(path),
+        };
+        match result {
+            Ok(()) => (CheckStatus::Ok, None),
+            Err((status, message)) => (status, Some(message)),
+        }
+    } else {
+        (CheckStatus::Missing, None)
+    };
+    FileCheck {
+        file: file.name.to_string(),
+        status,
+        message,
+    }
+}
+
+/// Check the syntax of a Firefox `prefs.js`: comments and one
+/// `user_pref(...);` call per line.
+fn check_prefs_js(text: &str) -> std::result::Result<(), String> {
+    let mut in_comment = false;
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if in_comment {
+            in_comment = !line.contains("*/");
+            continue;
+        }
+        if line.is_empty() || line.starts_with("//") || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with("/*") {
+            in_comment = !line.contains("*/");
+            continue;
+        }
+        let is_pref = ["user_pref(", "pref(", "sticky_pref("]
+            .iter()
+            .any(|call| line.starts_with(call));
+        if !is_pref || !line.ends_with(");") {
+            return Err(format!("unexpected content at line {}", index + 1));
+        }
+    }
+    if in_comment {
+        return Err("unterminated comment".to_string());
+    }
+    Ok(())
+}
+
+/// Run `PRAGMA integrity_check` on a copy of a database.
+fn check_sqlite(path: &Path) -> std::result::Result<(), (CheckStatus, String)> {
+    let snapshot = Snapshot::new(path).map_err(|err| (CheckStatus::Unreadable, err.to_string()))?;
+    let result = snapshot.open().and_then(|connection| {
+        connection.query_row("PRAGMA integrity_check", [], |row| row.get::<_, String>(0))
+    });
+    match result {
+        Ok(status) if status == "ok" => Ok(()),
+        Ok(status) => Err((CheckStatus::Invalid, status)),
+        Err(err) => Err((CheckStatus::Invalid, err.to_string())),
+    }
+}
+
+/// Add the size of the files under `root/relative` to `usage`.
+fn measure(root: &Path, relative: &Path, in_cache: bool, usage: &mut DiskUsage) {
+    let Ok(entries) = std::fs::read_dir(root.join(relative)) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = relative.join(entry.file_name());
+        let Ok(metadata) = entry.path().symlink_metadata() else {
+            continue;
+        };
+        let in_cache = in_cache || CACHE_DIRS.iter().any(|dir| path == Path::new(dir));
+        if metadata.is_dir() {
+            measure(root, &path, in_cache, usage);
+        } else if metadata.is_file() {
+            usage.total += metadata.len();
+            if in_cache {
+                usage.cache += metadata.len();
+            }
+        }
+    }
+}
+
+/// Modification time of a file, in seconds since the Unix epoch.
+fn modified(path: &Path) -> Option<u64> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    Some(
+        modified
+            .duration_since(std::time::UNIX_EPOCH)
+            .ok()?
+            .as_secs(),
+    )
+}
+
+/// Where a Linux browser keeps the disk cache of the profile at `path`.
+///
+/// Chromium mirrors its `$XDG_CONFIG_HOME` user data directory under
+/// `$XDG_CACHE_HOME`, and Firefox its `~/.mozilla` profiles under
+/// `$XDG_CACHE_HOME/mozilla`. Flatpak and Snap sandboxes have their own cache
+/// directory.
+fn external_cache_dir(
+    path: &Path,
+    home: &Path,
+    config_home: &Path,
+    cache_home: &Path,
+) -> Option<PathBuf> {
+    // (configuration root, cache root) pairs, sandboxes first
+    let mut roots = Vec::new();
+    let sandbox = |prefix: PathBuf, depth: usize| {
+        let rest = path.strip_prefix(&prefix).ok()?;
+        let parts: Vec<_> = rest.components().take(depth).collect();
+        (parts.len() == depth && parts.iter().all(|c| matches!(c, Component::Normal(_))))
+            .then(|| parts.iter().fold(prefix, |root, part| root.join(part)))
+    };
+    if let Some(root) = sandbox(home.join(".var").join("app"), 1) {
+        roots.push((root.join("config"), root.join("cache")));
+        roots.push((root.join(".mozilla"), root.join("cache").join("mozilla")));
+    }
+    if let Some(root) = sandbox(home.join("snap"), 2) {
+        roots.push((root.join(".config"), root.join(".cache")));
+        roots.push((root.join(".mozilla"), root.join(".cache").join("mozilla")));
+    }
+    roots.push((config_home.to_path_buf(), cache_home.to_path_buf()));
+    roots.push((home.join(".mozilla"), cache_home.join("mozilla")));
+
+    roots.into_iter().find_map(|(config, cache)| {
+        let rest = path.strip_prefix(config).ok()?;
+        (!rest.as_os_str().is_empty()).then(|| cache.join(rest))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn firefox_profile(dir: &Path) -> Profile {
+        Profile::new(
+            "abc.work",
+            "work",
+            dir.to_path_buf(),
+            BrowserFamily::Firefox,
+        )
+    }
+
+    #[test]
+    fn measures_data_and_caches() {
+        let profile = tempfile::tempdir().unwrap();
+        let cache = tempfile::tempdir().unwrap();
+        std::fs::write(
+            profile.path().join("prefs.js"),
+            "// Mozilla User Preferences\n",
+        )
+        .unwrap();
+        std::fs::create_dir_all(profile.path().join("startupCache")).unwrap();
+        std::fs::write(
+            profile.path().join("startupCache/scriptCache.bin"),
+            [0; 100],
+        )
+        .unwrap();
+        std::fs::create_dir_all(cache.path().join("cache2/entries")).unwrap();
+        std::fs::write(cache.path().join("cache2/entries/A1"), [0; 1000]).unwrap();
+
+        let report = inspect_with(&firefox_profile(profile.path()), Some(cache.path()));
+        assert_eq!(report.size.cache, 1100);
+        assert_eq!(report.size.data, 28);
+        assert_eq!(report.size.total, 1128);
+        assert_eq!(report.verdict, Verdict::Healthy, "{:?}", report.problems);
+        assert!(report.last_used.is_some());
+        assert_eq!(report.checks[2].status, CheckStatus::Missing);
+    }
+
+    #[test]
+    fn reports_corrupt_files() {
+        let profile = tempfile::tempdir().unwrap();
+        std::fs::write(
+            profile.path().join("prefs.js"),
+            "/* header\n * more */\nuser_pref(\"a\", 1);\nuser_pref(\"b\", \n",
+        )
+        .unwrap();
+        std::fs::write(profile.path().join("places.sqlite"), "not a database").unwrap();
+
+        let report = inspect_with(&firefox_profile(profile.path()), None);
+        assert_eq!(report.verdict, Verdict::Corrupt);
+        assert_eq!(report.checks[0].status, CheckStatus::Invalid);
+        assert_eq!(
+            report.checks[0].message.as_deref(),
+            Some("unexpected content at line 4")
+        );
+        assert_eq!(report.checks[2].status, CheckStatus::Invalid);
+        assert_eq!(
+            report.problems,
+            ["prefs.js is corrupt", "places.sqlite is corrupt"]
+        );
+
+        std::fs::remove_file(profile.path().join("prefs.js")).unwrap();
+        std::fs::remove_file(profile.path().join("places.sqlite")).unwrap();
+        let report = inspect_with(&firefox_profile(profile.path()), None);
+        assert_eq!(report.verdict, Verdict::Warning);
+        assert_eq!(report.problems, ["prefs.js is missing"]);
+    }
+
+    #[test]
+    fn checks_sqlite_integrity() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("History");
+        rusqlite::Connection::open(&path)
+            .unwrap()
+            .execute_batch("CREATE TABLE urls (url TEXT); INSERT INTO urls VALUES ('x');")
+            .unwrap();
+        assert_eq!(check_sqlite(&path), Ok(()));
+    }
+
+    #[test]
+    fn checks_sqlite_with_hot_journal() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("History");
+        let connection = rusqlite::Connection::open(&path).unwrap();
+        connection
+            .execute_batch("CREATE TABLE urls (url TEXT); INSERT INTO urls VALUES ('x');")
+            .unwrap();
+        // Leave a transaction open, spilling changes into the database
+        connection
+            .execute_batch(
+                "PRAGMA cache_size = 1; BEGIN; \
+                 WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 2000) \
+                 INSERT INTO urls SELECT 'https://x.example/' || i FROM n;",
+            )
+            .unwrap();
+        assert!(dir.path().join("History-journal").is_file());
+
+        assert_eq!(check_sqlite(&path), Ok(()));
+    }
+
+    #[test]
+    fn finds_external_cache_dirs() {
+        let home = Path::new("/home/me");
+        let find = |path: &str| {
+            external_cache_dir(
+                Path::new(path),
+                home,
+                &home.join(".config"),
+                &home.join(".cache"),
+            )
+        };
+        assert_eq!(
+            find("/home/me/.config/google-chrome/Default"),
+            Some("/home/me/.cache/google-chrome/Default".into())
+        );
+        assert_eq!(
+            find("/home/me/.mozilla/firefox/abc.work"),
+            Some("/home/me/.cache/mozilla/firefox/abc.work".into())
+        );
+        assert_eq!(
+            find("/home/me/.var/app/org.chromium.Chromium/config/chromium/Default"),
+            Some("/home/me/.var/app/org.chromium.Chromium/cache/chromium/Default".into())
+        );
+        assert_eq!(
+            find("/home/me/snap/firefox/common/.mozilla/firefox/abc.work"),
+            Some("/home/me/snap/firefox/common/.cache/mozilla/firefox/abc.work".into())
+        );
+        assert_eq!(find("/opt/profiles/work"), None);
+    }
+}
@@ -0,0 +1,23 @@
+//! Reading browser JSON state files.
+
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+
+use crate::{Error, Result};
+
+/// Read a JSON file, treating a missing file as `None`.
+pub(crate) fn read_json<T: DeserializeOwned>(path: &Path) -> Result<Option<T>> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(Error::io(path, err)),
+    };
+    serde_json::from_str(&text)
+        .map(Some)
+        .map_err(|err| Error::Parse {
+            path: path.to_path_buf(),
+            line: Some(err.line()),
+            message: err.to_string(),
+        })
+}
@@ -1,10 +1,232 @@
 //! Browser profile management for the browserware ecosystem.
 //!
-//! # Status
+//! This crate answers "what profiles exist for a browser?" by reading each
+//! browser family's own profile list from its user data directory (located
+//! with [`browserware_detect::user_data_dir`], so Flatpak and Snap installs
+//! are covered).
 //!
-//! This crate is under development. See the repository for progress.
+//! # Example
+//!
+//! ```no_run
+//! if let Some(chrome) = browserware_detect::detect_browser("chrome") {
+//!     for profile in browserware_profiles::list_profiles(&chrome)? {
+//!         println!("{} ({})", profile.name, profile.id);
+//!     }
+//! }
+//! # Ok::<(), browserware_profiles::Error>(())
+//! ```
+//!
+//! Firefox profiles' Multi-Account Containers are listed by
+//! [`list_containers`], and [`container_url`] builds addresses that open in
+//! one. [`export_profile`] and [`restore_profile`] back profiles up to
+//! compressed archives and restore them under a new name. On Linux,
+//! [`watch_profiles`] reports profiles being added, removed, renamed or
+//! updated without rescanning.
+//!
+//! # Browser Support
+//!
+//! | Family   | Source |
+//! |----------|--------|
+//! | Chromium | `Local State` (`profile.info_cache`) |
+//! | Firefox  | `profiles.ini` and `installs.ini`, with per-install defaults |
+//! | WebKit   | GNOME Web profile directories: default, web apps and running `--profile` instances |
 
 #![forbid(unsafe_code)]
 #![warn(missing_docs)]
 
-// TODO: Implement in Milestone 2
+mod backup;
+pub mod chromium;
+mod cityhash;
+pub mod ephemeral;
+mod error;
+pub mod extensions;
+mod find;
+pub mod firefox;
+pub mod history;
+mod identity;
+mod ini;
+pub mod inspect;
+mod json;
+pub mod lock;
+mod manage;
+mod profile_ext;
+#[cfg(target_os = "linux")]
+pub mod watch;
+pub mod webkit;
+mod write;
+
+use std::path::{Path, PathBuf};
+
+use browserware_detect::ProfileArg;
+use browserware_types::{Browser, BrowserFamily, Profile, ProfileSelector};
+
+pub use backup::{
+    BACKUP_FORMAT_VERSION, BackupManifest, MANIFEST_NAME, export_profile, export_profile_in,
+    read_backup_manifest, restore_profile, restore_profile_in,
+};
+pub use ephemeral::{EphemeralProfile, EphemeralProfileBuilder, sweep_ephemeral};
+pub use error::{Error, Result};
+pub use extensions::{Extension, InstallSource};
+pub use find::{ProfileActivity, resolve_selector, select_profile};
+pub use history::HostVisits;
+pub use identity::{ResolvedIdentity, discover_identities, populate_identity, resolve_identity};
+pub use inspect::{CheckStatus, DiskUsage, FileCheck, ProfileReport, Verdict, inspect_profile};
+pub use lock::LockState;
+pub use manage::{
+    create_profile, create_profile_in, delete_profile, delete_profile_in, rename_profile,
+    rename_profile_in,
+};
+pub use profile_ext::ProfileExt;
+#[cfg(target_os = "linux")]
+pub use watch::{ProfileEvent, ProfileWatcher, watch_profiles, watch_profiles_in};
+pub use write::BACKUP_SUFFIX;
+
+/// List the profiles of a browser.
+///
+/// Profiles are returned in the browser's own display order. A browser that
+/// has never been started has no profiles.
+///
+/// # Errors
+///
+/// - [`Error::UnknownDataDir`] if the browser's user data location is unknown
+/// - [`Error::UnsupportedBrowser`] if the browser family's profiles cannot be read yet
+/// - [`Error::Io`] or [`Error::Parse`] if the profile list cannot be read
+pub fn list_profiles(browser: &Browser) -> Result<Vec<Profile>> {
+    let dir = browserware_detect::user_data_dir(browser).ok_or_else(|| Error::UnknownDataDir {
+        browser: browser.id.0.clone(),
+    })?;
+    list_profiles_in(browser, &dir)
+}
+
+/// Find a browser's profile by ID, display name, email, unique prefix or
+/// selector.
+///
+/// See [`select_profile`] for the matching rules and [`resolve_selector`]
+/// for the `@default`, `@last-used` and `@last-active` selectors.
+///
+/// # Example
+///
+/// ```no_run
+/// if let Some(chrome) = browserware_detect::detect_browser("chrome") {
+///     let work = browserware_profiles::find_profile(&chrome, "Work")?;
+///     println!("{}", work.path.display());
+/// }
+/// # Ok::<(), browserware_profiles::Error>(())
+/// ```
+///
+/// # Errors
+///
+/// - [`Error::NotFound`] or [`Error::Ambiguous`] if the query does not select
+///   exactly one profile
+/// - [`Error::InvalidQuery`] if the query is an unknown `@` selector
+/// - any error of [`list_profiles`]
+pub fn find_profile(browser: &Browser, query: &str) -> Result<Profile> {
+    let selector = ProfileSelector::from_query(query).map_err(|_| Error::InvalidQuery {
+        query: query.to_string(),
+        message: "unknown profile selector (expected @default, @last-used or @last-active)"
+            .to_string(),
+    })?;
+    let dir = browserware_detect::user_data_dir(browser).ok_or_else(|| Error::UnknownDataDir {
+        browser: browser.id.0.clone(),
+    })?;
+    let profiles = list_profiles_in(browser, &dir)?;
+    match selector {
+        Some(selector) => {
+            let activity = ProfileActivity::read(browser, &dir)?;
+            resolve_selector(&browser.id.0, &profiles, selector, &activity)
+        }
+        None => select_profile(&browser.id.0, &profiles, query),
+    }
+}
+
+/// List the containers of a Firefox profile.
+///
+/// # Errors
+///
+/// - [`Error::UnsupportedBrowser`] if the profile is not a Firefox profile
+/// - [`Error::Io`] or [`Error::Parse`] if `containers.json` cannot be read
+pub fn list_containers(profile: &Profile) -> Result<Vec<firefox::containers::Container>> {
+    if profile.family != BrowserFamily::Firefox {
+        return Err(Error::UnsupportedBrowser {
+            browser: profile.family.to_string(),
+        });
+    }
+    firefox::containers::read_containers(&profile.path)
+}
+
+/// Address that opens `url` in a container of a Firefox profile.
+///
+/// The container is matched by name (ignoring case) or ID. Opening the
+/// address needs the container add-on in the profile; see
+/// [`firefox::containers`].
+///
+/// # Example
+///
+/// ```no_run
+/// if let Some(firefox) = browserware_detect::detect_browser("firefox") {
+///     let profile = browserware_profiles::find_profile(&firefox, "@default")?;
+///     let url = browserware_profiles::container_url(&profile, "Banking", "https://bank.example")?;
+///     assert!(url.starts_with("ext+container:"));
+/// }
+/// # Ok::<(), browserware_profiles::Error>(())
+/// ```
+///
+/// # Errors
+///
+/// - [`Error::ContainerNotFound`] if no container matches
+/// - [`Error::AddonMissing`] if the container add-on is not installed and enabled
+/// - any error of [`list_containers`]
+pub fn container_url(profile: &Profile, container: &str, url: &str) -> Result<String> {
+    use firefox::containers::{CONTAINER_ADDON_NAME, find_container, has_container_addon};
+
+    let containers = list_containers(profile)?;
+    let container = find_container(&profile.id.0, &containers, container)?;
+    if !has_container_addon(&profile.path)? {
+        return Err(Error::AddonMissing {
+            profile: profile.id.0.clone(),
+            addon: CONTAINER_ADDON_NAME.to_string(),
+            reason: format!("needed to open URLs in container '{}'", container.name),
+        });
+    }
+    Ok(firefox::containers::container_url(&container, url))
+}
+
+/// List the profiles of a browser stored in a specific user data directory.
+///
+/// Useful for browsers started with a custom `--user-data-dir`.
+///
+/// # Errors
+///
+/// See [`list_profiles`].
+pub fn list_profiles_in(browser: &Browser, user_data_dir: &Path) -> Result<Vec<Profile>> {
+    tracing::debug!(
+        browser = %browser.id,
+        dir = %user_data_dir.display(),
+        "Listing profiles"
+    );
+    match browser.family() {
+        BrowserFamily::Chromium => chromium::list_profiles(user_data_dir),
+        BrowserFamily::Firefox => {
+            let hash = firefox::browser_install_hash(browser);
+            firefox::list_profiles(user_data_dir, hash.as_deref())
+        }
+        BrowserFamily::WebKit => {
+            webkit::list_profiles(user_data_dir, &running_profile_dirs(browser))
+        }
+        BrowserFamily::Other => Err(Error::UnsupportedBrowser {
+            browser: browser.id.0.clone(),
+        }),
+    }
+}
+
+/// Profile directories passed with `--profile` to running instances of a browser.
+fn running_profile_dirs(browser: &Browser) -> Vec<PathBuf> {
+    browserware_detect::running_instances(std::slice::from_ref(browser))
+        .into_iter()
+        .filter(|instance| instance.browser.id == browser.id)
+        .filter_map(|instance| match instance.profile {
+            Some(ProfileArg::Path(path)) => Some(path),
+            _ => None,
+        })
+        .collect()
+}
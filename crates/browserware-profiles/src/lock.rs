@@ -0,0 +1,354 @@
+//! Profile lock detection.
+//!
+//! Browsers lock a profile while it is open so a second instance does not
+//! corrupt it:
+//!
+//! - Firefox creates a `lock` symlink in the profile pointing to
+//!   `<ip>:+<pid>`, and holds a POSIX lock on `.parentlock`.
+//! - Chromium creates a `SingletonLock` symlink in the user data directory
+//!   pointing to `<hostname>-<pid>`. It locks every profile of that
+//!   directory at once.
+//!
+//! Locks left behind by a crashed browser are reported as stale when the
+//! owning process is known to be gone.
+
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+
+use browserware_types::{BrowserFamily, Profile};
+use serde::{Deserialize, Serialize};
+
+/// Name of Chromium's lock symlink in the user data directory.
+pub const SINGLETON_LOCK: &str = "SingletonLock";
+
+/// Name of Firefox's lock symlink in the profile directory.
+pub const FIREFOX_LOCK: &str = "lock";
+
+/// Name of the file Firefox holds a POSIX lock on.
+pub const FIREFOX_PARENT_LOCK: &str = ".parentlock";
+
+/// Whether a profile is in use.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(tag = "state", rename_all = "kebab-case")]
+pub enum LockState {
+    /// No browser holds the profile
+    Free,
+    /// A browser process on this machine holds the profile
+    InUseLocally {
+        /// Process ID of the owning browser
+        pid: u32,
+    },
+    /// A browser on another machine holds the profile (shared home directory)
+    InUseOnOtherHost {
+        /// Host name or address recorded in the lock
+        host: String,
+    },
+    /// The profile is locked by a process that no longer exists
+    Stale {
+        /// Process ID recorded in the lock
+        pid: u32,
+    },
+}
+
+impl std::fmt::Display for LockState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Free => f.write_str("free"),
+            Self::InUseLocally { pid } => write!(f, "in use (pid {pid})"),
+            Self::InUseOnOtherHost { host } => write!(f, "in use on {host}"),
+            Self::Stale { pid } => write!(f, "stale (pid {pid})"),
+        }
+    }
+}
+
+impl LockState {
+    /// Returns true if a running browser holds the profile.
+    #[must_use]
+    pub const fn is_in_use(&self) -> bool {
+        matches!(
+            self,
+            Self::InUseLocally { .. } | Self::InUseOnOtherHost { .. }
+        )
+    }
+}
+
+/// Check whether a browser currently holds `profile`.
+///
+/// Also available as [`ProfileExt::lock_state`](crate::ProfileExt::lock_state).
+#[must_use]
+pub fn lock_state(profile: &Profile) -> LockState {
+    lock_state_with(&profile.path, profile.family, &Host::current())
+}
+
+/// Whether a local process exists; `None` when it cannot be told (no procfs).
+pub(crate) fn process_exists(pid: u32) -> Option<bool> {
+    Host::current().is_running(pid)
+}
+
+/// The machine locks are checked against.
+struct Host {
+    hostname: Option<String>,
+    proc_root: PathBuf,
+}
+
+impl Host {
+    fn current() -> Self {
+        let hostname = std::fs::read_to_string("/proc/sys/kernel/hostname")
+            .or_else(|_| std::fs::read_to_string("/etc/hostname"))
+            .ok()
+            .map(|name| name.trim().to_string())
+            .or_else(|| std::env::var("HOSTNAME").ok())
+            .filter(|name| !name.is_empty());
+        Self {
+            hostname,
+            proc_root: PathBuf::from("/proc"),
+        }
+    }
+
+    /// Whether a process exists; `None` when it cannot be told (no procfs).
+    fn is_running(&self, pid: u32) -> Option<bool> {
+        if !self.proc_root.join("self").exists() && !self.proc_root.join("1").exists() {
+            return None;
+        }
+        Some(self.proc_root.join(pid.to_string()).exists())
+    }
+
+    fn is_local_name(&self, host: &str) -> bool {
+        self.hostname.as_deref() == Some(host)
+    }
+
+    fn is_local_address(&self, addr: IpAddr) -> bool {
+        if addr.is_loopback() {
+            return true;
+        }
+        // Firefox records the address its own host name resolves to
+        let Some(hostname) = &self.hostname else {
+            return false;
+        };
+        std::net::ToSocketAddrs::to_socket_addrs(&(hostname.as_str(), 0))
+            .is_ok_and(|mut addrs| addrs.any(|a| a.ip() == addr))
+    }
+
+    /// PID holding a POSIX lock on a file, from `/proc/locks`.
+    #[cfg(unix)]
+    fn lock_holder(&self, path: &Path) -> Option<u32> {
+        use std::os::unix::fs::MetadataExt;
+
+        let meta = std::fs::metadata(path).ok()?;
+        let locks = std::fs::read_to_string(self.proc_root.join("locks")).ok()?;
+        let file = (device_numbers(meta.dev()), meta.ino());
+        // e.g. "1: POSIX  ADVISORY  WRITE 4242 08:02:1318 0 EOF", with the
+        // device in hex; waiting locks have a "->" after the index and are
+        // skipped
+        locks.lines().find_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.get(1) == Some(&"->") {
+                return None;
+            }
+            let pid = fields.get(4)?.parse().ok()?;
+            let mut id = fields.get(5)?.splitn(3, ':');
+            let major = u64::from_str_radix(id.next()?, 16).ok()?;
+            let minor = u64::from_str_radix(id.next()?, 16).ok()?;
+            let ino = id.next()?.parse().ok()?;
+            (((major, minor), ino) == file).then_some(pid)
+        })
+    }
+
+    #[cfg(not(unix))]
+    fn lock_holder(&self, _path: &Path) -> Option<u32> {
+        None
+    }
+}
+
+/// Major and minor number of a Linux device ID, as glibc encodes them.
+#[cfg(unix)]
+const fn device_numbers(dev: u64) -> (u64, u64) {
+    let major = ((dev >> 32) & 0xffff_f000) | ((dev >> 8) & 0x0fff);
+    let minor = ((dev >> 12) & 0xffff_ff00) | (dev & 0x00ff);
+    (major, minor)
+}
+
+fn lock_state_with(profile_dir: &Path, family: BrowserFamily, host: &Host) -> LockState {
+    match family {
+        BrowserFamily::Firefox => firefox_lock_state(profile_dir, host),
+        BrowserFamily::Chromium => {
+            // The lock lives in the user data directory, which is the
+            // profile directory itself for single-profile browsers
+            let own = chromium_lock_state(profile_dir, host);
+            if own != LockState::Free {
+                return own;
+            }
+            profile_dir
+                .parent()
+                .map_or(LockState::Free, |dir| chromium_lock_state(dir, host))
+        }
+        BrowserFamily::WebKit | BrowserFamily::Other => LockState::Free,
+    }
+}
+
+fn firefox_lock_state(profile_dir: &Path, host: &Host) -> LockState {
+    if let Ok(target) = std::fs::read_link(profile_dir.join(FIREFOX_LOCK)) {
+        let target = target.to_string_lossy();
+        let Some((addr, pid)) = target.rsplit_once(":+") else {
+            tracing::debug!(%target, "Unrecognized Firefox lock");
+            return LockState::Free;
+        };
+        let Ok(pid) = pid.parse() else {
+            return LockState::Free;
+        };
+        let is_local = addr
+            .parse()
+            .map_or_else(|_| host.is_local_name(addr), |a| host.is_local_address(a));
+        if !is_local {
+            return LockState::InUseOnOtherHost {
+                host: addr.to_string(),
+            };
+        }
+        return process_state(pid, host);
+    }
+
+    host.lock_holder(&profile_dir.join(FIREFOX_PARENT_LOCK))
+        .map_or(LockState::Free, |pid| LockState::InUseLocally { pid })
+}
+
+fn chromium_lock_state(user_data_dir: &Path, host: &Host) -> LockState {
+    let Ok(target) = std::fs::read_link(user_data_dir.join(SINGLETON_LOCK)) else {
+        return LockState::Free;
+    };
+    let target = target.to_string_lossy();
+    // Host names may contain dashes; the PID follows the last one
+    let Some((hostname, pid)) = target.rsplit_once('-') else {
+        tracing::debug!(%target, "Unrecognized Chromium lock");
+        return LockState::Free;
+    };
+    let Ok(pid) = pid.parse() else {
+        return LockState::Free;
+    };
+    if !host.is_local_name(hostname) {
+        return LockState::InUseOnOtherHost {
+            host: hostname.to_string(),
+        };
+    }
+    process_state(pid, host)
+}
+
+fn process_state(pid: u32, host: &Host) -> LockState {
+    match host.is_running(pid) {
+        Some(false) => LockState::Stale { pid },
+        // Without procfs the process is assumed alive, as browsers do
+        Some(true) | None => LockState::InUseLocally { pid },
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::os::unix::fs::symlink;
+
+    use super::*;
+
+    struct Fixture {
+        dir: tempfile::TempDir,
+        host: Host,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            let dir = tempfile::tempdir().unwrap();
+            let proc_root = dir.path().join("proc");
+            std::fs::create_dir_all(proc_root.join("1")).unwrap();
+            std::fs::create_dir_all(proc_root.join("4242")).unwrap();
+            Self {
+                host: Host {
+                    hostname: Some("my-laptop".into()),
+                    proc_root,
+                },
+                dir,
+            }
+        }
+
+        fn profile(&self, path: &str) -> PathBuf {
+            let path = self.dir.path().join(path);
+            std::fs::create_dir_all(&path).unwrap();
+            path
+        }
+    }
+
+    #[test]
+    fn firefox_lock_symlink() {
+        let fx = Fixture::new();
+        let profile = fx.profile("firefox/abc.default");
+        let state = |fx: &Fixture| lock_state_with(&profile, BrowserFamily::Firefox, &fx.host);
+        assert_eq!(state(&fx), LockState::Free);
+
+        symlink("127.0.1.1:+4242", profile.join(FIREFOX_LOCK)).unwrap();
+        assert_eq!(state(&fx), LockState::InUseLocally { pid: 4242 });
+
+        std::fs::remove_file(profile.join(FIREFOX_LOCK)).unwrap();
+        symlink("127.0.1.1:+999", profile.join(FIREFOX_LOCK)).unwrap();
+        assert_eq!(state(&fx), LockState::Stale { pid: 999 });
+
+        std::fs::remove_file(profile.join(FIREFOX_LOCK)).unwrap();
+        symlink("192.0.2.7:+999", profile.join(FIREFOX_LOCK)).unwrap();
+        assert_eq!(
+            state(&fx),
+            LockState::InUseOnOtherHost {
+                host: "192.0.2.7".into()
+            }
+        );
+    }
+
+    #[test]
+    fn firefox_parent_lock_from_proc_locks() {
+        use std::os::unix::fs::MetadataExt;
+
+        let fx = Fixture::new();
+        let profile = fx.profile("firefox/abc.default");
+        let parent_lock = profile.join(FIREFOX_PARENT_LOCK);
+        std::fs::write(&parent_lock, "").unwrap();
+        let meta = std::fs::metadata(&parent_lock).unwrap();
+        let inode = meta.ino();
+        let (major, minor) = device_numbers(meta.dev());
+        // The same inode number on another file system is another file
+        std::fs::write(
+            fx.host.proc_root.join("locks"),
+            format!(
+                "1: POSIX  ADVISORY  WRITE 1111 {:02x}:{minor:02x}:{inode} 0 EOF\n\
+                 2: POSIX  ADVISORY  WRITE 4242 {major:02x}:{minor:02x}:{inode} 0 EOF\n",
+                major + 1
+            ),
+        )
+        .unwrap();
+
+        assert_eq!(
+            lock_state_with(&profile, BrowserFamily::Firefox, &fx.host),
+            LockState::InUseLocally { pid: 4242 }
+        );
+    }
+
+    #[test]
+    fn chromium_singleton_lock() {
+        let fx = Fixture::new();
+        let profile = fx.profile("chrome/Profile 2");
+        let user_data_dir = profile.parent().unwrap();
+        let state = |fx: &Fixture| lock_state_with(&profile, BrowserFamily::Chromium, &fx.host);
+        assert_eq!(state(&fx), LockState::Free);
+
+        symlink("my-laptop-4242", user_data_dir.join(SINGLETON_LOCK)).unwrap();
+        assert_eq!(state(&fx), LockState::InUseLocally { pid: 4242 });
+        assert!(state(&fx).is_in_use());
+
+        std::fs::remove_file(user_data_dir.join(SINGLETON_LOCK)).unwrap();
+        symlink("my-laptop-31337", user_data_dir.join(SINGLETON_LOCK)).unwrap();
+        assert_eq!(state(&fx), LockState::Stale { pid: 31337 });
+
+        std::fs::remove_file(user_data_dir.join(SINGLETON_LOCK)).unwrap();
+        symlink("build-server-4242", user_data_dir.join(SINGLETON_LOCK)).unwrap();
+        assert_eq!(
+            state(&fx),
+            LockState::InUseOnOtherHost {
+                host: "build-server".into()
+            }
+        );
+    }
+}
@@ -0,0 +1,274 @@
+//! Creating, renaming and deleting profiles.
+//!
+//! Every change refuses to run while the browser is running on the same
+//! user data directory or the profile is locked: browsers keep their profile
+//! list in memory and would overwrite the change when they exit. Changed
+//! files are replaced atomically, and their previous contents are kept next
+//! to them with a [`BACKUP_SUFFIX`](crate::BACKUP_SUFFIX) suffix.
+
+use std::path::Path;
+
+use browserware_types::{Browser, BrowserFamily, Profile};
+
+use crate::{Error, ProfileExt, Result, chromium, firefox, list_profiles_in, select_profile};
+
+/// Create a profile named `name`.
+///
+/// # Example
+///
+/// ```no_run
+/// for browser in browserware_detect::detect_browsers() {
+///     browserware_profiles::create_profile(&browser, "Work")?;
+/// }
+/// # Ok::<(), browserware_profiles::Error>(())
+/// ```
+///
+/// # Errors
+///
+/// - [`Error::InvalidName`] if the name is empty or contains control characters
+/// - [`Error::AlreadyExists`] if a profile already has that name
+/// - [`Error::InUse`] if the browser is running
+/// - [`Error::UnsupportedBrowser`] for browsers other than Chromium and Firefox
+/// - [`Error::Io`], [`Error::Parse`] or [`Error::Database`] if a file cannot
+///   be read or written
+pub fn create_profile(browser: &Browser, name: &str) -> Result<Profile> {
+    create_profile_in(browser, &user_data_dir(browser)?, name)
+}
+
+/// Create a profile in a specific user data directory.
+///
+/// # Errors
+///
+/// See [`create_profile`].
+pub fn create_profile_in(browser: &Browser, user_data_dir: &Path, name: &str) -> Result<Profile> {
+    validate_name(name)?;
+    let family = writable_family(browser)?;
+    let profiles = list_profiles_in(browser, user_data_dir)?;
+    ensure_new_name(browser, &profiles, name, None)?;
+    ensure_not_running(browser, user_data_dir, name)?;
+
+    let profile = match family {
+        BrowserFamily::Chromium => chromium::create_profile(user_data_dir, name)?,
+        _ => firefox::create_profile(user_data_dir, name)?,
+    };
+    tracing::info!(browser = %browser.id, profile = %profile.id, "Created profile");
+    Ok(profile)
+}
+
+/// Rename the profile matching `query` (see [`find_profile`](crate::find_profile)).
+///
+/// Returns the renamed profile.
+///
+/// # Errors
+///
+/// See [`create_profile`]; also [`Error::NotFound`] or [`Error::Ambiguous`]
+/// if the query does not select exactly one profile.
+pub fn rename_profile(browser: &Browser, query: &str, new_name: &str) -> Result<Profile> {
+    rename_profile_in(browser, &user_data_dir(browser)?, query, new_name)
+}
+
+/// Rename a profile in a specific user data directory.
+///
+/// # Errors
+///
+/// See [`rename_profile`].
+pub fn rename_profile_in(
+    browser: &Browser,
+    user_data_dir: &Path,
+    query: &str,
+    new_name: &str,
+) -> Result<Profile> {
+    validate_name(new_name)?;
+    let family = writable_family(browser)?;
+    let profiles = list_profiles_in(browser, user_data_dir)?;
+    let mut profile = select_profile(&browser.id.0, &profiles, query)?;
+    ensure_new_name(browser, &profiles, new_name, Some(&profile))?;
+    ensure_unused(browser, user_data_dir, &profile)?;
+
+    match family {
+        BrowserFamily::Chromium => {
+            chromium::rename_profile(user_data_dir, &profile.id.0, new_name)?;
+        }
+        _ => firefox::rename_profile(user_data_dir, &profile.id.0, new_name)?,
+    }
+    tracing::info!(browser = %browser.id, profile = %profile.id, new_name, "Renamed profile");
+    profile.name = new_name.to_string();
+    Ok(profile)
+}
+
+/// Delete the profile matching `query` (see [`find_profile`](crate::find_profile)).
+///
+/// The profile's directory is deleted too, unless `keep_files` is set or it
+/// lies outside the user data directory. Returns the deleted profile.
+///
+/// # Errors
+///
+/// See [`rename_profile`].
+pub fn delete_profile(browser: &Browser, query: &str, keep_files: bool) -> Result<Profile> {
+    delete_profile_in(browser, &user_data_dir(browser)?, query, keep_files)
+}
+
+/// Delete a profile in a specific user data directory.
+///
+/// # Errors
+///
+/// See [`rename_profile`].
+pub fn delete_profile_in(
+    browser: &Browser,
+    user_data_dir: &Path,
+    query: &str,
+    keep_files: bool,
+) -> Result<Profile> {
+    let family = writable_family(browser)?;
+    let profiles = list_profiles_in(browser, user_data_dir)?;
+    let profile = select_profile(&browser.id.0, &profiles, query)?;
+    ensure_unused(browser, user_data_dir, &profile)?;
+
+    match family {
+        BrowserFamily::Chromium => {
+            chromium::delete_profile(user_data_dir, &profile.id.0, keep_files)?;
+        }
+        _ => firefox::delete_profile(user_data_dir, &profile.id.0, keep_files)?,
+    }
+    tracing::info!(browser = %browser.id, profile = %profile.id, "Deleted profile");
+    Ok(profile)
+}
+
+/// The user data directory of `browser`, which profiles are managed in.
+pub(crate) fn user_data_dir(browser: &Browser) -> Result<std::path::PathBuf> {
+    browserware_detect::user_data_dir(browser).ok_or_else(|| Error::UnknownDataDir {
+        browser: browser.id.0.clone(),
+    })
+}
+
+fn writable_family(browser: &Browser) -> Result<BrowserFamily> {
+    match browser.family() {
+        family @ (BrowserFamily::Chromium | BrowserFamily::Firefox) => Ok(family),
+        BrowserFamily::WebKit | BrowserFamily::Other => Err(Error::UnsupportedBrowser {
+            browser: browser.id.0.clone(),
+        }),
+    }
+}
+
+fn validate_name(name: &str) -> Result<()> {
+    let message = if name.trim().is_empty() {
+        "profile name is empty"
+    } else if name.chars().any(char::is_control) {
+        "profile name contains control characters"
+    } else {
+        return Ok(());
+    };
+    Err(Error::InvalidName {
+        name: name.to_string(),
+        message: message.to_string(),
+    })
+}
+
+/// Refuse names already used by a profile other than `renamed`.
+fn ensure_new_name(
+    browser: &Browser,
+    profiles: &[Profile],
+    name: &str,
+    renamed: Option<&Profile>,
+) -> Result<()> {
+    let taken = profiles.iter().any(|p| {
+        p.name.to_lowercase() == name.to_lowercase() && renamed.is_none_or(|r| r.id != p.id)
+    });
+    if taken {
+        return Err(Error::AlreadyExists {
+            browser: browser.id.0.clone(),
+            profile: name.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Refuse to change a profile that is locked or whose browser is running.
+pub(crate) fn ensure_unused(
+    browser: &Browser,
+    user_data_dir: &Path,
+    profile: &Profile,
+) -> Result<()> {
+    let state = profile.lock_state();
+    if state.is_in_use() {
+        return Err(Error::InUse {
+            browser: browser.id.0.clone(),
+            profile: profile.id.0.clone(),
+            reason: format!("profile is {state}"),
+        });
+    }
+    ensure_not_running(browser, user_data_dir, &profile.id.0)
+}
+
+/// Refuse to change a user data directory a running browser uses.
+fn ensure_not_running(browser: &Browser, user_data_dir: &Path, profile: &str) -> Result<()> {
+    for instance in browserware_detect::running_instances(std::slice::from_ref(browser)) {
+        let dir = instance
+            .user_data_dir
+            .clone()
+            .or_else(|| browserware_detect::user_data_dir(&instance.browser));
+        if dir.as_deref() == Some(user_data_dir) {
+            return Err(Error::InUse {
+                browser: browser.id.0.clone(),
+                profile: profile.to_string(),
+                reason: format!(
+                    "{} is running (pid {})",
+                    instance.browser.name, instance.pid
+                ),
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chrome() -> Browser {
+        Browser::new("chrome", "Google Chrome", "/usr/bin/google-chrome".into())
+            .with_variant(browserware_types::BrowserVariant::chromium_stable())
+    }
+
+    #[test]
+    fn refuses_duplicate_and_invalid_names() {
+        let dir = tempfile::tempdir().unwrap();
+        create_profile_in(&chrome(), dir.path(), "Work").unwrap();
+
+        let err = create_profile_in(&chrome(), dir.path(), "work").unwrap_err();
+        assert!(matches!(err, Error::AlreadyExists { .. }), "{err}");
+        let err = create_profile_in(&chrome(), dir.path(), " ").unwrap_err();
+        assert!(matches!(err, Error::InvalidName { .. }), "{err}");
+
+        // Renaming to the profile's own name (in another case) is allowed
+        let renamed = rename_profile_in(&chrome(), dir.path(), "Work", "WORK").unwrap();
+        assert_eq!(renamed.name, "WORK");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn refuses_locked_profiles() {
+        let dir = tempfile::tempdir().unwrap();
+        create_profile_in(&chrome(), dir.path(), "Work").unwrap();
+        let hostname = std::fs::read_to_string("/proc/sys/kernel/hostname").unwrap();
+        std::os::unix::fs::symlink(
+            format!("{}-{}", hostname.trim(), std::process::id()),
+            dir.path().join(crate::lock::SINGLETON_LOCK),
+        )
+        .unwrap();
+
+        let err = delete_profile_in(&chrome(), dir.path(), "Work", false).unwrap_err();
+        assert!(matches!(err, Error::InUse { .. }), "{err}");
+        assert!(dir.path().join("Default").exists());
+    }
+
+    #[test]
+    fn webkit_profiles_are_read_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let web = Browser::new("gnome-web", "GNOME Web", "/usr/bin/epiphany".into()).with_variant(
+            browserware_types::BrowserVariant::Single(BrowserFamily::WebKit),
+        );
+        let err = create_profile_in(&web, dir.path(), "Work").unwrap_err();
+        assert!(matches!(err, Error::UnsupportedBrowser { .. }), "{err}");
+    }
+}
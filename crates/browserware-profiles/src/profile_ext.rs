@@ -0,0 +1,55 @@
+//! Profile operations as methods of [`Profile`].
+
+use browserware_types::Profile;
+
+use crate::Result;
+use crate::extensions::Extension;
+use crate::history::HostVisits;
+use crate::inspect::ProfileReport;
+use crate::lock::LockState;
+
+/// Profile operations that inspect the profile on disk.
+pub trait ProfileExt {
+    /// Check whether a browser currently holds the profile.
+    ///
+    /// Launch code can use this to open URLs in the running instance instead
+    /// of starting a second one, which Firefox refuses with an error dialog.
+    fn lock_state(&self) -> LockState;
+
+    /// List the extensions installed in the profile, ordered by ID.
+    ///
+    /// # Errors
+    ///
+    /// See [`list_extensions`](crate::extensions::list_extensions).
+    fn extensions(&self) -> Result<Vec<Extension>>;
+
+    /// Count the visits to each host in the profile's history, most visited first.
+    ///
+    /// # Errors
+    ///
+    /// See [`host_visits`](crate::history::host_visits).
+    fn host_visits(&self) -> Result<Vec<HostVisits>>;
+
+    /// Measure the profile on disk and check its key files.
+    ///
+    /// See [`inspect_profile`](crate::inspect::inspect_profile).
+    fn inspect(&self) -> ProfileReport;
+}
+
+impl ProfileExt for Profile {
+    fn lock_state(&self) -> LockState {
+        crate::lock::lock_state(self)
+    }
+
+    fn extensions(&self) -> Result<Vec<Extension>> {
+        crate::extensions::list_extensions(self)
+    }
+
+    fn host_visits(&self) -> Result<Vec<HostVisits>> {
+        crate::history::host_visits(self)
+    }
+
+    fn inspect(&self) -> ProfileReport {
+        crate::inspect::inspect_profile(self)
+    }
+}
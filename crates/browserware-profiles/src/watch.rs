@@ -0,0 +1,465 @@
+//! Watching a browser's profiles for changes (Linux).
+//!
+//! A [`ProfileWatcher`] uses inotify to watch the files profiles are
+//! enumerated from, and the per-profile files that hold a profile's
+//! settings, containers and extensions:
+//!
+//! | Family    | User data directory | Profile directory |
+//! |-----------|---------------------|-------------------|
+//! | Chromium  | `Local State` | `Preferences`, `Secure Preferences` |
+//! | Firefox   | `profiles.ini`, `installs.ini`, `Profile Groups` | `containers.json`, `extensions.json` |
+//! | GNOME Web | profile directories | - |
+//!
+//! Browsers write these files several times in a row, so changes are
+//! debounced: once a change is seen, the watcher waits until the files have
+//! been quiet for the debounce interval, then lists the profiles again with
+//! [`list_profiles_in`] and reports the differences.
+
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsStr;
+use std::os::fd::AsFd;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use browserware_types::{Browser, BrowserFamily, Profile};
+use nix::errno::Errno;
+use nix::poll::{PollFd, PollFlags, PollTimeout, poll};
+use nix::sys::inotify::{AddWatchFlags, InitFlags, Inotify, WatchDescriptor};
+use serde::{Deserialize, Serialize};
+
+use crate::firefox::groups::PROFILE_GROUPS_DIR;
+use crate::{Error, Result, list_profiles_in};
+
+/// Default time the watched files must be quiet before changes are reported.
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// A change to a browser's profiles.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum ProfileEvent {
+    /// A profile was created
+    Added {
+        /// The new profile
+        profile: Profile,
+    },
+    /// A profile was deleted
+    Removed {
+        /// The profile before it was deleted
+        profile: Profile,
+    },
+    /// A profile's display name changed
+    Renamed {
+        /// The profile with its new name
+        profile: Profile,
+        /// The previous display name
+        old_name: String,
+    },
+    /// A profile's metadata, settings, containers or extensions changed
+    Updated {
+        /// The profile after the change
+        profile: Profile,
+    },
+}
+
+impl ProfileEvent {
+    /// The profile the event is about.
+    #[must_use]
+    pub const fn profile(&self) -> &Profile {
+        match self {
+            Self::Added { profile }
+            | Self::Removed { profile }
+            | Self::Renamed { profile, .. }
+            | Self::Updated { profile } => profile,
+        }
+    }
+}
+
+/// Watches a browser's profiles; see the [module documentation](self).
+#[derive(Debug)]
+pub struct ProfileWatcher {
+    browser: Browser,
+    dir: PathBuf,
+    inotify: Inotify,
+    watches: HashMap<WatchDescriptor, PathBuf>,
+    profiles: Vec<Profile>,
+    debounce: Duration,
+}
+
+/// Start watching a browser's profiles.
+///
+/// # Example
+///
+/// ```no_run
+/// if let Some(firefox) = browserware_detect::detect_browser("firefox") {
+///     let mut watcher = browserware_profiles::watch_profiles(&firefox)?;
+///     loop {
+///         for event in watcher.next_events()? {
+///             println!("{event:?}");
+///         }
+///     }
+/// }
+/// # Ok::<(), browserware_profiles::Error>(())
+/// ```
+///
+/// # Errors
+///
+/// - [`Error::UnknownDataDir`] if the browser's user data location is unknown
+/// - [`Error::Io`] if inotify cannot be set up
+/// - any error of [`list_profiles`](crate::list_profiles)
+pub fn watch_profiles(browser: &Browser) -> Result<ProfileWatcher> {
+    let dir = browserware_detect::user_data_dir(browser).ok_or_else(|| Error::UnknownDataDir {
+        browser: browser.id.0.clone(),
+    })?;
+    watch_profiles_in(browser, &dir)
+}
+
+/// Start watching the profiles in a specific user data directory.
+///
+/// The directory does not have to exist yet; profiles are reported as
+/// added once the browser creates it.
+///
+/// # Errors
+///
+/// See [`watch_profiles`].
+pub fn watch_profiles_in(browser: &Browser, user_data_dir: &Path) -> Result<ProfileWatcher> {
+    let inotify = Inotify::init(InitFlags::IN_CLOEXEC | InitFlags::IN_NONBLOCK)
+        .map_err(|errno| Error::io(user_data_dir, errno.into()))?;
+    let mut watcher = ProfileWatcher {
+        browser: browser.clone(),
+        dir: user_data_dir.to_path_buf(),
+        inotify,
+        watches: HashMap::new(),
+        profiles: list_profiles_in(browser, user_data_dir)?,
+        debounce: DEFAULT_DEBOUNCE,
+    };
+    watcher.update_watches();
+    tracing::debug!(
+        browser = %browser.id,
+        dir = %user_data_dir.display(),
+        watches = watcher.watches.len(),
+        "Watching profiles"
+    );
+    Ok(watcher)
+}
+
+impl ProfileWatcher {
+    /// Set how long the watched files must be quiet before changes are
+    /// reported (default [`DEFAULT_DEBOUNCE`]).
+    #[must_use]
+    pub const fn with_debounce(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+
+    /// The profiles as of the last reported change.
+    #[must_use]
+    pub fn profiles(&self) -> &[Profile] {
+        &self.profiles
+    }
+
+    /// Block until the profiles change, and return the changes.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] if inotify fails, and any error of
+    /// [`list_profiles`](crate::list_profiles) when listing the changed
+    /// profiles. The watcher can be used again after an error.
+    pub fn next_events(&mut self) -> Result<Vec<ProfileEvent>> {
+        loop {
+            let events = self.wait(None)?;
+            if !events.is_empty() {
+                return Ok(events);
+            }
+        }
+    }
+
+    /// Wait up to `timeout` for the profiles to change, and return the
+    /// changes (none if the timeout passed).
+    ///
+    /// Once a change is seen, the debounce interval may extend the wait
+    /// beyond `timeout`.
+    ///
+    /// # Errors
+    ///
+    /// See [`next_events`](Self::next_events).
+    pub fn poll_events(&mut self, timeout: Duration) -> Result<Vec<ProfileEvent>> {
+        self.wait(Some(Instant::now() + timeout))
+    }
+
+    fn wait(&mut self, deadline: Option<Instant>) -> Result<Vec<ProfileEvent>> {
+        let mut changed = false;
+        let mut changed_dirs = HashSet::new();
+        loop {
+            let timeout = if changed {
+                Some(self.debounce)
+            } else {
+                deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()))
+            };
+            if !self.readable(timeout)? {
+                if changed {
+                    break;
+                }
+                return Ok(Vec::new());
+            }
+            changed |= self.read_changes(&mut changed_dirs)?;
+        }
+
+        let profiles = list_profiles_in(&self.browser, &self.dir)?;
+        let events = diff_profiles(&self.profiles, &profiles, &changed_dirs);
+        self.profiles = profiles;
+        self.update_watches();
+        tracing::debug!(browser = %self.browser.id, events = events.len(), "Profiles changed");
+        Ok(events)
+    }
+
+    /// Wait for inotify events; `None` waits forever.
+    fn readable(&self, timeout: Option<Duration>) -> Result<bool> {
+        let timeout = timeout.map_or(PollTimeout::NONE, |timeout| {
+            PollTimeout::try_from(timeout).unwrap_or(PollTimeout::MAX)
+        });
+        let mut fds = [PollFd::new(self.inotify.as_fd(), PollFlags::POLLIN)];
+        match poll(&mut fds, timeout) {
+            Ok(ready) => Ok(ready > 0),
+            Err(Errno::EINTR) => Ok(false),
+            Err(errno) => Err(Error::io(&self.dir, errno.into())),
+        }
+    }
+
+    /// Read pending events, noting the profile directories they concern.
+    ///
+    /// Returns whether any event concerns a watched file.
+    fn read_changes(&mut self, changed_dirs: &mut HashSet<PathBuf>) -> Result<bool> {
+        let events = match self.inotify.read_events() {
+            Ok(events) => events,
+            Err(Errno::EAGAIN) => return Ok(false),
+            Err(errno) => return Err(Error::io(&self.dir, errno.into())),
+        };
+
+        let mut changed = false;
+        for event in events {
+            if event.mask.contains(AddWatchFlags::IN_Q_OVERFLOW) {
+                tracing::debug!("inotify queue overflowed");
+                changed = true;
+                continue;
+            }
+            let Some(dir) = self.watches.get(&event.wd).cloned() else {
+                continue;
+            };
+            if event.mask.contains(AddWatchFlags::IN_IGNORED) {
+                self.watches.remove(&event.wd);
+            }
+            if self.is_relevant(&dir, event.name.as_deref()) {
+                changed = true;
+                if dir != self.dir {
+                    changed_dirs.insert(dir);
+                }
+            }
+        }
+        Ok(changed)
+    }
+
+    /// Whether an event on `name` in the watched directory `dir` can change
+    /// the profiles.
+    fn is_relevant(&self, dir: &Path, name: Option<&OsStr>) -> bool {
+        let family = self.browser.family();
+        if dir == self.dir {
+            return match (family, name) {
+                (BrowserFamily::WebKit, _) | (_, None) => true,
+                (_, Some(name)) => root_files(family).iter().any(|file| name == *file),
+            };
+        }
+        if !dir.starts_with(&self.dir) || dir.ends_with(PROFILE_GROUPS_DIR) {
+            // An ancestor of a missing user data directory, or profile groups
+            return true;
+        }
+        name.is_some_and(|name| profile_files(family).iter().any(|file| name == *file))
+    }
+
+    /// Watch the user data directory (or its nearest existing ancestor) and
+    /// the current profiles' directories.
+    fn update_watches(&mut self) {
+        let mut wanted = Vec::new();
+        let root = self.dir.ancestors().find(|dir| dir.is_dir());
+        wanted.extend(root.map(Path::to_path_buf));
+        if root == Some(self.dir.as_path()) {
+            let family = self.browser.family();
+            if family == BrowserFamily::Firefox {
+                wanted.push(self.dir.join(PROFILE_GROUPS_DIR));
+            }
+            if !profile_files(family).is_empty() {
+                wanted.extend(self.profiles.iter().map(|p| p.path.clone()));
+            }
+        }
+
+        let stale: Vec<WatchDescriptor> = self
+            .watches
+            .iter()
+            .filter(|(_, dir)| !wanted.contains(dir))
+            .map(|(wd, _)| *wd)
+            .collect();
+        for wd in stale {
+            self.watches.remove(&wd);
+            // Fails if the directory is already gone, which removed the watch
+            let _ = self.inotify.rm_watch(wd);
+        }
+
+        let mask = AddWatchFlags::IN_CLOSE_WRITE
+            | AddWatchFlags::IN_CREATE
+            | AddWatchFlags::IN_DELETE
+            | AddWatchFlags::IN_MOVED_FROM
+            | AddWatchFlags::IN_MOVED_TO
+            | AddWatchFlags::IN_DELETE_SELF
+            | AddWatchFlags::IN_MOVE_SELF
+            | AddWatchFlags::IN_ONLYDIR;
+        for dir in wanted {
+            if self.watches.values().any(|watched| *watched == dir) || !dir.is_dir() {
+                continue;
+            }
+            match self.inotify.add_watch(&dir, mask) {
+                Ok(wd) => {
+                    self.watches.insert(wd, dir);
+                }
+                Err(errno) => {
+                    tracing::debug!(dir = %dir.display(), "Cannot watch directory: {errno}");
+                }
+            }
+        }
+    }
+}
+
+/// Files in the user data directory that profiles are listed from.
+const fn root_files(family: BrowserFamily) -> &'static [&'static str] {
+    match family {
+        BrowserFamily::Chromium => &[crate::chromium::LOCAL_STATE],
+        BrowserFamily::Firefox => &[
+            crate::firefox::PROFILES_INI,
+            crate::firefox::INSTALLS_INI,
+            PROFILE_GROUPS_DIR,
+        ],
+        BrowserFamily::WebKit | BrowserFamily::Other => &[],
+    }
+}
+
+/// Files in a profile directory that hold its settings, containers and extensions.
+const fn profile_files(family: BrowserFamily) -> &'static [&'static str] {
+    match family {
+        BrowserFamily::Chromium => &["Preferences", "Secure Preferences"],
+        BrowserFamily::Firefox => &[
+            crate::firefox::containers::CONTAINERS_JSON,
+            crate::extensions::FIREFOX_EXTENSIONS_JSON,
+        ],
+        BrowserFamily::WebKit | BrowserFamily::Other => &[],
+    }
+}
+
+/// Events turning `old` into `new`; profiles whose directory is in
+/// `changed_dirs` are updated even if their metadata is unchanged.
+fn diff_profiles(
+    old: &[Profile],
+    new: &[Profile],
+    changed_dirs: &HashSet<PathBuf>,
+) -> Vec<ProfileEvent> {
+    let mut events: Vec<ProfileEvent> = old
+        .iter()
+        .filter(|profile| !new.iter().any(|p| p.id == profile.id))
+        .map(|profile| ProfileEvent::Removed {
+            profile: profile.clone(),
+        })
+        .collect();
+    for profile in new {
+        let profile = profile.clone();
+        match old.iter().find(|p| p.id == profile.id) {
+            None => events.push(ProfileEvent::Added { profile }),
+            Some(before) if before.name != profile.name => events.push(ProfileEvent::Renamed {
+                old_name: before.name.clone(),
+                profile,
+            }),
+            Some(before) if *before != profile || changed_dirs.contains(&profile.path) => {
+                events.push(ProfileEvent::Updated { profile });
+            }
+            Some(_) => {}
+        }
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use browserware_types::BrowserVariant;
+
+    fn firefox() -> Browser {
+        Browser::new("firefox", "Firefox", "/usr/bin/firefox".into())
+            .with_variant(BrowserVariant::firefox_stable())
+    }
+
+    fn names(events: &[ProfileEvent]) -> Vec<String> {
+        events
+            .iter()
+            .map(|event| {
+                let kind = match event {
+                    ProfileEvent::Added { .. } => "added",
+                    ProfileEvent::Removed { .. } => "removed",
+                    ProfileEvent::Renamed { .. } => "renamed",
+                    ProfileEvent::Updated { .. } => "updated",
+                };
+                format!("{kind} {}", event.profile().name)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn diffs_profile_lists() {
+        let profile = |id: &str, name: &str| {
+            Profile::new(id, name, format!("/p/{id}").into(), BrowserFamily::Firefox)
+        };
+        let old = [profile("a", "A"), profile("b", "B"), profile("c", "C")];
+        let new = [
+            profile("a", "A"),
+            profile("b", "Bee"),
+            profile("c", "C").with_default(true),
+            profile("d", "D"),
+        ];
+        let events = diff_profiles(&old, &new, &HashSet::new());
+        assert_eq!(names(&events), ["renamed Bee", "updated C", "added D"]);
+
+        let changed = HashSet::from([PathBuf::from("/p/a")]);
+        let events = diff_profiles(&old, &old[..2], &changed);
+        assert_eq!(names(&events), ["removed C", "updated A"]);
+    }
+
+    #[test]
+    fn reports_changes_to_firefox_profiles() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("firefox");
+        let mut watcher = watch_profiles_in(&firefox(), &root)
+            .unwrap()
+            .with_debounce(Duration::from_millis(50));
+        let mut next = || watcher.poll_events(Duration::from_secs(5)).unwrap();
+
+        // The user data directory does not exist yet
+        let profile = crate::create_profile_in(&firefox(), &root, "Work").unwrap();
+        assert_eq!(names(&next()), ["added Work"]);
+
+        crate::rename_profile_in(&firefox(), &root, "Work", "Office").unwrap();
+        assert_eq!(names(&next()), ["renamed Office"]);
+
+        std::fs::write(
+            profile.path.join("containers.json"),
+            r#"{"identities": []}"#,
+        )
+        .unwrap();
+        assert_eq!(names(&next()), ["updated Office"]);
+
+        crate::delete_profile_in(&firefox(), &root, "Office", false).unwrap();
+        assert_eq!(names(&next()), ["removed Office"]);
+
+        assert!(
+            watcher
+                .poll_events(Duration::from_millis(100))
+                .unwrap()
+                .is_empty()
+        );
+        assert!(watcher.profiles().is_empty());
+    }
+}
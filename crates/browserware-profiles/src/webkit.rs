@@ -0,0 +1,295 @@
+//! WebKit-family profiles.
+//!
+//! GNOME Web (Epiphany) has no profile list. Each profile is a directory, and
+//! the browser uses whichever one `--profile=<dir>` points to:
+//!
+//! - the default profile is the user data directory itself
+//!   (`~/.local/share/epiphany`, or `~/.var/app/org.gnome.Epiphany/data/epiphany`
+//!   for the Flatpak),
+//! - each installed web app has its own profile next to it, named
+//!   `org.gnome.Epiphany.WebApp_<id>`, holding the app's `.desktop` file
+//!   (older releases used `app-epiphany-<name>-<hash>` inside the user data
+//!   directory),
+//! - any other directory can be used with `--profile`. These are only known
+//!   while a browser runs with them.
+
+use std::path::{Path, PathBuf};
+
+use browserware_types::{BrowserFamily, Profile, ProfileId};
+use serde::{Deserialize, Serialize};
+
+use crate::ini::Ini;
+use crate::{Error, Result};
+
+/// ID of the profile stored in the user data directory.
+pub const DEFAULT_PROFILE_ID: &str = "default";
+
+/// Prefix of web app profile directories.
+pub const WEB_APP_PREFIX: &str = "org.gnome.Epiphany.WebApp_";
+
+/// Prefix of web app profile directories before GNOME Web 3.38.
+const LEGACY_WEB_APP_PREFIX: &str = "app-epiphany-";
+
+/// A GNOME Web profile and the web app it belongs to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebKitProfile {
+    /// Browser-independent profile information
+    #[serde(flatten)]
+    pub profile: Profile,
+    /// Web app using this profile (if any)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub web_app: Option<WebApp>,
+}
+
+/// A web app installed from GNOME Web.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebApp {
+    /// Web app ID, the part of the directory name after the prefix
+    pub id: String,
+    /// Display name from the `.desktop` file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Address the app opens
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// The app's `.desktop` file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub desktop_file: Option<PathBuf>,
+}
+
+/// Read the profiles of a GNOME Web user data directory.
+///
+/// Returns the default profile, then web app profiles ordered by directory
+/// name, then any `extra` profile directories (e.g. from `--profile` options
+/// of running instances) not already listed. A missing user data directory
+/// (the browser has never run) contributes no default profile.
+///
+/// # Errors
+///
+/// Returns [`Error::Io`] if a directory exists but cannot be listed.
+pub fn read_profiles(user_data_dir: &Path, extra: &[PathBuf]) -> Result<Vec<WebKitProfile>> {
+    let mut profiles = Vec::new();
+    if user_data_dir.is_dir() {
+        profiles.push(WebKitProfile {
+            profile: Profile::new(
+                DEFAULT_PROFILE_ID,
+                "Default",
+                user_data_dir.to_path_buf(),
+                BrowserFamily::WebKit,
+            )
+            .with_default(true),
+            web_app: None,
+        });
+    }
+
+    let mut web_apps = Vec::new();
+    if let Some(parent) = user_data_dir.parent() {
+        web_apps.extend(web_app_dirs(parent, WEB_APP_PREFIX)?);
+    }
+    web_apps.extend(web_app_dirs(user_data_dir, LEGACY_WEB_APP_PREFIX)?);
+    web_apps.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
+    profiles.extend(web_apps.iter().map(|dir| web_app_profile(dir)));
+
+    for dir in extra {
+        if profiles.iter().any(|p| p.profile.path == *dir) {
+            continue;
+        }
+        let profile = if is_web_app_dir(dir) {
+            web_app_profile(dir)
+        } else {
+            let id = ProfileId::from_dir(dir).map_or_else(|| dir.display().to_string(), |id| id.0);
+            WebKitProfile {
+                profile: Profile::new(id.clone(), id, dir.clone(), BrowserFamily::WebKit),
+                web_app: None,
+            }
+        };
+        profiles.push(profile);
+    }
+    Ok(profiles)
+}
+
+/// List the profiles of a GNOME Web user data directory.
+///
+/// # Errors
+///
+/// See [`read_profiles`].
+pub fn list_profiles(user_data_dir: &Path, extra: &[PathBuf]) -> Result<Vec<Profile>> {
+    let profiles = read_profiles(user_data_dir, extra)?;
+    Ok(profiles.into_iter().map(|p| p.profile).collect())
+}
+
+/// Directories in `dir` whose name starts with `prefix`.
+fn web_app_dirs(dir: &Path, prefix: &str) -> Result<Vec<PathBuf>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(Error::io(dir, err)),
+    };
+    Ok(entries
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with(prefix))
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect())
+}
+
+fn is_web_app_dir(dir: &Path) -> bool {
+    dir.file_name()
+        .map(|name| name.to_string_lossy())
+        .is_some_and(|name| {
+            name.starts_with(WEB_APP_PREFIX) || name.starts_with(LEGACY_WEB_APP_PREFIX)
+        })
+}
+
+/// Describe a web app profile from its directory and `.desktop` file.
+fn web_app_profile(dir: &Path) -> WebKitProfile {
+    let dir_name = dir
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let app_id = dir_name
+        .strip_prefix(WEB_APP_PREFIX)
+        .or_else(|| dir_name.strip_prefix(LEGACY_WEB_APP_PREFIX))
+        .unwrap_or(&dir_name)
+        .to_string();
+
+    let mut web_app = WebApp {
+        id: app_id,
+        name: None,
+        url: None,
+        desktop_file: None,
+    };
+    if let Some(desktop_file) = find_desktop_file(dir) {
+        match std::fs::read_to_string(&desktop_file)
+            .map_err(|err| err.to_string())
+            .and_then(|text| Ini::parse(&text).map_err(|err| err.message))
+        {
+            Ok(ini) => {
+                if let Some(entry) = ini.sections.iter().find(|s| s.name == "Desktop Entry") {
+                    web_app.name = entry.get("Name").map(str::to_string);
+                    web_app.url = entry.get("Exec").and_then(exec_url);
+                }
+            }
+            Err(err) => tracing::debug!(
+                path = %desktop_file.display(),
+                "Cannot read web app desktop file: {err}"
+            ),
+        }
+        web_app.desktop_file = Some(desktop_file);
+    }
+
+    let name = web_app.name.clone().unwrap_or_else(|| dir_name.clone());
+    WebKitProfile {
+        profile: Profile::new(dir_name, name, dir.to_path_buf(), BrowserFamily::WebKit),
+        web_app: Some(web_app),
+    }
+}
+
+/// Find the `.desktop` file GNOME Web stores in a web app profile.
+fn find_desktop_file(dir: &Path) -> Option<PathBuf> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "desktop"))
+        .collect();
+    files.sort();
+    files.into_iter().next()
+}
+
+/// Extract the URL from a web app's `Exec=` line, which ends with it.
+fn exec_url(exec: &str) -> Option<String> {
+    exec.split_whitespace()
+        .last()
+        .map(|arg| arg.trim_matches(|c| c == '"' || c == '\''))
+        .filter(|arg| arg.contains("://"))
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn web_app(data: &Path, id: &str, name: &str, url: &str) -> PathBuf {
+        let dir = data.join(format!("{WEB_APP_PREFIX}{id}"));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join(format!("{WEB_APP_PREFIX}{id}.desktop")),
+            format!(
+                "[Desktop Entry]\nName={name}\nExec=epiphany --application-mode \
+                 \"--profile={}\" {url}\nStartupWMClass={WEB_APP_PREFIX}{id}\n",
+                dir.display()
+            ),
+        )
+        .unwrap();
+        dir
+    }
+
+    #[test]
+    fn lists_default_and_web_app_profiles() {
+        let data = tempfile::tempdir().unwrap();
+        let epiphany = data.path().join("epiphany");
+        std::fs::create_dir(&epiphany).unwrap();
+        web_app(data.path(), "f00d", "Mail", "https://mail.example.com/");
+        web_app(data.path(), "beef", "Chat", "https://chat.example.com/");
+        std::fs::create_dir(data.path().join("unrelated")).unwrap();
+
+        let profiles = read_profiles(&epiphany, &[]).unwrap();
+        let ids: Vec<_> = profiles.iter().map(|p| p.profile.id.0.as_str()).collect();
+        assert_eq!(
+            ids,
+            [
+                "default",
+                "org.gnome.Epiphany.WebApp_beef",
+                "org.gnome.Epiphany.WebApp_f00d"
+            ]
+        );
+        assert!(profiles[0].profile.is_default);
+        assert_eq!(profiles[0].profile.path, epiphany);
+
+        let chat = &profiles[1];
+        assert_eq!(chat.profile.name, "Chat");
+        assert!(!chat.profile.is_default);
+        let app = chat.web_app.as_ref().unwrap();
+        assert_eq!(app.id, "beef");
+        assert_eq!(app.url.as_deref(), Some("https://chat.example.com/"));
+    }
+
+    #[test]
+    fn adds_extra_and_legacy_profiles() {
+        let data = tempfile::tempdir().unwrap();
+        let epiphany = data.path().join("epiphany");
+        let legacy = epiphany.join("app-epiphany-news-0123abcd");
+        std::fs::create_dir_all(&legacy).unwrap();
+        let custom = data.path().join("scratch");
+        let mail = web_app(data.path(), "f00d", "Mail", "https://mail.example.com/");
+
+        let profiles = read_profiles(&epiphany, &[custom.clone(), mail]).unwrap();
+        assert_eq!(profiles.len(), 4);
+        let news = &profiles[1];
+        assert_eq!(news.profile.name, "app-epiphany-news-0123abcd");
+        assert_eq!(news.web_app.as_ref().unwrap().id, "news-0123abcd");
+        assert_eq!(profiles[3].profile.id.0, "scratch");
+        assert_eq!(profiles[3].profile.path, custom);
+        assert_eq!(profiles[3].web_app, None);
+    }
+
+    #[test]
+    fn missing_data_dir_has_no_profiles() {
+        let data = tempfile::tempdir().unwrap();
+        assert!(
+            list_profiles(&data.path().join("epiphany"), &[])
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn url_is_last_exec_argument() {
+        assert_eq!(
+            exec_url("epiphany --application-mode --profile=/p 'https://a.example/'").as_deref(),
+            Some("https://a.example/")
+        );
+        assert_eq!(exec_url("epiphany --application-mode"), None);
+    }
+}
@@ -0,0 +1,113 @@
+//! Atomic writes of browser state files.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::{Error, Result};
+
+/// Suffix of the backup of a file's original contents, kept next to it.
+pub const BACKUP_SUFFIX: &str = ".browserware.bak";
+
+/// Path of the backup of `path`.
+pub(crate) fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(BACKUP_SUFFIX);
+    path.with_file_name(name)
+}
+
+/// Replace a file's contents atomically, backing up the original version.
+///
+/// The new contents are written to a temporary file in the same directory,
+/// flushed to disk and renamed over `path`, so readers see either the old or
+/// the new file. The first write copies the contents to [`backup_path`];
+/// later writes keep that backup, so it always holds the file as it was
+/// before browserware changed it.
+pub(crate) fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let backup = backup_path(path);
+    if path.exists() && !backup.exists() {
+        std::fs::copy(path, &backup).map_err(|err| Error::io(path, err))?;
+    }
+
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}.tmp", std::process::id()));
+    let temp = dir.join(name);
+
+    let result = std::fs::File::create(&temp)
+        .and_then(|mut file| {
+            file.write_all(contents)?;
+            file.sync_all()
+        })
+        .and_then(|()| std::fs::rename(&temp, path));
+    if let Err(err) = result {
+        let _ = std::fs::remove_file(&temp);
+        return Err(Error::io(path, err));
+    }
+    tracing::debug!(path = %path.display(), "Wrote file");
+    Ok(())
+}
+
+/// Delete a profile directory.
+///
+/// Only directories strictly inside `root` are deleted, so a profile stored
+/// elsewhere (or at the root itself) never takes unrelated data with it.
+/// A missing directory is not an error.
+pub(crate) fn remove_profile_dir(root: &Path, dir: &Path) -> Result<()> {
+    let (Ok(root), Ok(dir)) = (std::fs::canonicalize(root), std::fs::canonicalize(dir)) else {
+        return Ok(());
+    };
+    if dir == root || !dir.starts_with(&root) {
+        tracing::warn!(
+            dir = %dir.display(),
+            "Not deleting profile directory outside of {}",
+            root.display()
+        );
+        return Ok(());
+    }
+    std::fs::remove_dir_all(&dir).map_err(|err| Error::io(&dir, err))?;
+    tracing::debug!(dir = %dir.display(), "Deleted profile directory");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_and_backs_up() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("profiles.ini");
+
+        write_atomic(&path, b"first").unwrap();
+        assert!(!backup_path(&path).exists());
+
+        write_atomic(&path, b"second").unwrap();
+        write_atomic(&path, b"third").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "third");
+        // The backup keeps the contents before the first change
+        assert_eq!(
+            std::fs::read_to_string(backup_path(&path)).unwrap(),
+            "first"
+        );
+        // No temporary files are left behind
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn removes_only_directories_inside_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("root");
+        let inside = root.join("Profile 1");
+        let outside = dir.path().join("elsewhere");
+        std::fs::create_dir_all(&inside).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+
+        remove_profile_dir(&root, &inside).unwrap();
+        remove_profile_dir(&root, &outside).unwrap();
+        remove_profile_dir(&root, &root).unwrap();
+        remove_profile_dir(&root, &root.join("missing")).unwrap();
+        assert!(!inside.exists());
+        assert!(outside.exists());
+        assert!(root.exists());
+    }
+}
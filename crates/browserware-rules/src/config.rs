@@ -0,0 +1,328 @@
+//! Routing rule configuration.
+//!
+//! Rules are stored as TOML and evaluated in order; the first rule whose
+//! domains match a URL decides where it opens:
+//!
+//! ```toml
+//! default = "firefox"
+//!
+//! [[rules]]
+//! name = "work"
+//! domains = ["*.corp.example.com", "github.com"]
+//! target = "chrome:Work"
+//!
+//! [[rules]]
+//! name = "dashboards"
+//! domains = ["grafana.corp.example.com"]
+//! target = "chrome:Work#app"
+//! window = "1280x800+0+0"
+//!
+//! [[rules]]
+//! name = "mail"
+//! domains = ["mail.corp.example.com"]
+//! target = "identity:work"
+//!
+//! [[identities]]
+//! name = "work"
+//! candidates = ["chrome:Profile 1", "firefox:work"]
+//! emails = ["me@corp.example.com"]
+//! ```
+//!
+//! An `identity:<name>` target opens the first candidate of the identity
+//! that is available on the current machine. A target's `#<mode>` and a
+//! rule's `window` say how the window opens.
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use browserware_types::{BrowserTarget, Identity, WindowGeometry};
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, Result};
+
+/// A routing rule configuration file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct RulesConfig {
+    /// Target for URLs that match no rule (the system default browser if unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<BrowserTarget>,
+    /// Rules, in evaluation order
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+    /// Identities that `identity:<name>` targets refer to
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub identities: Vec<Identity>,
+}
+
+/// A single routing rule.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    /// Unique rule name
+    pub name: String,
+    /// Domains this rule applies to; `*.` matches any subdomain
+    pub domains: Vec<String>,
+    /// Where matching URLs open
+    pub target: BrowserTarget,
+    /// Size and position of the window, as `[WIDTHxHEIGHT][+X+Y]`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub window: Option<WindowGeometry>,
+}
+
+impl RulesConfig {
+    /// Parse and validate a TOML rule configuration.
+    ///
+    /// `path` is only used to report where a syntax error occurred.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Config`] for TOML syntax errors and
+    /// [`Error::InvalidRule`] or [`Error::InvalidIdentity`] for rules and
+    /// identities that fail validation.
+    pub fn from_toml(text: &str, path: Option<&Path>) -> Result<Self> {
+        let config: Self = toml::from_str(text).map_err(|err| {
+            Error::Config(browserware_types::Error::config_toml(
+                path.map(Path::to_path_buf),
+                text,
+                err,
+            ))
+        })?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Find an identity by name.
+    #[must_use]
+    pub fn identity(&self, name: &str) -> Option<&Identity> {
+        self.identities
+            .iter()
+            .find(|identity| identity.name == name)
+    }
+
+    /// Check that rule names are unique, domains are well-formed and
+    /// identities are defined once and referenced by name.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidRule`], [`Error::InvalidPattern`] or
+    /// [`Error::InvalidIdentity`] for the first rule or identity that fails
+    /// validation, and [`Error::Config`] if the default target refers to an
+    /// unknown identity.
+    pub fn validate(&self) -> Result<()> {
+        self.validate_identities()?;
+        if let Some(name) = self.default.as_ref().and_then(BrowserTarget::identity)
+            && self.identity(name).is_none()
+        {
+            return Err(Error::Config(browserware_types::Error::config(format!(
+                "default target refers to unknown identity '{name}'"
+            ))));
+        }
+
+        let mut names = HashSet::new();
+        for rule in &self.rules {
+            let invalid = |message: String| Error::InvalidRule {
+                rule: rule.name.clone(),
+                message,
+            };
+
+            if rule.name.trim().is_empty() {
+                return Err(invalid("name must not be empty".to_string()));
+            }
+            if !names.insert(rule.name.as_str()) {
+                return Err(invalid("duplicate rule name".to_string()));
+            }
+            if let Some(name) = rule.target.identity()
+                && self.identity(name).is_none()
+            {
+                return Err(invalid(format!("unknown identity '{name}'")));
+            }
+            if rule.domains.is_empty() {
+                return Err(invalid("at least one domain is required".to_string()));
+            }
+            for domain in &rule.domains {
+                let host = domain.strip_prefix("*.").unwrap_or(domain);
+                let valid = !host.is_empty()
+                    && host
+                        .chars()
+                        .all(|c| c.is_alphanumeric() || c == '-' || c == '.')
+                    && !host.starts_with('.')
+                    && !host.ends_with('.');
+                if !valid {
+                    return Err(Error::InvalidPattern {
+                        rule: rule.name.clone(),
+                        pattern: domain.clone(),
+                        message: "expected a host name, optionally prefixed with '*.'".to_string(),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_identities(&self) -> Result<()> {
+        let mut names = HashSet::new();
+        for identity in &self.identities {
+            let invalid = |message: String| Error::InvalidIdentity {
+                identity: identity.name.clone(),
+                message,
+            };
+
+            if identity.name.trim().is_empty() {
+                return Err(invalid("name must not be empty".to_string()));
+            }
+            if !names.insert(identity.name.as_str()) {
+                return Err(invalid("duplicate identity name".to_string()));
+            }
+            if identity.candidates.is_empty() && identity.emails.is_empty() {
+                return Err(invalid(
+                    "at least one candidate or email is required".to_string(),
+                ));
+            }
+            if let Some(nested) = identity.candidates.iter().find(|c| c.identity().is_some()) {
+                return Err(invalid(format!(
+                    "candidate '{nested}' must be a browser, not an identity"
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use browserware_types::LaunchMode;
+
+    #[test]
+    fn parses_rules() {
+        let config = RulesConfig::from_toml(
+            r#"
+            default = "firefox:@last-used"
+
+            [[rules]]
+            name = "work"
+            domains = ["*.corp.example.com", "github.com"]
+            target = "chrome:Work#new-window"
+            window = "1280x800"
+            "#,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.default,
+            Some(BrowserTarget::new("firefox").with_profile("@last-used"))
+        );
+        assert_eq!(config.rules.len(), 1);
+        assert_eq!(
+            config.rules[0].target,
+            BrowserTarget::new("chrome")
+                .with_profile("Work")
+                .with_mode(LaunchMode::NewWindow)
+        );
+        assert_eq!(
+            config.rules[0].window,
+            Some(WindowGeometry::new().with_size(1280, 800))
+        );
+    }
+
+    #[test]
+    fn syntax_errors_report_location() {
+        let err = RulesConfig::from_toml(
+            "[[rules]]\nname = \"work\"\ntarget = \"chrome#sideways\"\ndomains = []\n",
+            Some(Path::new("rules.toml")),
+        )
+        .unwrap_err();
+        let err = browserware_types::Error::from(err);
+        assert_eq!(err.code(), browserware_types::ErrorCode::InvalidConfig);
+        assert!(err.to_string().contains("rules.toml at line 3"), "{err}");
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        let rule = |name: &str, domain: &str| Rule {
+            name: name.to_string(),
+            domains: vec![domain.to_string()],
+            target: BrowserTarget::new("chrome"),
+            window: None,
+        };
+
+        let duplicate = RulesConfig {
+            rules: vec![rule("a", "a.com"), rule("a", "b.com")],
+            ..RulesConfig::default()
+        };
+        assert!(matches!(
+            duplicate.validate(),
+            Err(Error::InvalidRule { .. })
+        ));
+
+        for domain in ["", "*.", "https://a.com", "a.com/path", ".a.com"] {
+            let config = RulesConfig {
+                rules: vec![rule("a", domain)],
+                ..RulesConfig::default()
+            };
+            assert!(
+                matches!(config.validate(), Err(Error::InvalidPattern { .. })),
+                "{domain:?} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn identities_are_validated_and_referenced() {
+        let config = RulesConfig::from_toml(
+            r#"
+            default = "identity:personal"
+
+            [[rules]]
+            name = "work"
+            domains = ["corp.example.com"]
+            target = "identity:work#new-window"
+
+            [[identities]]
+            name = "work"
+            candidates = ["chrome:Profile 1", "firefox:work"]
+
+            [[identities]]
+            name = "personal"
+            emails = ["me@example.com"]
+            "#,
+            None,
+        )
+        .unwrap();
+        let work = config.identity("work").unwrap();
+        assert_eq!(
+            work.candidates[1],
+            BrowserTarget::new("firefox").with_profile("work")
+        );
+        assert!(
+            config
+                .identity("personal")
+                .unwrap()
+                .has_email("Me@Example.com")
+        );
+
+        let unknown = "[[rules]]\nname = \"r\"\ndomains = [\"a.com\"]\ntarget = \"identity:x\"\n";
+        assert!(matches!(
+            RulesConfig::from_toml(unknown, None),
+            Err(Error::InvalidRule { .. })
+        ));
+        for identity in [
+            "name = \"w\"",
+            "name = \"w\"\ncandidates = [\"identity:v\"]",
+        ] {
+            let text = format!("[[identities]]\n{identity}\n");
+            assert!(matches!(
+                RulesConfig::from_toml(&text, None),
+                Err(Error::InvalidIdentity { .. })
+            ));
+        }
+        assert!(matches!(
+            RulesConfig::from_toml("default = \"identity:x\"", None),
+            Err(Error::Config(_))
+        ));
+    }
+}
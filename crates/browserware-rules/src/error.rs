@@ -0,0 +1,82 @@
+//! Error types for routing rules.
+
+use thiserror::Error;
+
+/// Result type alias using the rules Error type.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Errors that can occur while loading or evaluating routing rules.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// Rule is malformed
+    #[error("invalid rule '{rule}': {message}")]
+    InvalidRule {
+        /// Name of the rule
+        rule: String,
+        /// Description of the problem
+        message: String,
+    },
+
+    /// Rule contains a pattern that cannot be compiled
+    #[error("invalid pattern '{pattern}' in rule '{rule}': {message}")]
+    InvalidPattern {
+        /// Name of the rule
+        rule: String,
+        /// The offending pattern
+        pattern: String,
+        /// Description of the problem
+        message: String,
+    },
+
+    /// Identity definition is malformed
+    #[error("invalid identity '{identity}': {message}")]
+    InvalidIdentity {
+        /// Name of the identity
+        identity: String,
+        /// Description of the problem
+        message: String,
+    },
+
+    /// Configuration file could not be parsed
+    #[error(transparent)]
+    Config(browserware_types::Error),
+}
+
+impl From<Error> for browserware_types::Error {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::InvalidRule { rule, message } => Self::InvalidRule { rule, message },
+            Error::InvalidPattern {
+                rule,
+                pattern,
+                message,
+            } => Self::InvalidRule {
+                rule,
+                message: format!("invalid pattern '{pattern}': {message}"),
+            },
+            Error::InvalidIdentity { .. } => Self::config(err.to_string()),
+            Error::Config(err) => err,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use browserware_types::ErrorCode;
+
+    #[test]
+    fn pattern_errors_keep_rule_name() {
+        let err: browserware_types::Error = Error::InvalidPattern {
+            rule: "work".into(),
+            pattern: "*.*.".into(),
+            message: "empty label".into(),
+        }
+        .into();
+        assert_eq!(err.code(), ErrorCode::InvalidRule);
+        assert_eq!(
+            err.to_string(),
+            "invalid rule 'work': invalid pattern '*.*.': empty label"
+        );
+    }
+}
@@ -7,4 +7,14 @@
 #![forbid(unsafe_code)]
 #![warn(missing_docs)]
 
+mod config;
+mod error;
+mod suggest;
+
+pub use config::{Rule, RulesConfig};
+pub use error::{Error, Result};
+pub use suggest::{
+    ProfileHistory, SuggestOptions, Suggestion, registrable_domain, suggest_rules, suggestions_toml,
+};
+
 // TODO: Implement in Milestone 4
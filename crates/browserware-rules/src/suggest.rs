@@ -0,0 +1,345 @@
+//! Rule suggestions learned from browsing history.
+//!
+//! Each profile's visits are grouped by registrable domain. A domain visited
+//! predominantly in one profile becomes a suggested rule for that profile,
+//! with a confidence equal to that profile's share of the domain's visits.
+//!
+//! Registrable domains are approximated without the Public Suffix List: the
+//! last two labels of a host, or three under common second-level suffixes
+//! such as `co.uk`.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use std::net::IpAddr;
+
+use browserware_types::BrowserTarget;
+use serde::Serialize;
+
+/// Second-level labels that, under a two-letter country code, form a public suffix.
+const SECOND_LEVEL_SUFFIXES: [&str; 9] =
+    ["ac", "co", "com", "edu", "gov", "ltd", "net", "org", "plc"];
+
+/// Visits to the hosts of one profile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileHistory {
+    /// Where the profile's URLs open
+    pub target: BrowserTarget,
+    /// Host names and their visit counts
+    pub visits: Vec<(String, u64)>,
+}
+
+impl ProfileHistory {
+    /// Create the history of the profile `target` refers to.
+    pub fn new(target: BrowserTarget, visits: impl IntoIterator<Item = (String, u64)>) -> Self {
+        Self {
+            target,
+            visits: visits.into_iter().collect(),
+        }
+    }
+}
+
+/// Thresholds a domain must reach to be suggested.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SuggestOptions {
+    /// Visits to the domain across all profiles
+    pub min_visits: u64,
+    /// Share of the visits made in the suggested profile, from 0 to 1
+    pub min_confidence: f64,
+}
+
+impl Default for SuggestOptions {
+    fn default() -> Self {
+        Self {
+            min_visits: 10,
+            min_confidence: 0.8,
+        }
+    }
+}
+
+impl SuggestOptions {
+    /// Set the minimum number of visits.
+    #[must_use]
+    pub const fn with_min_visits(mut self, visits: u64) -> Self {
+        self.min_visits = visits;
+        self
+    }
+
+    /// Set the minimum confidence.
+    #[must_use]
+    pub const fn with_min_confidence(mut self, confidence: f64) -> Self {
+        self.min_confidence = confidence;
+        self
+    }
+}
+
+/// A domain suggested to open in a profile.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Suggestion {
+    /// Registrable domain (e.g. `example.co.uk`)
+    pub domain: String,
+    /// Profile the domain is mostly visited in
+    pub target: BrowserTarget,
+    /// Share of the domain's visits made in `target`, from 0 to 1
+    pub confidence: f64,
+    /// Visits to the domain in `target`
+    pub visits: u64,
+    /// Visits to the domain across all profiles
+    pub total_visits: u64,
+}
+
+/// Suggest domains to route to the profile they are mostly visited in.
+///
+/// Suggestions are ordered by profile (in the order of `histories`), then by
+/// visits. At least two profiles are needed: with one, every domain would be
+/// suggested for it.
+///
+/// # Example
+///
+/// ```
+/// use browserware_rules::{ProfileHistory, SuggestOptions, suggest_rules};
+///
+/// let histories = [
+///     ProfileHistory::new("chrome:Work".parse()?, [("github.com".to_string(), 40)]),
+///     ProfileHistory::new("firefox".parse()?, [("news.example.org".to_string(), 25)]),
+/// ];
+/// let suggestions = suggest_rules(&histories, &SuggestOptions::default());
+/// assert_eq!(suggestions[0].domain, "github.com");
+/// assert_eq!(suggestions[0].target.to_string(), "chrome:Work");
+/// # Ok::<(), browserware_types::Error>(())
+/// ```
+#[must_use]
+pub fn suggest_rules(histories: &[ProfileHistory], options: &SuggestOptions) -> Vec<Suggestion> {
+    if histories.len() < 2 {
+        return Vec::new();
+    }
+
+    let mut domains: HashMap<String, Vec<u64>> = HashMap::new();
+    for (index, history) in histories.iter().enumerate() {
+        for (host, visits) in &history.visits {
+            if let Some(domain) = registrable_domain(host) {
+                domains
+                    .entry(domain)
+                    .or_insert_with(|| vec![0; histories.len()])[index] += visits;
+            }
+        }
+    }
+
+    let mut suggestions: Vec<(usize, Suggestion)> = domains
+        .into_iter()
+        .filter_map(|(domain, counts)| {
+            let total_visits: u64 = counts.iter().sum();
+            let (index, &visits) = counts
+                .iter()
+                .enumerate()
+                .max_by(|(a_index, a), (b_index, b)| a.cmp(b).then(b_index.cmp(a_index)))?;
+            #[allow(clippy::cast_precision_loss)]
+            let confidence = visits as f64 / total_visits as f64;
+            (total_visits >= options.min_visits && confidence >= options.min_confidence).then(
+                || {
+                    let suggestion = Suggestion {
+                        domain,
+                        target: histories[index].target.clone(),
+                        confidence,
+                        visits,
+                        total_visits,
+                    };
+                    (index, suggestion)
+                },
+            )
+        })
+        .collect();
+    suggestions.sort_by(|(a_index, a), (b_index, b)| {
+        a_index
+            .cmp(b_index)
+            .then(b.visits.cmp(&a.visits))
+            .then_with(|| a.domain.cmp(&b.domain))
+    });
+    suggestions.into_iter().map(|(_, s)| s).collect()
+}
+
+/// Write suggestions as a TOML snippet of `[[rules]]`, one per profile.
+///
+/// Each domain is listed with its subdomains and a comment giving its
+/// confidence, so the snippet can be reviewed before it is copied into a
+/// configuration.
+#[must_use]
+pub fn suggestions_toml(suggestions: &[Suggestion]) -> String {
+    let mut toml = String::from(
+        "# Routing rules suggested from browsing history.\n\
+         # Review them and copy the ones you want into your configuration.\n\
+         # Confidence is the share of a domain's visits made in the rule's profile.\n",
+    );
+
+    let mut targets: Vec<&BrowserTarget> = Vec::new();
+    for suggestion in suggestions {
+        if !targets.contains(&&suggestion.target) {
+            targets.push(&suggestion.target);
+        }
+    }
+
+    let mut names = HashSet::new();
+    for target in targets {
+        let base = rule_name(target);
+        let name = (1..=u32::MAX)
+            .map(|n| {
+                if n == 1 {
+                    base.clone()
+                } else {
+                    format!("{base}-{n}")
+                }
+            })
+            .find(|name| !names.contains(name))
+            .unwrap_or(base);
+
+        let _ = write!(
+            toml,
+            "\n[[rules]]\nname = {}\ntarget = {}\ndomains = [\n",
+            quote(&name),
+            quote(&target.to_string())
+        );
+        for suggestion in suggestions.iter().filter(|s| s.target == *target) {
+            let _ = write!(
+                toml,
+                "    # {}: {:.0}% of {} visits\n    {}, {},\n",
+                suggestion.domain,
+                suggestion.confidence * 100.0,
+                suggestion.total_visits,
+                quote(&suggestion.domain),
+                quote(&format!("*.{}", suggestion.domain)),
+            );
+        }
+        toml.push_str("]\n");
+        names.insert(name);
+    }
+    toml
+}
+
+/// The registrable domain of a host, or `None` for IP addresses and hosts
+/// that cannot be used in a rule.
+#[must_use]
+pub fn registrable_domain(host: &str) -> Option<String> {
+    let host = host.trim_end_matches('.').to_lowercase();
+    let valid = !host.is_empty()
+        && host
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '.')
+        && !host.starts_with('.')
+        && !host.contains("..");
+    if !valid || host.parse::<IpAddr>().is_ok() {
+        return None;
+    }
+
+    let labels: Vec<&str> = host.split('.').collect();
+    let keep = match labels.as_slice() {
+        [.., second, tld] if tld.len() == 2 && SECOND_LEVEL_SUFFIXES.contains(second) => 3,
+        _ => 2,
+    };
+    Some(labels[labels.len().saturating_sub(keep)..].join("."))
+}
+
+/// Rule name derived from a target (e.g. `chrome-profile-1`).
+fn rule_name(target: &BrowserTarget) -> String {
+    let name = target
+        .to_string()
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    if name.is_empty() {
+        "suggested".to_string()
+    } else {
+        name
+    }
+}
+
+/// A TOML basic string.
+fn quote(value: &str) -> String {
+    toml::Value::String(value.to_string()).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RulesConfig;
+
+    fn history(target: &str, visits: &[(&str, u64)]) -> ProfileHistory {
+        ProfileHistory::new(
+            target.parse().unwrap(),
+            visits.iter().map(|(host, n)| ((*host).to_string(), *n)),
+        )
+    }
+
+    #[test]
+    fn registrable_domains() {
+        assert_eq!(
+            registrable_domain("www.github.com").as_deref(),
+            Some("github.com")
+        );
+        assert_eq!(
+            registrable_domain("news.bbc.co.uk").as_deref(),
+            Some("bbc.co.uk")
+        );
+        assert_eq!(
+            registrable_domain("Example.COM.").as_deref(),
+            Some("example.com")
+        );
+        assert_eq!(
+            registrable_domain("localhost").as_deref(),
+            Some("localhost")
+        );
+        assert_eq!(registrable_domain("192.168.1.1"), None);
+        assert_eq!(registrable_domain("[::1]"), None);
+    }
+
+    #[test]
+    fn suggests_predominant_profile() {
+        let histories = [
+            history(
+                "chrome:Work",
+                &[
+                    ("github.com", 30),
+                    ("gist.github.com", 10),
+                    ("news.example", 5),
+                ],
+            ),
+            history(
+                "firefox",
+                &[("news.example", 5), ("github.com", 2), ("rare.example", 3)],
+            ),
+        ];
+        let suggestions = suggest_rules(&histories, &SuggestOptions::default());
+        assert_eq!(suggestions.len(), 1, "{suggestions:?}");
+        let github = &suggestions[0];
+        assert_eq!(github.domain, "github.com");
+        assert_eq!((github.visits, github.total_visits), (40, 42));
+        assert!(github.confidence > 0.95);
+
+        // A single profile gives nothing to choose between
+        assert!(suggest_rules(&histories[..1], &SuggestOptions::default()).is_empty());
+        let relaxed = SuggestOptions::default()
+            .with_min_visits(3)
+            .with_min_confidence(0.6);
+        assert_eq!(suggest_rules(&histories, &relaxed).len(), 2);
+    }
+
+    #[test]
+    fn snippet_is_a_valid_config() {
+        let histories = [
+            history("chrome:Profile 1", &[("github.com", 40), ("docs.rs", 20)]),
+            history("firefox@flatpak", &[("bbc.co.uk", 12)]),
+        ];
+        let toml = suggestions_toml(&suggest_rules(&histories, &SuggestOptions::default()));
+        assert!(toml.contains("# github.com: 100% of 40 visits"), "{toml}");
+
+        let config = RulesConfig::from_toml(&toml, None).unwrap();
+        assert_eq!(config.rules.len(), 2);
+        assert_eq!(config.rules[0].name, "chrome-profile-1");
+        assert_eq!(
+            config.rules[0].domains,
+            ["github.com", "*.github.com", "docs.rs", "*.docs.rs"]
+        );
+        assert_eq!(config.rules[1].target.to_string(), "firefox@flatpak");
+    }
+}
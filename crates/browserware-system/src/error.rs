@@ -0,0 +1,49 @@
+//! Error types for system integration.
+
+use thiserror::Error;
+
+/// Result type alias using the system integration Error type.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Errors that can occur while integrating with the operating system.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// Operation is not available on this platform or desktop
+    #[error("{0} is not supported on this platform")]
+    Unsupported(String),
+
+    /// A system command (e.g. `xdg-settings`) failed
+    #[error("command '{command}' failed: {message}")]
+    Command {
+        /// Command that was run
+        command: String,
+        /// Description of the failure
+        message: String,
+    },
+
+    /// I/O error
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl From<Error> for browserware_types::Error {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::Unsupported(_) => Self::UnsupportedPlatform(err.to_string()),
+            Error::Command { .. } => Self::Other(err.to_string()),
+            Error::Io(source) => Self::from(source),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use browserware_types::ErrorCode;
+
+    #[test]
+    fn unsupported_maps_to_unsupported_code() {
+        let err: browserware_types::Error = Error::Unsupported("registering".into()).into();
+        assert_eq!(err.code(), ErrorCode::Unsupported);
+    }
+}
@@ -7,4 +7,8 @@
 #![forbid(unsafe_code)]
 #![warn(missing_docs)]
 
+mod error;
+
+pub use error::{Error, Result};
+
 // TODO: Implement in Milestone 5
@@ -7,6 +7,7 @@ use crate::variant::BrowserVariant;
 
 /// Unique identifier for a browser installation.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct BrowserId(pub String);
 
 impl BrowserId {
@@ -24,6 +25,7 @@ impl std::fmt::Display for BrowserId {
 
 /// Browser engine family.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "lowercase")]
 pub enum BrowserFamily {
     /// Chromium-based browsers (Chrome, Edge, Brave, Arc, etc.)
@@ -49,8 +51,66 @@ impl std::fmt::Display for BrowserFamily {
     }
 }
 
+/// How a browser installation is packaged.
+///
+/// Sandboxed packages relocate the browser's data directories and need a
+/// wrapper command to launch, so consumers must know which one they have.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum Packaging {
+    /// Installed directly on the host (system package, app bundle, installer)
+    #[default]
+    Native,
+    /// Installed as a Flatpak application (Linux)
+    Flatpak,
+    /// Installed as a Snap package (Linux)
+    Snap,
+}
+
+impl Packaging {
+    /// Returns the canonical string name for this packaging.
+    #[must_use]
+    pub const fn canonical_name(self) -> &'static str {
+        match self {
+            Self::Native => "native",
+            Self::Flatpak => "flatpak",
+            Self::Snap => "snap",
+        }
+    }
+
+    /// Returns true if the browser runs inside a sandbox (Flatpak or Snap).
+    #[must_use]
+    pub const fn is_sandboxed(self) -> bool {
+        matches!(self, Self::Flatpak | Self::Snap)
+    }
+}
+
+impl std::fmt::Display for Packaging {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.canonical_name())
+    }
+}
+
+impl std::str::FromStr for Packaging {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "native" => Ok(Self::Native),
+            "flatpak" => Ok(Self::Flatpak),
+            "snap" => Ok(Self::Snap),
+            _ => Err(crate::Error::InvalidTarget {
+                input: s.to_string(),
+                reason: "unknown packaging (expected native, flatpak or snap)".to_string(),
+            }),
+        }
+    }
+}
+
 /// Information about an installed browser.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Browser {
     /// Unique identifier for this browser
     pub id: BrowserId,
@@ -65,6 +125,9 @@ pub struct Browser {
     /// Bundle identifier (macOS)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub bundle_id: Option<String>,
+    /// How the browser is packaged (native, Flatpak, Snap)
+    #[serde(default)]
+    pub packaging: Packaging,
 }
 
 impl Browser {
@@ -77,6 +140,7 @@ impl Browser {
             version: None,
             executable,
             bundle_id: None,
+            packaging: Packaging::Native,
         }
     }
 
@@ -106,6 +170,13 @@ impl Browser {
         self.bundle_id = Some(bundle_id.into());
         self
     }
+
+    /// Set the packaging (native, Flatpak, Snap).
+    #[must_use]
+    pub const fn with_packaging(mut self, packaging: Packaging) -> Self {
+        self.packaging = packaging;
+        self
+    }
 }
 
 #[cfg(test)]
@@ -156,6 +227,24 @@ mod tests {
         assert_eq!(other.family(), BrowserFamily::Other);
     }
 
+    #[test]
+    fn packaging_defaults_to_native() {
+        let browser = Browser::new("chrome", "Chrome", "/usr/bin/chrome".into());
+        assert_eq!(browser.packaging, Packaging::Native);
+        assert!(!browser.packaging.is_sandboxed());
+
+        let flatpak = browser.with_packaging(Packaging::Flatpak);
+        assert!(flatpak.packaging.is_sandboxed());
+        assert_eq!(flatpak.packaging.to_string(), "flatpak");
+    }
+
+    #[test]
+    fn packaging_missing_from_json_is_native() {
+        let json = r#"{"id":"chrome","name":"Chrome","variant":{"type":"Single","value":"chromium"},"version":null,"executable":"/usr/bin/chrome"}"#;
+        let parsed: Browser = serde_json::from_str(json).unwrap();
+        assert_eq!(parsed.packaging, Packaging::Native);
+    }
+
     #[test]
     fn browser_serialization() {
         let browser = Browser::new("firefox", "Firefox", "/usr/bin/firefox".into())
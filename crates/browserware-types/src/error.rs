@@ -1,28 +1,275 @@
 //! Error types for the browserware ecosystem.
+//!
+//! Every [`Error`] carries a stable [`ErrorCode`] for programmatic handling
+//! (CLI exit codes, GUI messages, JSON output) and keeps the context needed to
+//! explain the failure: the file path, line and column, browser ID or rule
+//! name involved. Underlying errors are preserved as [sources](std::error::Error::source).
 
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 /// Result type alias using browserware's Error type.
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// Boxed error used as the source of errors raised by other crates.
+pub type BoxError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+/// Process exit codes, following BSD `sysexits.h`.
+pub mod exit_code {
+    /// Generic failure.
+    pub const FAILURE: i32 = 1;
+    /// The command was used incorrectly.
+    pub const USAGE: i32 = 64;
+    /// The input data was incorrect.
+    pub const DATA_ERR: i32 = 65;
+    /// An input file did not exist or was not readable.
+    pub const NO_INPUT: i32 = 66;
+    /// A required service (browser, profile) is unavailable.
+    pub const UNAVAILABLE: i32 = 69;
+    /// An internal software error.
+    pub const SOFTWARE: i32 = 70;
+    /// An operating system error, such as failure to spawn a process.
+    pub const OS_ERR: i32 = 71;
+    /// An error occurred while doing I/O.
+    pub const IO_ERR: i32 = 74;
+    /// A temporary failure; retrying may succeed.
+    pub const TEMP_FAIL: i32 = 75;
+    /// Insufficient permission to perform the operation.
+    pub const NO_PERM: i32 = 77;
+    /// Something was found in an unconfigured or misconfigured state.
+    pub const CONFIG: i32 = 78;
+}
+
+/// Stable, machine-readable identifier for a class of errors.
+///
+/// Codes never change once published, so they are safe to match on in
+/// scripts and to show in user-facing messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// A browser could not be found
+    BrowserNotFound,
+    /// A profile could not be found
+    ProfileNotFound,
+    /// A profile query matches more than one profile
+    ProfileAmbiguous,
+    /// A profile is in use by a running browser
+    ProfileInUse,
+    /// A container could not be found in a profile
+    ContainerNotFound,
+    /// A browser add-on the operation needs is not installed
+    AddonMissing,
+    /// A browser target string is malformed
+    InvalidTarget,
+    /// A command-line or API argument is invalid
+    InvalidArgument,
+    /// The configuration file does not exist
+    ConfigNotFound,
+    /// The configuration is invalid
+    InvalidConfig,
+    /// A routing rule is invalid
+    InvalidRule,
+    /// A browser data file could not be parsed
+    Parse,
+    /// A browser could not be launched
+    LaunchFailed,
+    /// An I/O operation failed
+    Io,
+    /// A URL could not be parsed
+    InvalidUrl,
+    /// JSON could not be parsed or produced
+    Json,
+    /// TOML could not be parsed
+    Toml,
+    /// The operation is not supported on this platform
+    Unsupported,
+    /// Any other error
+    Other,
+}
+
+impl ErrorCode {
+    /// Returns the stable string form of this code.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::BrowserNotFound => "browser_not_found",
+            Self::ProfileNotFound => "profile_not_found",
+            Self::ProfileAmbiguous => "profile_ambiguous",
+            Self::ProfileInUse => "profile_in_use",
+            Self::ContainerNotFound => "container_not_found",
+            Self::AddonMissing => "addon_missing",
+            Self::InvalidTarget => "invalid_target",
+            Self::InvalidArgument => "invalid_argument",
+            Self::ConfigNotFound => "config_not_found",
+            Self::InvalidConfig => "invalid_config",
+            Self::InvalidRule => "invalid_rule",
+            Self::Parse => "parse",
+            Self::LaunchFailed => "launch_failed",
+            Self::Io => "io",
+            Self::InvalidUrl => "invalid_url",
+            Self::Json => "json",
+            Self::Toml => "toml",
+            Self::Unsupported => "unsupported",
+            Self::Other => "other",
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 /// Errors that can occur in browserware operations.
 #[derive(Debug, Error)]
 pub enum Error {
     /// Browser was not found
-    #[error("browser not found: {0}")]
-    BrowserNotFound(String),
+    #[error("browser not found: {browser}")]
+    BrowserNotFound {
+        /// Browser ID or name that was looked up
+        browser: String,
+    },
 
     /// Profile was not found
-    #[error("profile not found: {0}")]
-    ProfileNotFound(String),
+    #[error("profile not found: {profile}{}", in_browser(.browser.as_deref()))]
+    ProfileNotFound {
+        /// Browser the profile was looked up in (if known)
+        browser: Option<String>,
+        /// Profile ID or name that was looked up
+        profile: String,
+    },
+
+    /// Profile query matches more than one profile
+    #[error("profile '{profile}' is ambiguous{}: matches {}", in_browser(.browser.as_deref()), .candidates.join(", "))]
+    ProfileAmbiguous {
+        /// Browser the profile was looked up in (if known)
+        browser: Option<String>,
+        /// Profile query
+        profile: String,
+        /// IDs of the matching profiles
+        candidates: Vec<String>,
+    },
+
+    /// Profile cannot be changed because a browser is using it
+    #[error("profile {profile}{} is in use: {reason}", in_browser(.browser.as_deref()))]
+    ProfileInUse {
+        /// Browser the profile belongs to (if known)
+        browser: Option<String>,
+        /// Profile ID or name
+        profile: String,
+        /// What is using the profile
+        reason: String,
+    },
+
+    /// Container was not found in a profile
+    #[error("container not found: {container} in profile {profile}")]
+    ContainerNotFound {
+        /// Profile the container was looked up in
+        profile: String,
+        /// Container name or ID that was looked up
+        container: String,
+    },
+
+    /// A browser add-on needed for the operation is not installed
+    #[error("add-on '{addon}' is not installed in profile {profile}: {reason}")]
+    AddonMissing {
+        /// Profile that lacks the add-on
+        profile: String,
+        /// Add-on name
+        addon: String,
+        /// What the add-on is needed for
+        reason: String,
+    },
+
+    /// Browser target string could not be parsed
+    #[error("invalid browser target '{input}': {reason}")]
+    InvalidTarget {
+        /// The target string as given
+        input: String,
+        /// Why it was rejected
+        reason: String,
+    },
 
-    /// Configuration error
-    #[error("configuration error: {0}")]
-    Config(String),
+    /// An argument has an invalid value
+    #[error("invalid {argument}: {message}")]
+    InvalidArgument {
+        /// Name of the argument
+        argument: String,
+        /// Why the value was rejected
+        message: String,
+    },
+
+    /// Configuration file does not exist
+    #[error("configuration file not found: {}", .path.display())]
+    ConfigNotFound {
+        /// Path that was looked up
+        path: PathBuf,
+    },
+
+    /// Configuration is invalid
+    #[error("invalid configuration{}: {message}", location(" in", .path.as_deref(), *.line, *.column))]
+    Config {
+        /// Configuration file (if known)
+        path: Option<PathBuf>,
+        /// 1-based line of the problem (if known)
+        line: Option<usize>,
+        /// 1-based column of the problem (if known)
+        column: Option<usize>,
+        /// Description of the problem
+        message: String,
+        /// Underlying parse error
+        #[source]
+        source: Option<BoxError>,
+    },
+
+    /// Routing rule is invalid
+    #[error("invalid rule '{rule}': {message}")]
+    InvalidRule {
+        /// Name of the rule
+        rule: String,
+        /// Description of the problem
+        message: String,
+    },
+
+    /// Browser data file (profile list, preferences) could not be parsed
+    #[error("failed to parse{}: {message}", location("", .path.as_deref(), *.line, *.column))]
+    Parse {
+        /// File being parsed (if known)
+        path: Option<PathBuf>,
+        /// 1-based line of the problem (if known)
+        line: Option<usize>,
+        /// 1-based column of the problem (if known)
+        column: Option<usize>,
+        /// Description of the problem
+        message: String,
+        /// Underlying parse error
+        #[source]
+        source: Option<BoxError>,
+    },
+
+    /// Browser could not be launched
+    #[error("failed to launch {browser}: {message}")]
+    LaunchFailed {
+        /// Browser ID
+        browser: String,
+        /// Description of the problem
+        message: String,
+        /// Underlying error
+        #[source]
+        source: Option<BoxError>,
+    },
 
     /// I/O error
-    #[error("I/O error: {0}")]
-    Io(#[from] std::io::Error),
+    #[error("I/O error{}: {source}", at_path(.path.as_deref()))]
+    Io {
+        /// File or directory involved (if known)
+        path: Option<PathBuf>,
+        /// Underlying I/O error
+        source: std::io::Error,
+    },
 
     /// URL parsing error
     #[error("invalid URL: {0}")]
@@ -36,11 +283,291 @@ pub enum Error {
     #[error("TOML error: {0}")]
     Toml(#[from] toml::de::Error),
 
-    /// Platform not supported
-    #[error("platform not supported: {0}")]
+    /// Operation not supported on this platform
+    #[error("not supported on this platform: {0}")]
     UnsupportedPlatform(String),
 
     /// Generic error with message
     #[error("{0}")]
     Other(String),
 }
+
+impl Error {
+    /// Create an I/O error for a specific path.
+    pub fn io_at(path: impl Into<PathBuf>, source: std::io::Error) -> Self {
+        Self::Io {
+            path: Some(path.into()),
+            source,
+        }
+    }
+
+    /// Create a configuration error without location information.
+    pub fn config(message: impl Into<String>) -> Self {
+        Self::Config {
+            path: None,
+            line: None,
+            column: None,
+            message: message.into(),
+            source: None,
+        }
+    }
+
+    /// Create a configuration error from a TOML parse failure.
+    ///
+    /// `text` is the parsed document; it is used to turn the error's byte
+    /// span into a line and column.
+    #[must_use]
+    pub fn config_toml(path: Option<PathBuf>, text: &str, source: toml::de::Error) -> Self {
+        let (line, column) = source.span().map_or((None, None), |span| {
+            let (line, column) = line_column(text, span.start);
+            (Some(line), Some(column))
+        });
+        Self::Config {
+            path,
+            line,
+            column,
+            message: source.message().to_string(),
+            source: Some(Box::new(source)),
+        }
+    }
+
+    /// Create a parse error for a browser data file.
+    pub fn parse(path: impl Into<PathBuf>, message: impl Into<String>) -> Self {
+        Self::Parse {
+            path: Some(path.into()),
+            line: None,
+            column: None,
+            message: message.into(),
+            source: None,
+        }
+    }
+
+    /// Returns the stable code for this error.
+    #[must_use]
+    pub const fn code(&self) -> ErrorCode {
+        match self {
+            Self::BrowserNotFound { .. } => ErrorCode::BrowserNotFound,
+            Self::ProfileNotFound { .. } => ErrorCode::ProfileNotFound,
+            Self::ProfileAmbiguous { .. } => ErrorCode::ProfileAmbiguous,
+            Self::ProfileInUse { .. } => ErrorCode::ProfileInUse,
+            Self::ContainerNotFound { .. } => ErrorCode::ContainerNotFound,
+            Self::AddonMissing { .. } => ErrorCode::AddonMissing,
+            Self::InvalidTarget { .. } => ErrorCode::InvalidTarget,
+            Self::InvalidArgument { .. } => ErrorCode::InvalidArgument,
+            Self::ConfigNotFound { .. } => ErrorCode::ConfigNotFound,
+            Self::Config { .. } => ErrorCode::InvalidConfig,
+            Self::InvalidRule { .. } => ErrorCode::InvalidRule,
+            Self::Parse { .. } => ErrorCode::Parse,
+            Self::LaunchFailed { .. } => ErrorCode::LaunchFailed,
+            Self::Io { .. } => ErrorCode::Io,
+            Self::Url(_) => ErrorCode::InvalidUrl,
+            Self::Json(_) => ErrorCode::Json,
+            Self::Toml(_) => ErrorCode::Toml,
+            Self::UnsupportedPlatform(_) => ErrorCode::Unsupported,
+            Self::Other(_) => ErrorCode::Other,
+        }
+    }
+
+    /// Returns true if retrying the operation may succeed.
+    ///
+    /// Transient I/O failures (interrupted calls, timeouts, resources
+    /// temporarily unavailable) are retryable, and so is a profile in use,
+    /// which can be changed once its browser exits.
+    #[must_use]
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Io { source, .. } => is_transient(source),
+            // Succeeds once the browser exits
+            Self::ProfileInUse { .. } => true,
+            Self::LaunchFailed {
+                source: Some(source),
+                ..
+            } => source
+                .downcast_ref::<std::io::Error>()
+                .is_some_and(is_transient),
+            _ => false,
+        }
+    }
+
+    /// Returns the process exit code for this error, following `sysexits.h`.
+    #[must_use]
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::InvalidTarget { .. }
+            | Self::InvalidArgument { .. }
+            | Self::ProfileAmbiguous { .. } => exit_code::USAGE,
+            Self::Url(_) | Self::Json(_) | Self::Parse { .. } => exit_code::DATA_ERR,
+            Self::ConfigNotFound { .. } => exit_code::NO_INPUT,
+            Self::BrowserNotFound { .. }
+            | Self::ProfileNotFound { .. }
+            | Self::ContainerNotFound { .. }
+            | Self::AddonMissing { .. }
+            | Self::UnsupportedPlatform(_) => exit_code::UNAVAILABLE,
+            Self::Config { .. } | Self::InvalidRule { .. } | Self::Toml(_) => exit_code::CONFIG,
+            Self::LaunchFailed { .. } => exit_code::OS_ERR,
+            Self::ProfileInUse { .. } => exit_code::TEMP_FAIL,
+            Self::Io { source, .. } => match source.kind() {
+                _ if is_transient(source) => exit_code::TEMP_FAIL,
+                std::io::ErrorKind::NotFound => exit_code::NO_INPUT,
+                std::io::ErrorKind::PermissionDenied => exit_code::NO_PERM,
+                _ => exit_code::IO_ERR,
+            },
+            Self::Other(_) => exit_code::FAILURE,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(source: std::io::Error) -> Self {
+        Self::Io { path: None, source }
+    }
+}
+
+fn is_transient(error: &std::io::Error) -> bool {
+    use std::io::ErrorKind;
+    matches!(
+        error.kind(),
+        ErrorKind::Interrupted | ErrorKind::WouldBlock | ErrorKind::TimedOut
+    )
+}
+
+/// Convert a byte offset into a 1-based line and column.
+fn line_column(text: &str, offset: usize) -> (usize, usize) {
+    let mut offset = offset.min(text.len());
+    while !text.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let before = &text[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().map_or(0, |l| l.chars().count()) + 1;
+    (line, column)
+}
+
+fn in_browser(browser: Option<&str>) -> String {
+    browser.map(|b| format!(" in {b}")).unwrap_or_default()
+}
+
+fn at_path(path: Option<&Path>) -> String {
+    path.map(|p| format!(" at {}", p.display()))
+        .unwrap_or_default()
+}
+
+fn location(
+    path_prefix: &str,
+    path: Option<&Path>,
+    line: Option<usize>,
+    column: Option<usize>,
+) -> String {
+    use std::fmt::Write;
+
+    let mut out = String::new();
+    if let Some(path) = path {
+        let _ = write!(out, "{path_prefix} {}", path.display());
+    }
+    match (line, column) {
+        (Some(line), Some(column)) => {
+            let _ = write!(out, " at line {line}, column {column}");
+        }
+        (Some(line), None) => {
+            let _ = write!(out, " at line {line}");
+        }
+        _ => {}
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error as _;
+
+    #[test]
+    fn codes_are_stable() {
+        let err = Error::BrowserNotFound {
+            browser: "chrome".into(),
+        };
+        assert_eq!(err.code(), ErrorCode::BrowserNotFound);
+        assert_eq!(err.code().as_str(), "browser_not_found");
+        assert_eq!(
+            serde_json::to_string(&ErrorCode::InvalidConfig).unwrap(),
+            r#""invalid_config""#
+        );
+    }
+
+    #[test]
+    fn messages_include_context() {
+        let err = Error::ProfileNotFound {
+            browser: Some("firefox".into()),
+            profile: "Work".into(),
+        };
+        assert_eq!(err.to_string(), "profile not found: Work in firefox");
+
+        let err = Error::ProfileAmbiguous {
+            browser: Some("chrome".into()),
+            profile: "w".into(),
+            candidates: vec!["Profile 1".into(), "Profile 2".into()],
+        };
+        assert_eq!(
+            err.to_string(),
+            "profile 'w' is ambiguous in chrome: matches Profile 1, Profile 2"
+        );
+        assert_eq!(err.exit_code(), exit_code::USAGE);
+
+        let err = Error::io_at(
+            "/tmp/missing",
+            std::io::Error::from(std::io::ErrorKind::NotFound),
+        );
+        assert!(err.to_string().contains("at /tmp/missing"));
+        assert!(err.source().is_some());
+    }
+
+    #[test]
+    fn toml_errors_carry_line_and_column() {
+        let text = "[rules]\nname = \"work\"\nbroken =\n";
+        let source = toml::from_str::<toml::Table>(text).unwrap_err();
+        let err = Error::config_toml(Some("config.toml".into()), text, source);
+
+        let Error::Config { line, column, .. } = &err else {
+            panic!("expected a config error");
+        };
+        assert_eq!(*line, Some(3));
+        assert!(column.is_some());
+        assert!(
+            err.to_string()
+                .starts_with("invalid configuration in config.toml at line 3")
+        );
+        assert!(err.source().is_some());
+        assert_eq!(err.exit_code(), exit_code::CONFIG);
+    }
+
+    #[test]
+    fn line_column_counts_from_one() {
+        assert_eq!(line_column("abc", 0), (1, 1));
+        assert_eq!(line_column("ab\ncd", 4), (2, 2));
+        assert_eq!(line_column("é\nx", 1), (1, 1));
+    }
+
+    #[test]
+    fn retryable_and_exit_codes() {
+        let transient = Error::from(std::io::Error::from(std::io::ErrorKind::Interrupted));
+        assert!(transient.is_retryable());
+        assert_eq!(transient.exit_code(), exit_code::TEMP_FAIL);
+
+        let denied = Error::from(std::io::Error::from(std::io::ErrorKind::PermissionDenied));
+        assert!(!denied.is_retryable());
+        assert_eq!(denied.exit_code(), exit_code::NO_PERM);
+
+        let target = Error::InvalidTarget {
+            input: "#".into(),
+            reason: "empty browser".into(),
+        };
+        assert_eq!(target.exit_code(), exit_code::USAGE);
+
+        let launch = Error::LaunchFailed {
+            browser: "chrome".into(),
+            message: "spawn timed out".into(),
+            source: Some(Box::new(std::io::Error::from(std::io::ErrorKind::TimedOut))),
+        };
+        assert!(launch.is_retryable());
+    }
+}
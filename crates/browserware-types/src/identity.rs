@@ -0,0 +1,75 @@
+//! Identities that span browsers.
+//!
+//! An [`Identity`] names a role such as "work" that maps to different
+//! browsers and profiles on different machines. Its candidates are tried in
+//! order, and the first one available on the current machine is used.
+//! Targets refer to an identity as `identity:<name>`.
+
+use serde::{Deserialize, Serialize};
+
+use crate::BrowserTarget;
+
+/// A named identity and the browser profiles that can represent it.
+///
+/// # Example
+///
+/// ```
+/// use browserware_types::{BrowserTarget, Identity};
+///
+/// let work = Identity::new("work")
+///     .with_candidate("chrome:Profile 1".parse().unwrap())
+///     .with_candidate("firefox:work".parse().unwrap())
+///     .with_email("me@corp.example");
+/// assert_eq!(work.target(), "identity:work".parse::<BrowserTarget>().unwrap());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct Identity {
+    /// Identity name, as used in `identity:<name>` targets
+    pub name: String,
+    /// Browser profiles representing the identity, in order of preference
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub candidates: Vec<BrowserTarget>,
+    /// Account emails of the identity; profiles signed in with one of them
+    /// are used when no candidate is available
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub emails: Vec<String>,
+}
+
+impl Identity {
+    /// Create an identity without candidates.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            candidates: Vec::new(),
+            emails: Vec::new(),
+        }
+    }
+
+    /// Add a candidate browser profile.
+    #[must_use]
+    pub fn with_candidate(mut self, candidate: BrowserTarget) -> Self {
+        self.candidates.push(candidate);
+        self
+    }
+
+    /// Add an account email.
+    #[must_use]
+    pub fn with_email(mut self, email: impl Into<String>) -> Self {
+        self.emails.push(email.into());
+        self
+    }
+
+    /// Returns the `identity:<name>` target referring to this identity.
+    #[must_use]
+    pub fn target(&self) -> BrowserTarget {
+        BrowserTarget::new(BrowserTarget::IDENTITY).with_profile(&self.name)
+    }
+
+    /// Returns true if `email` is one of the identity's emails (ignoring case).
+    #[must_use]
+    pub fn has_email(&self, email: &str) -> bool {
+        self.emails.iter().any(|e| e.eq_ignore_ascii_case(email))
+    }
+}
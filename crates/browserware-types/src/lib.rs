@@ -8,11 +8,19 @@
 
 mod browser;
 mod error;
+mod identity;
+mod profile;
+mod target;
 mod variant;
+mod window;
 
-pub use browser::{Browser, BrowserFamily, BrowserId};
-pub use error::{Error, Result};
+pub use browser::{Browser, BrowserFamily, BrowserId, Packaging};
+pub use error::{BoxError, Error, ErrorCode, Result, exit_code};
+pub use identity::Identity;
+pub use profile::{Profile, ProfileId, ProfileSelector};
+pub use target::{BrowserTarget, LaunchMode};
 pub use variant::{BrowserVariant, ChromiumChannel, FirefoxChannel, WebKitChannel};
+pub use window::WindowGeometry;
 
 // Re-export url for convenience
 pub use url::Url;
@@ -0,0 +1,237 @@
+//! Browser profile type definitions.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::{BrowserFamily, Error};
+
+/// Stable identifier for a browser profile.
+///
+/// Derived from the profile's directory name (e.g., `Default`, `Profile 3`,
+/// `x8k2m1qa.default-release`) rather than its display name, so it does not
+/// change when the user renames the profile.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ProfileId(pub String);
+
+impl ProfileId {
+    /// Create a new profile ID.
+    pub fn new(id: impl Into<String>) -> Self {
+        Self(id.into())
+    }
+
+    /// Create a profile ID from a profile directory path.
+    ///
+    /// Uses the final path component. Returns `None` if the path has no
+    /// final component or it is not valid UTF-8.
+    #[must_use]
+    pub fn from_dir(path: &Path) -> Option<Self> {
+        path.file_name()?.to_str().map(Self::new)
+    }
+}
+
+impl std::fmt::Display for ProfileId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Information about a browser profile.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Profile {
+    /// Stable identifier, derived from the profile directory
+    pub id: ProfileId,
+    /// Display name shown by the browser
+    pub name: String,
+    /// Path to the profile directory
+    pub path: PathBuf,
+    /// Engine family of the browser owning this profile
+    pub family: BrowserFamily,
+    /// Whether the browser opens this profile by default
+    pub is_default: bool,
+    /// Signed-in account email (if any)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    /// Avatar identifier as stored by the browser (if any)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avatar: Option<String>,
+    /// Last time the profile was used, in seconds since the Unix epoch (if known)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_used: Option<u64>,
+}
+
+impl Profile {
+    /// Create a new profile with minimal information.
+    pub fn new(
+        id: impl Into<String>,
+        name: impl Into<String>,
+        path: PathBuf,
+        family: BrowserFamily,
+    ) -> Self {
+        Self {
+            id: ProfileId::new(id),
+            name: name.into(),
+            path,
+            family,
+            is_default: false,
+            email: None,
+            avatar: None,
+            last_used: None,
+        }
+    }
+
+    /// Set whether this is the browser's default profile.
+    #[must_use]
+    pub const fn with_default(mut self, is_default: bool) -> Self {
+        self.is_default = is_default;
+        self
+    }
+
+    /// Set the signed-in account email.
+    #[must_use]
+    pub fn with_email(mut self, email: impl Into<String>) -> Self {
+        self.email = Some(email.into());
+        self
+    }
+
+    /// Set the avatar identifier.
+    #[must_use]
+    pub fn with_avatar(mut self, avatar: impl Into<String>) -> Self {
+        self.avatar = Some(avatar.into());
+        self
+    }
+
+    /// Set the last-used time in seconds since the Unix epoch.
+    #[must_use]
+    pub const fn with_last_used(mut self, last_used: u64) -> Self {
+        self.last_used = Some(last_used);
+        self
+    }
+}
+
+/// A profile chosen by the browser's state instead of by name.
+///
+/// Selectors are written with a leading `@` wherever a profile name is
+/// accepted, e.g. `chrome:@last-used`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum ProfileSelector {
+    /// The browser's own default profile
+    Default,
+    /// The most recently used profile, as recorded by the browser
+    LastUsed,
+    /// The profile of the most recently started running instance
+    LastActive,
+}
+
+impl ProfileSelector {
+    /// Returns the canonical string name for this selector, without the `@`.
+    #[must_use]
+    pub const fn canonical_name(self) -> &'static str {
+        match self {
+            Self::Default => "default",
+            Self::LastUsed => "last-used",
+            Self::LastActive => "last-active",
+        }
+    }
+
+    /// Interpret a profile query.
+    ///
+    /// Returns `Ok(None)` for plain profile names.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidTarget`] if the query starts with `@` but is
+    /// not a known selector.
+    pub fn from_query(query: &str) -> Result<Option<Self>, Error> {
+        if query.starts_with('@') {
+            query.parse().map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+impl std::fmt::Display for ProfileSelector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "@{}", self.canonical_name())
+    }
+}
+
+impl std::str::FromStr for ProfileSelector {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix('@').unwrap_or(s) {
+            "default" => Ok(Self::Default),
+            "last-used" => Ok(Self::LastUsed),
+            "last-active" => Ok(Self::LastActive),
+            _ => Err(Error::InvalidTarget {
+                input: s.to_string(),
+                reason: "unknown profile selector (expected @default, @last-used or @last-active)"
+                    .to_string(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profile_id_from_dir() {
+        let id = ProfileId::from_dir(Path::new("/home/user/.config/google-chrome/Profile 3"));
+        assert_eq!(id, Some(ProfileId::new("Profile 3")));
+        assert_eq!(ProfileId::from_dir(Path::new("/")), None);
+    }
+
+    #[test]
+    fn profile_builder_pattern() {
+        let profile = Profile::new(
+            "Profile 1",
+            "Work",
+            "/home/user/.config/google-chrome/Profile 1".into(),
+            BrowserFamily::Chromium,
+        )
+        .with_default(true)
+        .with_email("me@example.com")
+        .with_last_used(1_700_000_000);
+
+        assert_eq!(profile.id.to_string(), "Profile 1");
+        assert_eq!(profile.name, "Work");
+        assert!(profile.is_default);
+        assert_eq!(profile.email.as_deref(), Some("me@example.com"));
+        assert_eq!(profile.avatar, None);
+        assert_eq!(profile.last_used, Some(1_700_000_000));
+    }
+
+    #[test]
+    fn profile_serialization() {
+        let profile = Profile::new(
+            "abc123.default-release",
+            "default-release",
+            "/home/user/.mozilla/firefox/abc123.default-release".into(),
+            BrowserFamily::Firefox,
+        )
+        .with_avatar("book");
+
+        let json = serde_json::to_string(&profile).unwrap();
+        assert!(!json.contains("email"));
+        let parsed: Profile = serde_json::from_str(&json).unwrap();
+        assert_eq!(profile, parsed);
+    }
+
+    #[test]
+    fn profile_selectors() {
+        assert_eq!(
+            ProfileSelector::from_query("@last-used").unwrap(),
+            Some(ProfileSelector::LastUsed)
+        );
+        assert_eq!(ProfileSelector::from_query("Work").unwrap(), None);
+        assert!(ProfileSelector::from_query("@newest").is_err());
+        assert_eq!(ProfileSelector::LastActive.to_string(), "@last-active");
+    }
+}
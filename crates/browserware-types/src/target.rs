@@ -0,0 +1,531 @@
+//! Routing target definitions.
+//!
+//! A [`BrowserTarget`] names where a URL should be opened: a browser, and
+//! optionally its packaging, a profile and a launch mode. Targets have a
+//! compact string form used in rules and on the command line:
+//!
+//! ```text
+//! <browser>[@<packaging>][:<profile>[/<container>]][#<mode>]
+//!
+//! chrome
+//! chrome:Work
+//! chrome:@last-used
+//! firefox@flatpak:dev#private
+//! firefox:default/Banking
+//! ```
+//!
+//! The profile may be a [`ProfileSelector`] such as `@default`. A container
+//! (Firefox Multi-Account Containers) follows the profile after a `/`.
+//! Within profile and container names, `/`, `#`, `:` and `\` are escaped
+//! with a backslash (`chrome:C\#1`, `firefox:a\/b`).
+//!
+//! `identity:<name>[#<mode>]` refers to an [`Identity`](crate::Identity)
+//! instead of a browser.
+
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use crate::{Browser, BrowserId, Error, Packaging, Profile, ProfileSelector};
+
+/// How a browser window should be opened.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "kebab-case")]
+pub enum LaunchMode {
+    /// Open in a new tab of an existing window
+    NewTab,
+    /// Open in a new window
+    NewWindow,
+    /// Open in a private (incognito) window
+    Private,
+    /// Open in a guest session
+    Guest,
+    /// Open as a standalone app window
+    App,
+    /// Open fullscreen in kiosk mode
+    Kiosk,
+}
+
+impl LaunchMode {
+    /// Returns the canonical string name for this mode.
+    #[must_use]
+    pub const fn canonical_name(self) -> &'static str {
+        match self {
+            Self::NewTab => "new-tab",
+            Self::NewWindow => "new-window",
+            Self::Private => "private",
+            Self::Guest => "guest",
+            Self::App => "app",
+            Self::Kiosk => "kiosk",
+        }
+    }
+}
+
+impl std::fmt::Display for LaunchMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.canonical_name())
+    }
+}
+
+impl FromStr for LaunchMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mode = match s.to_lowercase().as_str() {
+            "new-tab" | "tab" => Self::NewTab,
+            "new-window" | "window" => Self::NewWindow,
+            "private" | "incognito" | "inprivate" => Self::Private,
+            "guest" => Self::Guest,
+            "app" => Self::App,
+            "kiosk" => Self::Kiosk,
+            _ => {
+                return Err(Error::InvalidTarget {
+                    input: s.to_string(),
+                    reason: "unknown launch mode (expected new-tab, new-window, private, guest, app or kiosk)"
+                        .to_string(),
+                });
+            }
+        };
+        Ok(mode)
+    }
+}
+
+/// Where to open a URL: a browser with optional packaging, profile and mode.
+///
+/// # Example
+///
+/// ```
+/// use browserware_types::{BrowserTarget, LaunchMode, Packaging};
+///
+/// let target: BrowserTarget = "firefox@flatpak:dev#private".parse().unwrap();
+/// assert_eq!(target.browser.0, "firefox");
+/// assert_eq!(target.packaging, Some(Packaging::Flatpak));
+/// assert_eq!(target.profile.as_deref(), Some("dev"));
+/// assert_eq!(target.mode, Some(LaunchMode::Private));
+/// assert_eq!(target.to_string(), "firefox@flatpak:dev#private");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct BrowserTarget {
+    /// Browser to open
+    pub browser: BrowserId,
+    /// Required packaging, when several installs of the browser exist
+    pub packaging: Option<Packaging>,
+    /// Profile to open, by directory, display name or `@` selector
+    pub profile: Option<String>,
+    /// Container within the profile, by name or ID
+    pub container: Option<String>,
+    /// How to open the window
+    pub mode: Option<LaunchMode>,
+}
+
+impl BrowserTarget {
+    /// Browser ID reserved for `identity:<name>` targets.
+    pub const IDENTITY: &'static str = "identity";
+
+    /// Create a target for a browser with default profile and mode.
+    pub fn new(browser: impl Into<String>) -> Self {
+        Self {
+            browser: BrowserId::new(browser),
+            packaging: None,
+            profile: None,
+            container: None,
+            mode: None,
+        }
+    }
+
+    /// Create a target for `profile` of `browser`, by profile ID.
+    ///
+    /// The packaging is included for sandboxed installs only, so native
+    /// browsers get the short form (e.g. `chrome:Profile 1`, but
+    /// `chrome@flatpak:Profile 1`).
+    #[must_use]
+    pub fn for_profile(browser: &Browser, profile: &Profile) -> Self {
+        let target = Self::new(browser.id.0.clone()).with_profile(profile.id.0.clone());
+        if browser.packaging == Packaging::Native {
+            target
+        } else {
+            target.with_packaging(browser.packaging)
+        }
+    }
+
+    /// Set the required packaging.
+    #[must_use]
+    pub const fn with_packaging(mut self, packaging: Packaging) -> Self {
+        self.packaging = Some(packaging);
+        self
+    }
+
+    /// Set the profile.
+    #[must_use]
+    pub fn with_profile(mut self, profile: impl Into<String>) -> Self {
+        self.profile = Some(profile.into());
+        self
+    }
+
+    /// Set the container within the profile.
+    #[must_use]
+    pub fn with_container(mut self, container: impl Into<String>) -> Self {
+        self.container = Some(container.into());
+        self
+    }
+
+    /// Set the launch mode.
+    #[must_use]
+    pub const fn with_mode(mut self, mode: LaunchMode) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    /// Returns the identity name of an `identity:<name>` target.
+    #[must_use]
+    pub fn identity(&self) -> Option<&str> {
+        (self.browser.0 == Self::IDENTITY)
+            .then_some(self.profile.as_deref())
+            .flatten()
+    }
+
+    /// Returns the profile selector, if the profile is one.
+    #[must_use]
+    pub fn profile_selector(&self) -> Option<ProfileSelector> {
+        let profile = self.profile.as_deref()?;
+        ProfileSelector::from_query(profile).ok().flatten()
+    }
+}
+
+impl std::fmt::Display for BrowserTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.browser)?;
+        if let Some(packaging) = self.packaging {
+            write!(f, "@{packaging}")?;
+        }
+        if let Some(profile) = &self.profile {
+            write!(f, ":{}", escape(profile))?;
+        }
+        if let Some(container) = &self.container {
+            write!(f, "/{}", escape(container))?;
+        }
+        if let Some(mode) = self.mode {
+            write!(f, "#{mode}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for BrowserTarget {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: String| Error::InvalidTarget {
+            input: s.to_string(),
+            reason,
+        };
+        // Report part errors against the whole target string
+        let whole = |err: Error| match err {
+            Error::InvalidTarget { input, reason } => invalid(format!("{reason}: '{input}'")),
+            other => other,
+        };
+
+        let (rest, mode) = match split_unescaped(s, '#', true) {
+            Some((rest, mode)) => (rest, Some(mode.parse::<LaunchMode>().map_err(whole)?)),
+            None => (s, None),
+        };
+
+        let (browser_part, profile_part) = match split_unescaped(rest, ':', false) {
+            Some((browser, profile)) => (browser, Some(profile)),
+            None => (rest, None),
+        };
+        let (profile, container) = match profile_part {
+            Some(part) => {
+                let (profile, container) = match split_unescaped(part, '/', false) {
+                    Some((_, "")) => return Err(invalid("empty container".to_string())),
+                    Some((profile, container)) => (profile, Some(unescape(container))),
+                    None => (part, None),
+                };
+                if profile.is_empty() {
+                    return Err(invalid("empty profile".to_string()));
+                }
+                let profile = unescape(profile);
+                ProfileSelector::from_query(&profile).map_err(whole)?;
+                (Some(profile), container)
+            }
+            None => (None, None),
+        };
+
+        let (browser, packaging) = match browser_part.split_once('@') {
+            Some((browser, packaging)) => (
+                browser,
+                Some(packaging.parse::<Packaging>().map_err(whole)?),
+            ),
+            None => (browser_part, None),
+        };
+
+        if browser.is_empty() {
+            return Err(invalid("empty browser".to_string()));
+        }
+        if browser == Self::IDENTITY
+            && (profile.is_none() || packaging.is_some() || container.is_some())
+        {
+            return Err(invalid("expected identity:<name>".to_string()));
+        }
+
+        Ok(Self {
+            browser: BrowserId::new(browser),
+            packaging,
+            profile,
+            container,
+            mode,
+        })
+    }
+}
+
+/// Characters escaped in profile and container names.
+const ESCAPED: [char; 4] = ['\\', '/', '#', ':'];
+
+/// Escape the target delimiters in a profile or container name.
+fn escape(name: &str) -> String {
+    let mut escaped = String::with_capacity(name.len());
+    for c in name.chars() {
+        if ESCAPED.contains(&c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Undo [`escape`]. A backslash before any other character is kept.
+fn unescape(name: &str) -> String {
+    let mut unescaped = String::with_capacity(name.len());
+    let mut chars = name.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some(next)) if ESCAPED.contains(&next) => {
+                unescaped.push(next);
+                chars.next();
+            }
+            _ => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+/// Split `s` around the first (or, with `last`, the last) `delimiter` that
+/// is not escaped with a backslash.
+fn split_unescaped(s: &str, delimiter: char, last: bool) -> Option<(&str, &str)> {
+    let mut found = None;
+    let mut escaped = false;
+    for (i, c) in s.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == delimiter {
+            found = Some(i);
+            if !last {
+                break;
+            }
+        }
+    }
+    found.map(|i| (&s[..i], &s[i + delimiter.len_utf8()..]))
+}
+
+#[cfg(feature = "schema")]
+impl schemars::JsonSchema for BrowserTarget {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "BrowserTarget".into()
+    }
+
+    fn json_schema(_generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({
+            "type": "string",
+            "description": "Browser to open, as `browser[@packaging][:profile[/container]][#mode]`",
+            "pattern": "^[^@:#]+(@(native|flatpak|snap))?(:([^#/\\\\]|\\\\.)+(/([^#/\\\\]|\\\\.)+)?)?(#(new-tab|tab|new-window|window|private|incognito|inprivate|guest|app|kiosk))?$",
+            "examples": ["chrome", "chrome:Work", "chrome:@last-used", "firefox@flatpak:dev#private", "firefox:default/Banking"]
+        })
+    }
+}
+
+impl TryFrom<String> for BrowserTarget {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<BrowserTarget> for String {
+    fn from(target: BrowserTarget) -> Self {
+        target.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_browser_only() {
+        let target: BrowserTarget = "chrome".parse().unwrap();
+        assert_eq!(target, BrowserTarget::new("chrome"));
+    }
+
+    #[test]
+    fn parse_browser_and_profile() {
+        let target: BrowserTarget = "chrome:Work".parse().unwrap();
+        assert_eq!(target, BrowserTarget::new("chrome").with_profile("Work"));
+    }
+
+    #[test]
+    fn parse_profile_selector() {
+        let target: BrowserTarget = "chrome@flatpak:@last-active".parse().unwrap();
+        assert_eq!(target.packaging, Some(Packaging::Flatpak));
+        assert_eq!(target.profile_selector(), Some(ProfileSelector::LastActive));
+        assert_eq!(target.to_string(), "chrome@flatpak:@last-active");
+        assert_eq!(
+            "chrome:Work"
+                .parse::<BrowserTarget>()
+                .unwrap()
+                .profile_selector(),
+            None
+        );
+
+        let err = "chrome:@newest".parse::<BrowserTarget>().unwrap_err();
+        assert!(
+            err.to_string().contains("unknown profile selector"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn parse_full_target() {
+        let target: BrowserTarget = "firefox@flatpak:dev#private".parse().unwrap();
+        assert_eq!(
+            target,
+            BrowserTarget::new("firefox")
+                .with_packaging(Packaging::Flatpak)
+                .with_profile("dev")
+                .with_mode(LaunchMode::Private)
+        );
+    }
+
+    #[test]
+    fn parse_profile_with_spaces_and_colons() {
+        let target: BrowserTarget = "chrome:Profile 3".parse().unwrap();
+        assert_eq!(target.profile.as_deref(), Some("Profile 3"));
+
+        let target: BrowserTarget = "chrome:Client: Acme#new-window".parse().unwrap();
+        assert_eq!(target.profile.as_deref(), Some("Client: Acme"));
+        assert_eq!(target.mode, Some(LaunchMode::NewWindow));
+    }
+
+    #[test]
+    fn parse_container() {
+        let target: BrowserTarget = "firefox:@default/Banking#new-tab".parse().unwrap();
+        assert_eq!(target.profile_selector(), Some(ProfileSelector::Default));
+        assert_eq!(target.container.as_deref(), Some("Banking"));
+        assert_eq!(target.mode, Some(LaunchMode::NewTab));
+
+        assert!("firefox:/Banking".parse::<BrowserTarget>().is_err());
+        assert!("firefox:default/".parse::<BrowserTarget>().is_err());
+    }
+
+    #[test]
+    fn parse_identity() {
+        let target: BrowserTarget = "identity:work#private".parse().unwrap();
+        assert_eq!(target.identity(), Some("work"));
+        assert_eq!(target.mode, Some(LaunchMode::Private));
+        assert_eq!(
+            "chrome:work".parse::<BrowserTarget>().unwrap().identity(),
+            None
+        );
+
+        for input in ["identity", "identity@snap:work", "identity:work/Banking"] {
+            assert!(input.parse::<BrowserTarget>().is_err(), "{input}");
+        }
+    }
+
+    #[test]
+    fn parse_mode_without_profile() {
+        let target: BrowserTarget = "brave#incognito".parse().unwrap();
+        assert_eq!(target.profile, None);
+        assert_eq!(target.mode, Some(LaunchMode::Private));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!("".parse::<BrowserTarget>().is_err());
+        assert!(":Work".parse::<BrowserTarget>().is_err());
+        assert!("chrome:".parse::<BrowserTarget>().is_err());
+        assert!("chrome@appimage".parse::<BrowserTarget>().is_err());
+        assert!("chrome#sideways".parse::<BrowserTarget>().is_err());
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for input in [
+            "chrome",
+            "chrome:Work",
+            "firefox@snap#kiosk",
+            "edge@native:Default#app",
+            "firefox:default/Work",
+        ] {
+            let target: BrowserTarget = input.parse().unwrap();
+            assert_eq!(target.to_string(), input);
+        }
+    }
+
+    #[test]
+    fn targets_profiles_with_sandboxed_packaging_only() {
+        let profile = Profile::new(
+            "Profile 1",
+            "Work",
+            "/home/u/.config/chromium/Profile 1".into(),
+            crate::BrowserFamily::Chromium,
+        );
+        let chromium = Browser::new("chromium", "Chromium", "/usr/bin/chromium".into());
+        assert_eq!(
+            BrowserTarget::for_profile(&chromium, &profile).to_string(),
+            "chromium:Profile 1"
+        );
+        let flatpak = chromium.with_packaging(Packaging::Flatpak);
+        assert_eq!(
+            BrowserTarget::for_profile(&flatpak, &profile).to_string(),
+            "chromium@flatpak:Profile 1"
+        );
+    }
+
+    #[test]
+    fn escapes_delimiters_in_names() {
+        for (profile, container, text) in [
+            ("C#1", None, r"chrome:C\#1#new-tab"),
+            ("a/b", Some("x:y"), r"firefox:a\/b/x\:y#new-tab"),
+            (r"back\slash", None, r"chrome:back\\slash#new-tab"),
+        ] {
+            let mut target = BrowserTarget::new(text.split(':').next().unwrap())
+                .with_profile(profile)
+                .with_mode(LaunchMode::NewTab);
+            if let Some(container) = container {
+                target = target.with_container(container);
+            }
+            assert_eq!(target.to_string(), text);
+            assert_eq!(text.parse::<BrowserTarget>().unwrap(), target);
+        }
+        // Unescaped colons still parse, and other backslashes are kept
+        let target: BrowserTarget = r"chrome:Client: C:\Users".parse().unwrap();
+        assert_eq!(target.profile.as_deref(), Some(r"Client: C:\Users"));
+    }
+
+    #[test]
+    fn serializes_as_string() {
+        let target = BrowserTarget::new("chrome")
+            .with_profile("Work")
+            .with_mode(LaunchMode::NewWindow);
+        let json = serde_json::to_string(&target).unwrap();
+        assert_eq!(json, r#""chrome:Work#new-window""#);
+
+        let parsed: BrowserTarget = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, target);
+        assert!(serde_json::from_str::<BrowserTarget>(r#""chrome#nope""#).is_err());
+    }
+}
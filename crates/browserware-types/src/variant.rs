@@ -10,6 +10,7 @@ use crate::BrowserFamily;
 
 /// Release channels for Chromium-based browsers (Chrome, Edge, Brave, Arc, etc.)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "lowercase")]
 pub enum ChromiumChannel {
     /// Stable release channel
@@ -44,6 +45,7 @@ impl std::fmt::Display for ChromiumChannel {
 
 /// Release channels for Firefox-based browsers (Firefox, `LibreWolf`, Waterfox, etc.)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "lowercase")]
 pub enum FirefoxChannel {
     /// Stable release channel
@@ -81,6 +83,7 @@ impl std::fmt::Display for FirefoxChannel {
 
 /// Release channels for WebKit-based browsers (Safari, GNOME Web, etc.)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "kebab-case")]
 pub enum WebKitChannel {
     /// Stable release channel
@@ -112,6 +115,7 @@ impl std::fmt::Display for WebKitChannel {
 /// This enum provides a type-safe way to represent browser variants,
 /// encoding both the engine family and the release channel in a single type.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(tag = "type", content = "value")]
 pub enum BrowserVariant {
     /// Chromium-based browser with a specific release channel
@@ -0,0 +1,179 @@
+//! Window geometry.
+//!
+//! A [`WindowGeometry`] is a window size and/or position, written in the
+//! X11 style used by rules and on the command line:
+//!
+//! ```text
+//! [<width>x<height>][+<x>+<y>]
+//!
+//! 1280x800
+//! 1280x800+0+0
+//! +100+50
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use crate::Error;
+
+/// Size and position of a browser window, in pixels.
+///
+/// # Example
+///
+/// ```
+/// use browserware_types::WindowGeometry;
+///
+/// let geometry: WindowGeometry = "1280x800+0+0".parse().unwrap();
+/// assert_eq!(geometry.size, Some((1280, 800)));
+/// assert_eq!(geometry.position, Some((0, 0)));
+/// assert_eq!(geometry.to_string(), "1280x800+0+0");
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct WindowGeometry {
+    /// Width and height
+    pub size: Option<(u32, u32)>,
+    /// Position of the top-left corner
+    pub position: Option<(i32, i32)>,
+}
+
+impl WindowGeometry {
+    /// Create a geometry that leaves size and position to the browser.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            size: None,
+            position: None,
+        }
+    }
+
+    /// Set the window size.
+    #[must_use]
+    pub const fn with_size(mut self, width: u32, height: u32) -> Self {
+        self.size = Some((width, height));
+        self
+    }
+
+    /// Set the window position.
+    #[must_use]
+    pub const fn with_position(mut self, x: i32, y: i32) -> Self {
+        self.position = Some((x, y));
+        self
+    }
+}
+
+impl std::fmt::Display for WindowGeometry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some((width, height)) = self.size {
+            write!(f, "{width}x{height}")?;
+        }
+        if let Some((x, y)) = self.position {
+            write!(f, "+{x}+{y}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for WindowGeometry {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::InvalidArgument {
+            argument: "window".to_string(),
+            message: format!("'{s}' is not a geometry (expected WIDTHxHEIGHT, +X+Y or both)"),
+        };
+
+        let (size, position) = s
+            .split_once('+')
+            .map_or((s, None), |(size, position)| (size, Some(position)));
+        let size = if size.is_empty() {
+            None
+        } else {
+            let (width, height) = size.split_once('x').ok_or_else(invalid)?;
+            let width = width.parse::<u32>().map_err(|_| invalid())?;
+            let height = height.parse::<u32>().map_err(|_| invalid())?;
+            if width == 0 || height == 0 {
+                return Err(invalid());
+            }
+            Some((width, height))
+        };
+        let position = match position {
+            Some(position) => {
+                let (x, y) = position.split_once('+').ok_or_else(invalid)?;
+                let x = x.parse::<i32>().map_err(|_| invalid())?;
+                let y = y.parse::<i32>().map_err(|_| invalid())?;
+                Some((x, y))
+            }
+            None => None,
+        };
+        if size.is_none() && position.is_none() {
+            return Err(invalid());
+        }
+        Ok(Self { size, position })
+    }
+}
+
+#[cfg(feature = "schema")]
+impl schemars::JsonSchema for WindowGeometry {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "WindowGeometry".into()
+    }
+
+    fn json_schema(_generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({
+            "type": "string",
+            "description": "Window size and/or position, as `[WIDTHxHEIGHT][+X+Y]`",
+            "pattern": "^([1-9][0-9]*x[1-9][0-9]*)?(\\+-?[0-9]+\\+-?[0-9]+)?$",
+            "examples": ["1280x800", "1280x800+0+0", "+100+50"]
+        })
+    }
+}
+
+impl TryFrom<String> for WindowGeometry {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<WindowGeometry> for String {
+    fn from(geometry: WindowGeometry) -> Self {
+        geometry.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_size_and_position() {
+        let parse = |s: &str| s.parse::<WindowGeometry>().unwrap();
+        assert_eq!(parse("800x600"), WindowGeometry::new().with_size(800, 600));
+        assert_eq!(
+            parse("+-10+20"),
+            WindowGeometry::new().with_position(-10, 20)
+        );
+        assert_eq!(parse("1280x800+0+0").to_string(), "1280x800+0+0");
+    }
+
+    #[test]
+    fn rejects_malformed_geometry() {
+        for input in [
+            "",
+            "800",
+            "0x600",
+            "800x",
+            "+10",
+            "800x600+1",
+            "axb",
+            "800x600+1+2+3",
+        ] {
+            assert!(
+                input.parse::<WindowGeometry>().is_err(),
+                "{input:?} should be rejected"
+            );
+        }
+    }
+}